@@ -1,6 +1,10 @@
+use std::path::PathBuf;
+
 use bevy::prelude::*;
 use clap::Parser;
 
+use crate::game::{clock::TimeControl, stockfish::OpponentSide};
+
 pub struct CliPlugin;
 
 impl Plugin for CliPlugin {
@@ -14,4 +18,41 @@ impl Plugin for CliPlugin {
 pub struct CliArgs {
     /// Load into game with the provided FEN
     pub fen: Option<String>,
+
+    /// Load into game with the first game in the provided PGN file
+    #[arg(long, value_name = "FILE", conflicts_with = "fen")]
+    pub pgn: Option<PathBuf>,
+
+    /// Save the game as PGN to the provided file when it ends or on Ctrl+S
+    #[arg(long, value_name = "FILE")]
+    pub save_pgn: Option<PathBuf>,
+
+    /// Play with clocks, e.g. 5+3 (minutes + increment seconds), 3d2 (minutes, delay seconds), or
+    /// 40/90+30 (minutes for a number of moves)
+    #[arg(long, value_name = "TC")]
+    pub time_control: Option<TimeControl>,
+
+    /// Let Stockfish play the given side
+    #[arg(long, value_enum, value_name = "SIDE")]
+    pub opponent: Option<OpponentSide>,
+
+    /// Stockfish's skill level, from 0 to 20
+    #[arg(long, value_parser = clap::value_parser!(u8).range(0..=20))]
+    pub skill: Option<u8>,
+
+    /// Limit Stockfish's strength to the given Elo, from 1350 to 2850
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1350..=2850))]
+    pub elo: Option<u16>,
+
+    /// Let Stockfish search for the given number of milliseconds per move
+    #[arg(long, value_name = "MS", group = "search_limit")]
+    pub movetime: Option<u32>,
+
+    /// Let Stockfish search to the given depth per move
+    #[arg(long, group = "search_limit")]
+    pub depth: Option<u32>,
+
+    /// Let Stockfish search the given number of nodes per move
+    #[arg(long, group = "search_limit")]
+    pub nodes: Option<u64>,
 }
@@ -0,0 +1,491 @@
+use std::str::FromStr;
+
+use bevy::prelude::*;
+use chess::{Board, ChessMove};
+
+use crate::{
+    debug_name, debug_name_f,
+    game::{
+        board::{
+            BoardState, PromotingPiece, SelectionEvent, SelectionState, SpawnPieces, Square,
+            SyncCaptureState, ViewedPly,
+        },
+        consts::{
+            COLOR_PANEL, COLOR_PANEL_HIGHLIGHT, COLOR_PANEL_ROW_ODD, COLOR_PANEL_TEXT, FONT_PATH,
+        },
+        mouse::Dragging,
+        pgn::to_san,
+        stockfish::{EngineAnalysis, EngineInfo, Score},
+        ui::MoveListContainer,
+    },
+    utils::{NoopExts, ReparentInTag, SortIndex},
+};
+
+use super::{move_list::spawn_move_list, ui::spawn_ui};
+
+#[derive(Debug)]
+pub struct AnalysisPlugin;
+
+impl Plugin for AnalysisPlugin {
+    fn build(&self, app: &mut App) {
+        app.noop()
+            // Resources
+            .init_resource::<AnalysisMode>()
+            // Observers
+            .add_observer(preview_line)
+            // Systems
+            .add_systems(Startup, spawn_analysis_panel.after(spawn_ui).after(spawn_move_list))
+            .add_systems(
+                Update,
+                (
+                    (sync_analysis_header, sync_analysis_rows)
+                        .run_if(resource_changed::<AnalysisMode>),
+                    update_analysis_rows.run_if(
+                        resource_changed::<AnalysisMode>
+                            .or(resource_exists_and_changed::<EngineAnalysis>),
+                    ),
+                )
+                    .chain(),
+            )
+            .noop();
+    }
+}
+
+const ANALYSIS_FONT_SIZE: f32 = 12.0;
+
+const ANALYSIS_ROW_HEIGHT: f32 = 24.0;
+
+const ANALYSIS_SCORE_WIDTH: f32 = 44.0;
+
+const ANALYSIS_DEPTH_WIDTH: f32 = 28.0;
+
+/// The most moves of a line that are shown, which is more than fits in the panel.
+const MAX_PV_PLIES: usize = 12;
+
+/// Whether Stockfish reports multiple lines for the live position, and how many.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Resource)]
+pub struct AnalysisMode {
+    pub enabled: bool,
+    pub lines: u8,
+}
+
+impl Default for AnalysisMode {
+    fn default() -> Self {
+        Self { enabled: false, lines: 3 }
+    }
+}
+
+impl AnalysisMode {
+    pub const MAX_LINES: u8 = 5;
+
+    /// The number of lines Stockfish should search, i.e. its `MultiPV` option.
+    pub fn multipv(self) -> u8 {
+        if self.enabled { self.lines } else { 1 }
+    }
+}
+
+#[derive(Component)]
+struct AnalysisToggle;
+
+#[derive(Component)]
+struct AnalysisLineCount;
+
+#[derive(Component)]
+struct AnalysisLines;
+
+/// A row of the analysis panel, holding the rank of the line it shows, starting at 0.
+#[derive(Clone, Copy, Component, Debug)]
+struct AnalysisLine(usize);
+
+#[derive(Component)]
+struct AnalysisLineScore;
+
+#[derive(Component)]
+struct AnalysisLineDepth;
+
+#[derive(Component)]
+struct AnalysisLineMoves;
+
+/// Show the position at the end of the line of the given rank, read-only, as if browsing past
+/// positions.
+#[derive(Event, Debug)]
+pub struct PreviewLine(pub usize);
+
+/// Format a score from white's point of view, e.g. `+0.35` or `#-3`.
+pub fn format_score(score: Score) -> String {
+    match score {
+        Score::Centipawns(cp) => format!("{:+.2}", cp as f32 / 100.0),
+        Score::Mate(moves) => format!("#{moves}"),
+    }
+}
+
+/// Format the legal prefix of `pv` in SAN with move numbers, e.g. `1. e4 e5 2. Nf3` or `3... Nc6`.
+pub fn format_pv(board: &Board, full_move_count: u16, pv: &[ChessMove]) -> String {
+    let mut board = *board;
+    let mut number = full_move_count.max(1);
+    let mut text = String::new();
+    for (i, &r#move) in pv.iter().take(MAX_PV_PLIES).enumerate() {
+        if !board.legal(r#move) {
+            break;
+        }
+        let is_white = board.side_to_move() == chess::Color::White;
+        if !text.is_empty() {
+            text.push(' ');
+        }
+        if is_white {
+            text.push_str(&format!("{number}. "));
+        } else if i == 0 {
+            text.push_str(&format!("{number}... "));
+        }
+        text.push_str(&to_san(&board, r#move));
+        if !is_white {
+            number += 1;
+        }
+        board = board.make_move_new(r#move);
+    }
+    text
+}
+
+/// The position being analysed and its fullmove count.
+fn analysed_position(analysis: &EngineAnalysis) -> Option<(Board, u16)> {
+    let fen = analysis.fen.as_deref()?;
+    let board = Board::from_str(fen).ok()?;
+    let full_move_count = fen.split_whitespace().nth(5).and_then(|n| n.parse().ok()).unwrap_or(1);
+    Some((board, full_move_count))
+}
+
+fn spawn_analysis_panel(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load(FONT_PATH);
+    let text_font = TextFont { font, font_size: ANALYSIS_FONT_SIZE, ..default() };
+
+    let header_button = |label: &str| {
+        (
+            Button,
+            Node {
+                height: Val::Percent(100.0),
+                padding: UiRect::horizontal(Val::Px(6.0)),
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            children![(
+                Text::new(label),
+                text_font.clone(),
+                TextColor(Color::WHITE),
+                Pickable::IGNORE,
+            )],
+        )
+    };
+
+    let panel = commands
+        .spawn((
+            debug_name!("Analysis Panel"),
+            SortIndex(2),
+            Node {
+                width: Val::Percent(100.0),
+                flex_shrink: 0.0,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            BackgroundColor(COLOR_PANEL),
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    debug_name!("Analysis Header"),
+                    Node {
+                        height: Val::Px(ANALYSIS_ROW_HEIGHT),
+                        justify_content: JustifyContent::SpaceBetween,
+                        ..default()
+                    },
+                    BackgroundColor(COLOR_PANEL_HIGHLIGHT),
+                ))
+                .with_children(|header| {
+                    header.spawn((AnalysisToggle, header_button(""))).observe(toggle_analysis);
+                    header
+                        .spawn((AnalysisLineCount, header_button("")))
+                        .observe(cycle_analysis_lines);
+                });
+            parent.spawn((
+                AnalysisLines,
+                debug_name!("Analysis Lines"),
+                Node { flex_direction: FlexDirection::Column, ..default() },
+            ));
+        })
+        .id();
+
+    commands.reparent_in_tag::<MoveListContainer>([panel]);
+}
+
+fn toggle_analysis(_trigger: Trigger<Pointer<Click>>, mut mode: ResMut<AnalysisMode>) {
+    mode.enabled = !mode.enabled;
+}
+
+fn cycle_analysis_lines(_trigger: Trigger<Pointer<Click>>, mut mode: ResMut<AnalysisMode>) {
+    mode.lines = mode.lines % AnalysisMode::MAX_LINES + 1;
+}
+
+fn sync_analysis_header(
+    mode: Res<AnalysisMode>,
+    q_toggle: Query<&Children, With<AnalysisToggle>>,
+    q_line_count: Query<&Children, With<AnalysisLineCount>>,
+    mut q_text: Query<&mut Text>,
+) {
+    let labels = [
+        (q_toggle.single(), format!("Analysis: {}", if mode.enabled { "on" } else { "off" })),
+        (q_line_count.single(), format!("Lines: {}", mode.lines)),
+    ];
+    for (children, label) in labels {
+        if let Ok(children) = children
+            && let Ok(mut text) = q_text.get_mut(children[0])
+        {
+            text.0 = label;
+        }
+    }
+}
+
+/// Spawn a row for each line, and hide the rows while analysis is off.
+fn sync_analysis_rows(
+    mut commands: Commands,
+    mode: Res<AnalysisMode>,
+    asset_server: Res<AssetServer>,
+    mut q_lines: Query<(Entity, &mut Node), With<AnalysisLines>>,
+) {
+    let Ok((lines_entity, mut node)) = q_lines.single_mut() else { return };
+    node.display = if mode.enabled { Display::Flex } else { Display::None };
+
+    let font = asset_server.load(FONT_PATH);
+    let text_font = TextFont { font, font_size: ANALYSIS_FONT_SIZE, ..default() };
+    let text_layout = TextLayout::new(JustifyText::Left, LineBreak::NoWrap);
+
+    commands.entity(lines_entity).despawn_related::<Children>();
+    for rank in 0..mode.lines as usize {
+        let row_color = if rank % 2 == 1 { COLOR_PANEL_ROW_ODD } else { COLOR_PANEL };
+        commands
+            .spawn((
+                AnalysisLine(rank),
+                debug_name_f!("Analysis Line ({})", rank + 1),
+                Button,
+                Node {
+                    height: Val::Px(ANALYSIS_ROW_HEIGHT),
+                    padding: UiRect::horizontal(Val::Px(4.0)),
+                    align_items: AlignItems::Center,
+                    overflow: Overflow::clip(),
+                    ..default()
+                },
+                BackgroundColor(row_color),
+                ChildOf(lines_entity),
+                children![
+                    (
+                        AnalysisLineScore,
+                        Node {
+                            width: Val::Px(ANALYSIS_SCORE_WIDTH),
+                            flex_shrink: 0.0,
+                            ..default()
+                        },
+                        Text::default(),
+                        text_font.clone(),
+                        TextColor(Color::WHITE),
+                        text_layout,
+                        Pickable::IGNORE,
+                    ),
+                    (
+                        AnalysisLineDepth,
+                        Node {
+                            width: Val::Px(ANALYSIS_DEPTH_WIDTH),
+                            flex_shrink: 0.0,
+                            ..default()
+                        },
+                        Text::default(),
+                        text_font.clone(),
+                        TextColor(COLOR_PANEL_TEXT.with_alpha(0.6)),
+                        text_layout,
+                        Pickable::IGNORE,
+                    ),
+                    (
+                        AnalysisLineMoves,
+                        Text::default(),
+                        text_font.clone(),
+                        TextColor(COLOR_PANEL_TEXT),
+                        text_layout,
+                        Pickable::IGNORE,
+                    ),
+                ],
+            ))
+            .observe(preview_line_on_click);
+    }
+}
+
+/// Show the score, depth, and moves of each line.
+fn update_analysis_rows(
+    analysis: Option<Res<EngineAnalysis>>,
+    q_rows: Query<(&AnalysisLine, &Children)>,
+    mut q_score: Query<&mut Text, (With<AnalysisLineScore>, Without<AnalysisLineDepth>)>,
+    mut q_depth: Query<&mut Text, (With<AnalysisLineDepth>, Without<AnalysisLineMoves>)>,
+    mut q_moves: Query<&mut Text, (With<AnalysisLineMoves>, Without<AnalysisLineScore>)>,
+) {
+    let Some(analysis) = analysis else { return };
+    let position = analysed_position(&analysis);
+    let side_to_move = analysis.side_to_move();
+
+    for (&AnalysisLine(rank), children) in &q_rows {
+        let line = analysis.lines.get(rank).filter(|line| !line.pv.is_empty());
+        let (score, depth, moves) = match (line, position, side_to_move) {
+            (
+                Some(EngineInfo { score: Some(score), depth, pv, .. }),
+                Some((board, full_move_count)),
+                Some(side_to_move),
+            ) => (
+                format_score(score.score.for_white(side_to_move)),
+                depth.map(|depth| depth.to_string()).unwrap_or_default(),
+                format_pv(&board, full_move_count, pv),
+            ),
+            _ => default(),
+        };
+
+        for &child in children {
+            if let Ok(mut text) = q_score.get_mut(child) {
+                text.0.clone_from(&score);
+            } else if let Ok(mut text) = q_depth.get_mut(child) {
+                text.0.clone_from(&depth);
+            } else if let Ok(mut text) = q_moves.get_mut(child) {
+                text.0.clone_from(&moves);
+            }
+        }
+    }
+}
+
+fn preview_line_on_click(
+    trigger: Trigger<Pointer<Click>>,
+    mut commands: Commands,
+    q_line: Query<&AnalysisLine>,
+) {
+    if let Ok(&AnalysisLine(rank)) = q_line.get(trigger.target()) {
+        commands.trigger(PreviewLine(rank));
+    }
+}
+
+fn preview_line(
+    trigger: Trigger<PreviewLine>,
+    mut commands: Commands,
+    board_state: Res<BoardState>,
+    analysis: Option<Res<EngineAnalysis>>,
+    mut viewed_ply: ResMut<ViewedPly>,
+    mut selection_state: ResMut<SelectionState>,
+    q_promo: Query<(), With<PromotingPiece>>,
+    q_dragging: Query<(), With<Dragging>>,
+) {
+    if !q_promo.is_empty() || !q_dragging.is_empty() {
+        return;
+    }
+
+    // Lines of a position that is no longer live can't be played from the live board
+    let board = *board_state.board();
+    let Some(analysis) = analysis else { return };
+    if analysis.fen.as_deref() != Some(board_state.fen().as_str()) {
+        return;
+    }
+    let Some(line) = analysis.lines.get(trigger.event().0) else { return };
+
+    let mut pv = Vec::with_capacity(line.pv.len());
+    let mut end = board;
+    for &r#move in &line.pv {
+        if !end.legal(r#move) {
+            break;
+        }
+        pv.push(r#move);
+        end = end.make_move_new(r#move);
+    }
+    let Some(last) = pv.last() else { return };
+    trace!(rank = trigger.event().0, plies = pv.len(), "Preview line");
+
+    // Past the live ply, so that browsing to any ply shows the real position again
+    *viewed_ply = ViewedPly(Some(board_state.history().entries().len()));
+
+    *selection_state = SelectionState::Unselected;
+    commands.trigger(SelectionEvent::Unselect);
+    commands.trigger(SelectionEvent::UpdateLastMove(
+        Square::new(last.get_source()),
+        Square::new(last.get_dest()),
+    ));
+
+    commands.queue(SpawnPieces::new(end));
+    let ply = board_state.history().entries().len();
+    commands.queue(SyncCaptureState::at_ply(&board_state, ply).then(pv));
+}
+
+#[cfg(test)]
+mod tests {
+    use chess::Square as ChessSquare;
+
+    use crate::game::{
+        board::{PieceColor, PieceMeta, PieceType},
+        core::build_app,
+        stockfish::{EngineScore, ScoreBound},
+    };
+
+    use super::*;
+
+    fn line(rank: u32, cp: i32, pv: &[(ChessSquare, ChessSquare)]) -> EngineInfo {
+        EngineInfo {
+            depth: Some(20),
+            multipv: Some(rank),
+            score: Some(EngineScore { score: Score::Centipawns(cp), bound: ScoreBound::Exact }),
+            pv: pv.iter().map(|&(from, to)| ChessMove::new(from, to, None)).collect(),
+            ..default()
+        }
+    }
+
+    #[test]
+    fn formats_lines_in_san() {
+        let board = Board::default();
+        let pv = [
+            ChessMove::new(ChessSquare::E2, ChessSquare::E4, None),
+            ChessMove::new(ChessSquare::E7, ChessSquare::E5, None),
+            ChessMove::new(ChessSquare::G1, ChessSquare::F3, None),
+        ];
+        assert_eq!(format_pv(&board, 1, &pv), "1. e4 e5 2. Nf3");
+
+        let board = board.make_move_new(pv[0]);
+        assert_eq!(format_pv(&board, 1, &pv[1..]), "1... e5 2. Nf3");
+
+        // Stop at the first move that isn't legal
+        assert_eq!(format_pv(&board, 1, &pv[2..]), "");
+
+        assert_eq!(format_score(Score::Centipawns(35)), "+0.35");
+        assert_eq!(format_score(Score::Centipawns(-120)), "-1.20");
+        assert_eq!(format_score(Score::Mate(-3)), "#-3");
+    }
+
+    #[test]
+    fn shows_and_previews_lines() {
+        let mut app = build_app(|app: &mut App| {
+            app.init_resource::<EngineAnalysis>();
+        });
+        app.world_mut().resource_mut::<AnalysisMode>().enabled = true;
+        let fen = app.world().resource::<BoardState>().fen();
+        let mut analysis = app.world_mut().resource_mut::<EngineAnalysis>();
+        analysis.reset(fen);
+        analysis.update(&line(1, 30, &[(ChessSquare::E2, ChessSquare::E4)]));
+        analysis.update(&line(
+            2,
+            -15,
+            &[(ChessSquare::G1, ChessSquare::F3), (ChessSquare::D7, ChessSquare::D5)],
+        ));
+        app.update();
+
+        let mut q_moves = app.world_mut().query_filtered::<&Text, With<AnalysisLineMoves>>();
+        let mut moves: Vec<_> = q_moves.iter(app.world()).map(|text| text.0.clone()).collect();
+        moves.sort();
+        assert_eq!(moves, ["", "1. Nf3 d5", "1. e4"]);
+
+        app.world_mut().trigger(PreviewLine(1));
+        app.update();
+
+        assert_eq!(*app.world().resource::<ViewedPly>(), ViewedPly(Some(0)));
+        let board_state = app.world().resource::<BoardState>();
+        assert_eq!(*board_state.board(), Board::default());
+        let piece = board_state.piece(Square::D5);
+        let meta = app.world().get::<PieceMeta>(piece).copied();
+        assert_eq!(meta, Some(PieceMeta::new(PieceColor::BLACK, PieceType::PAWN)));
+    }
+}
@@ -1,7 +1,35 @@
-use bevy::prelude::*;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::{platform::collections::HashSet, prelude::*};
 use chess::BitBoard;
 
-use crate::game::board::BoardState;
+use crate::{
+    game::{LoadGame, board::BoardState, settings::Settings},
+    utils::{NoopExts, assets_dir},
+};
+
+pub struct GameAudioPlugin;
+
+impl Plugin for GameAudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.noop()
+            // Resources
+            .init_resource::<SoundPack>()
+            .init_resource::<SoundPacks>()
+            // Observers
+            .add_observer(play_game_start_on_load_game)
+            .noop();
+    }
+}
+
+/// The directory in the assets folder with a directory for each sound pack.
+const SOUND_PACKS_DIR: &str = "audio";
+
+/// The sound pack used when none is set, which has a file for every sound.
+pub const DEFAULT_SOUND_PACK: &str = "default";
 
 /// A `MoveCheck` variant is intentionally absent as the check audio should never be explicitly
 /// played. The variant for the the move type should be used; the board will be examined and if
@@ -14,22 +42,172 @@ pub enum PlayGameAudio {
     MoveOpponent,
     MoveSelf,
     Promote,
+    GameStart,
+    GameWin,
+    GameLose,
+    GameDraw,
+    Illegal,
+    LowTime,
+    Premove,
+}
+
+impl PlayGameAudio {
+    fn file_name(&self, checkers: BitBoard) -> &'static str {
+        match self {
+            Self::Capture | Self::Castle | Self::MoveOpponent | Self::MoveSelf | Self::Promote
+                if checkers != BitBoard::new(0) =>
+            {
+                "move-check.flac"
+            }
+            Self::Capture => "capture.flac",
+            Self::Castle => "castle.flac",
+            Self::MoveOpponent => "move-opponent.flac",
+            Self::MoveSelf => "move-self.flac",
+            Self::Promote => "promote.flac",
+            Self::GameStart => "game-start.flac",
+            Self::GameWin => "game-win.flac",
+            Self::GameLose => "game-lose.flac",
+            Self::GameDraw => "game-draw.flac",
+            Self::Illegal => "illegal.flac",
+            Self::LowTime => "low-time.flac",
+            Self::Premove => "premove.flac",
+        }
+    }
 }
 
 impl Command for PlayGameAudio {
     fn apply(self, world: &mut World) {
         trace!(action = ?self, "Play audio");
 
-        let checkers = *world.resource::<BoardState>().board().checkers();
+        if world.get_resource::<Settings>().is_some_and(|settings| settings.sound.muted) {
+            return;
+        }
+        let Some(pack) = world.get_resource::<SoundPack>() else { return };
 
-        let source = world.resource::<AssetServer>().load::<AudioSource>(match self {
-            _ if checkers != BitBoard::new(0) => "audio/move-check.flac",
-            Self::Capture => "audio/capture.flac",
-            Self::Castle => "audio/castle.flac",
-            Self::MoveOpponent => "audio/move-opponent.flac",
-            Self::MoveSelf => "audio/move-self.flac",
-            Self::Promote => "audio/promote.flac",
-        });
+        let checkers = *world.resource::<BoardState>().board().checkers();
+        let path = pack.path(self.file_name(checkers));
+        let source = world.resource::<AssetServer>().load::<AudioSource>(path);
         world.spawn((AudioPlayer(source), PlaybackSettings::DESPAWN));
     }
 }
+
+/// The sounds in `assets/audio/<name>/`.
+///
+/// A pack doesn't need to have every sound, those it is missing are played from the default pack.
+#[derive(Clone, Debug, PartialEq, Resource)]
+pub struct SoundPack {
+    /// The name of the sound pack, i.e. its directory.
+    pub name: String,
+    files: HashSet<String>,
+}
+
+impl Default for SoundPack {
+    fn default() -> Self {
+        Self::load(DEFAULT_SOUND_PACK)
+    }
+}
+
+impl SoundPack {
+    /// List the sounds of the sound pack named `name`.
+    pub fn load(name: &str) -> Self {
+        let dir = sound_packs_dir().join(name);
+        let files = match fs::read_dir(&dir) {
+            Ok(entries) => entries
+                .filter_map(Result::ok)
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .collect(),
+            Err(err) => {
+                warn!(name, %err, "Failed to read sound pack, using the default sound pack");
+                HashSet::new()
+            }
+        };
+        Self { name: name.to_string(), files }
+    }
+
+    /// The asset path of the sound `file_name` in this pack, or in the default pack if this pack
+    /// doesn't have it.
+    pub fn path(&self, file_name: &str) -> String {
+        let name = if self.files.contains(file_name) { &self.name } else { DEFAULT_SOUND_PACK };
+        format!("{SOUND_PACKS_DIR}/{name}/{file_name}")
+    }
+}
+
+fn sound_packs_dir() -> PathBuf {
+    assets_dir().join(SOUND_PACKS_DIR)
+}
+
+/// The names of the sound packs that can be chosen, i.e. the directories in the sound packs
+/// directory.
+#[derive(Clone, Debug, Deref, Resource)]
+pub struct SoundPacks(Vec<String>);
+
+impl Default for SoundPacks {
+    fn default() -> Self {
+        Self::discover(&sound_packs_dir())
+    }
+}
+
+impl SoundPacks {
+    fn discover(dir: &Path) -> Self {
+        let mut names = match fs::read_dir(dir) {
+            Ok(entries) => entries
+                .filter_map(Result::ok)
+                .filter(|entry| entry.path().is_dir())
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .collect(),
+            Err(err) => {
+                debug!(dir = %dir.display(), %err, "No sound packs directory");
+                Vec::new()
+            }
+        };
+        names.sort();
+        names.retain(|name| name != DEFAULT_SOUND_PACK);
+        names.insert(0, DEFAULT_SOUND_PACK.to_string());
+        debug!(?names, "Discover sound packs");
+        Self(names)
+    }
+}
+
+fn play_game_start_on_load_game(_trigger: Trigger<LoadGame>, mut commands: Commands) {
+    commands.queue(PlayGameAudio::GameStart);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn falls_back_to_the_default_sound_pack() {
+        let packs = SoundPacks::default();
+        assert_eq!(packs[0], DEFAULT_SOUND_PACK);
+
+        let default = SoundPack::load(DEFAULT_SOUND_PACK);
+        let checkers = BitBoard::new(0);
+        for cue in [
+            PlayGameAudio::Capture,
+            PlayGameAudio::Castle,
+            PlayGameAudio::MoveOpponent,
+            PlayGameAudio::MoveSelf,
+            PlayGameAudio::Promote,
+            PlayGameAudio::GameStart,
+            PlayGameAudio::GameWin,
+            PlayGameAudio::GameLose,
+            PlayGameAudio::GameDraw,
+            PlayGameAudio::Illegal,
+            PlayGameAudio::LowTime,
+            PlayGameAudio::Premove,
+        ] {
+            let file_name = cue.file_name(checkers);
+            assert!(default.files.contains(file_name), "missing {file_name}");
+        }
+        assert_eq!(PlayGameAudio::Castle.file_name(BitBoard::new(1)), "move-check.flac");
+        assert_eq!(PlayGameAudio::GameWin.file_name(BitBoard::new(1)), "game-win.flac");
+
+        let pack = SoundPack {
+            name: "retro".to_string(),
+            files: ["capture.flac".to_string()].into_iter().collect(),
+        };
+        assert_eq!(pack.path("capture.flac"), "audio/retro/capture.flac");
+        assert_eq!(pack.path("castle.flac"), "audio/default/castle.flac");
+    }
+}
@@ -0,0 +1,344 @@
+use std::collections::BTreeMap;
+
+use bevy::{platform::collections::HashMap, prelude::*};
+
+use crate::{
+    debug_name_f,
+    game::{LoadGame, menu::MenuState},
+    utils::NoopExts,
+};
+
+use super::{
+    Arrow, ArrowLayer, BoardState, DrawArrowsSystem, HistoryPlugin, MovePieceCompleted, Square,
+    SquareMark, ViewedPly,
+};
+
+#[derive(Debug)]
+pub struct AnnotationPlugin;
+
+impl Plugin for AnnotationPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<HistoryPlugin>() {
+            panic!("Attempted to add plugin without required dependency: {HistoryPlugin:?}");
+        }
+
+        app.noop()
+            // Resources
+            .init_resource::<PlyAnnotations>()
+            // Events
+            .add_event::<MouseAnnotationEvent>()
+            // Observers
+            .add_observer(clear_annotations_on_load_game)
+            .add_observer(clear_stale_annotations_on_move)
+            // Systems
+            .add_systems(
+                Update,
+                (
+                    handle_mouse_annotation_events.run_if(in_state(MenuState::Game)),
+                    sync_annotation_shapes.run_if(
+                        resource_changed::<PlyAnnotations>
+                            .or(resource_changed::<ViewedPly>)
+                            .or(resource_changed::<BoardState>),
+                    ),
+                )
+                    .chain()
+                    .before(DrawArrowsSystem),
+            )
+            .noop();
+    }
+}
+
+/// The colors of annotations, as in lichess.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnnotationColor {
+    Green,
+    Red,
+    Blue,
+    Yellow,
+}
+
+impl AnnotationColor {
+    /// The color drawn with the right mouse button and the given modifier keys held.
+    pub fn from_modifiers(shift: bool, alt: bool) -> Self {
+        match (shift, alt) {
+            (false, false) => Self::Green,
+            (true, false) => Self::Red,
+            (false, true) => Self::Blue,
+            (true, true) => Self::Yellow,
+        }
+    }
+
+    pub const fn color(self) -> Color {
+        match self {
+            // `#15781b`
+            Self::Green => {
+                Color::srgba(0x15 as f32 / 255.0, 0x78 as f32 / 255.0, 0x1b as f32 / 255.0, 0.8)
+            }
+            // `#882020`
+            Self::Red => {
+                Color::srgba(0x88 as f32 / 255.0, 0x20 as f32 / 255.0, 0x20 as f32 / 255.0, 0.8)
+            }
+            // `#003088`
+            Self::Blue => {
+                Color::srgba(0x00 as f32 / 255.0, 0x30 as f32 / 255.0, 0x88 as f32 / 255.0, 0.8)
+            }
+            // `#e68f00`
+            Self::Yellow => {
+                Color::srgba(0xe6 as f32 / 255.0, 0x8f as f32 / 255.0, 0x00 as f32 / 255.0, 0.8)
+            }
+        }
+    }
+
+    /// The color's code in `[%csl]` and `[%cal]` PGN commands.
+    pub fn pgn_code(self) -> char {
+        match self {
+            Self::Green => 'G',
+            Self::Red => 'R',
+            Self::Blue => 'B',
+            Self::Yellow => 'Y',
+        }
+    }
+}
+
+/// The squares and arrows drawn on a position.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Annotations {
+    pub squares: Vec<(Square, AnnotationColor)>,
+    /// The arrows, by their (from, to) squares.
+    pub arrows: Vec<((Square, Square), AnnotationColor)>,
+}
+
+impl Annotations {
+    pub fn is_empty(&self) -> bool {
+        self.squares.is_empty() && self.arrows.is_empty()
+    }
+
+    /// Mark `from` if it is the same square as `to`, otherwise draw an arrow between them.
+    ///
+    /// Drawing a shape that is already drawn in the same color erases it, and in another color
+    /// recolors it.
+    pub fn toggle(&mut self, from: Square, to: Square, color: AnnotationColor) {
+        fn toggle<T: PartialEq>(
+            shapes: &mut Vec<(T, AnnotationColor)>,
+            shape: T,
+            color: AnnotationColor,
+        ) {
+            match shapes.iter().position(|(s, _)| *s == shape) {
+                Some(i) if shapes[i].1 == color => {
+                    shapes.remove(i);
+                }
+                Some(i) => shapes[i].1 = color,
+                None => shapes.push((shape, color)),
+            }
+        }
+
+        if from == to {
+            toggle(&mut self.squares, from, color);
+        } else {
+            toggle(&mut self.arrows, (from, to), color);
+        }
+    }
+
+    /// The annotations as a PGN comment, e.g. `{[%csl Gd4][%cal Re2e4,Bg1f3]}`.
+    pub fn to_pgn_comment(&self) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let mut comment = String::from("{");
+        if !self.squares.is_empty() {
+            let squares: Vec<_> = self
+                .squares
+                .iter()
+                .map(|(sq, color)| format!("{}{sq}", color.pgn_code()))
+                .collect();
+            comment.push_str(&format!("[%csl {}]", squares.join(",")));
+        }
+        if !self.arrows.is_empty() {
+            let arrows: Vec<_> = self
+                .arrows
+                .iter()
+                .map(|((from, to), color)| format!("{}{from}{to}", color.pgn_code()))
+                .collect();
+            comment.push_str(&format!("[%cal {}]", arrows.join(",")));
+        }
+        comment.push('}');
+        Some(comment)
+    }
+}
+
+/// The annotations of each position of the game, by ply.
+#[derive(Debug, Default, Resource)]
+pub struct PlyAnnotations(HashMap<usize, Annotations>);
+
+impl PlyAnnotations {
+    pub fn get(&self, ply: usize) -> Option<&Annotations> {
+        self.0.get(&ply)
+    }
+
+    /// The PGN comments of the annotated positions, ordered by ply.
+    pub fn pgn_comments(&self) -> BTreeMap<usize, String> {
+        self.0
+            .iter()
+            .filter_map(|(&ply, annotations)| Some((ply, annotations.to_pgn_comment()?)))
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Event)]
+pub enum MouseAnnotationEvent {
+    /// A square was right-clicked, or the mouse was right-dragged from one square to another.
+    Draw { from: Square, to: Square, color: AnnotationColor },
+    /// The board was left-clicked.
+    Clear,
+}
+
+#[derive(Component)]
+struct AnnotationShape;
+
+/// The ply of the position shown on the board.
+fn shown_ply(board_state: &BoardState, viewed_ply: &ViewedPly) -> usize {
+    viewed_ply.0.unwrap_or(board_state.history().entries().len())
+}
+
+fn handle_mouse_annotation_events(
+    mut event_reader: EventReader<MouseAnnotationEvent>,
+    board_state: Res<BoardState>,
+    viewed_ply: Res<ViewedPly>,
+    mut annotations: ResMut<PlyAnnotations>,
+) {
+    let ply = shown_ply(&board_state, &viewed_ply);
+    for event in event_reader.read() {
+        match *event {
+            MouseAnnotationEvent::Draw { from, to, color } => {
+                trace!(ply, %from, %to, ?color, "Annotate");
+                let ply_annotations = annotations.0.entry(ply).or_default();
+                ply_annotations.toggle(from, to, color);
+                if ply_annotations.is_empty() {
+                    annotations.0.remove(&ply);
+                }
+            }
+            MouseAnnotationEvent::Clear => {
+                if annotations.0.contains_key(&ply) {
+                    annotations.0.remove(&ply);
+                }
+            }
+        }
+    }
+}
+
+fn clear_annotations_on_load_game(
+    _trigger: Trigger<LoadGame>,
+    mut annotations: ResMut<PlyAnnotations>,
+) {
+    annotations.0.clear();
+}
+
+/// Forget the annotations of positions that were taken back and replaced by a new move.
+fn clear_stale_annotations_on_move(
+    _trigger: Trigger<MovePieceCompleted>,
+    board_state: Res<BoardState>,
+    mut annotations: ResMut<PlyAnnotations>,
+) {
+    let live_ply = board_state.history().entries().len();
+    if annotations.0.keys().any(|&ply| ply >= live_ply) {
+        annotations.0.retain(|&ply, _| ply < live_ply);
+    }
+}
+
+fn sync_annotation_shapes(
+    mut commands: Commands,
+    annotations: Res<PlyAnnotations>,
+    board_state: Res<BoardState>,
+    viewed_ply: Res<ViewedPly>,
+    q_layer: Query<Entity, With<ArrowLayer>>,
+    q_shapes: Query<Entity, With<AnnotationShape>>,
+) {
+    for entity in &q_shapes {
+        commands.entity(entity).despawn();
+    }
+
+    let Ok(layer) = q_layer.single() else { return };
+    let Some(annotations) = annotations.get(shown_ply(&board_state, &viewed_ply)) else { return };
+
+    for &(square, color) in &annotations.squares {
+        commands.spawn((
+            AnnotationShape,
+            debug_name_f!("Square Annotation ({square})"),
+            SquareMark { square, color: color.color() },
+            ChildOf(layer),
+        ));
+    }
+    for &((from, to), color) in &annotations.arrows {
+        commands.spawn((
+            AnnotationShape,
+            debug_name_f!("Arrow Annotation ({from} => {to})"),
+            Arrow::new(from, to, color.color()),
+            ChildOf(layer),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{
+        board::ViewPly,
+        core::{build_app, make_move},
+    };
+
+    use super::*;
+
+    fn draw(app: &mut App, from: Square, to: Square, color: AnnotationColor) {
+        app.world_mut().send_event(MouseAnnotationEvent::Draw { from, to, color });
+        app.update();
+    }
+
+    fn shape_count(app: &mut App) -> usize {
+        app.world_mut().query_filtered::<(), With<AnnotationShape>>().iter(app.world()).count()
+    }
+
+    #[test]
+    fn toggles_shapes_and_writes_pgn_comment() {
+        let mut annotations = Annotations::default();
+        annotations.toggle(Square::D4, Square::D4, AnnotationColor::Green);
+        annotations.toggle(Square::E2, Square::E4, AnnotationColor::Red);
+        annotations.toggle(Square::G1, Square::F3, AnnotationColor::Blue);
+        assert_eq!(annotations.to_pgn_comment().unwrap(), "{[%csl Gd4][%cal Re2e4,Bg1f3]}");
+
+        // Same color erases, another color recolors
+        annotations.toggle(Square::D4, Square::D4, AnnotationColor::Green);
+        annotations.toggle(Square::E2, Square::E4, AnnotationColor::Yellow);
+        annotations.toggle(Square::G1, Square::F3, AnnotationColor::Blue);
+        assert_eq!(annotations.to_pgn_comment().unwrap(), "{[%cal Ye2e4]}");
+
+        annotations.toggle(Square::E2, Square::E4, AnnotationColor::Yellow);
+        assert_eq!(annotations.to_pgn_comment(), None);
+    }
+
+    #[test]
+    fn stores_annotations_per_ply() {
+        let mut app = build_app(AnnotationPlugin);
+        make_move(&mut app, Square::E2, Square::E4);
+        draw(&mut app, Square::E7, Square::E5, AnnotationColor::Green);
+        draw(&mut app, Square::D4, Square::D4, AnnotationColor::Red);
+        assert_eq!(shape_count(&mut app), 2);
+
+        app.world_mut().trigger(ViewPly(0));
+        app.update();
+        assert_eq!(shape_count(&mut app), 0);
+
+        app.world_mut().trigger(ViewPly(1));
+        app.update();
+        assert_eq!(shape_count(&mut app), 2);
+        let comments = app.world().resource::<PlyAnnotations>().pgn_comments();
+        assert_eq!(
+            comments.into_iter().collect::<Vec<_>>(),
+            [(1, "{[%csl Rd4][%cal Ge7e5]}".into())]
+        );
+
+        app.world_mut().send_event(MouseAnnotationEvent::Clear);
+        app.update();
+        assert_eq!(shape_count(&mut app), 0);
+        assert!(app.world().resource::<PlyAnnotations>().get(1).is_none());
+    }
+}
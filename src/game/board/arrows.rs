@@ -0,0 +1,162 @@
+use bevy::prelude::*;
+
+use crate::{debug_name, game::consts::Z_ARROW};
+
+use super::{BoardOrientation, Square, UiBoard};
+
+/// The width of an arrow's shaft, in squares.
+const ARROW_SHAFT_WIDTH: f32 = 0.2;
+
+/// The length of an arrow's head, in squares.
+const ARROW_HEAD_LENGTH: f32 = 0.45;
+
+/// The width of an arrow's head, in squares.
+const ARROW_HEAD_WIDTH: f32 = 0.5;
+
+/// The width of a square mark's ring, in squares.
+const SQUARE_MARK_WIDTH: f32 = 0.08;
+
+const SQUARE_PERCENT: f32 = 100.0 / 8.0;
+
+/// The systems that lay out arrows and square marks. Spawn and despawn them before this set.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, SystemSet)]
+pub struct DrawArrowsSystem;
+
+#[derive(Component)]
+pub struct ArrowLayer;
+
+/// An arrow drawn over the board from the center of one square to the center of another.
+///
+/// Spawn it as a child of the [`ArrowLayer`]; its shaft and head are laid out from the squares and
+/// are sized relative to the board, so they follow the board when it is resized or flipped.
+#[derive(Clone, Copy, Component, Debug, PartialEq)]
+#[require(Node = arrow_node(), Pickable = Pickable::IGNORE)]
+pub struct Arrow {
+    pub from: Square,
+    pub to: Square,
+    pub color: Color,
+}
+
+impl Arrow {
+    pub fn new(from: Square, to: Square, color: Color) -> Self {
+        Self { from, to, color }
+    }
+}
+
+/// A ring drawn over a square.
+///
+/// Spawn it as a child of the [`ArrowLayer`], like an [`Arrow`].
+#[derive(Clone, Copy, Component, Debug, PartialEq)]
+#[require(Node, Pickable = Pickable::IGNORE)]
+pub struct SquareMark {
+    pub square: Square,
+    pub color: Color,
+}
+
+fn arrow_node() -> Node {
+    Node {
+        position_type: PositionType::Absolute,
+        top: Val::Px(0.0),
+        left: Val::Px(0.0),
+        width: Val::Percent(100.0),
+        height: Val::Percent(100.0),
+        ..default()
+    }
+}
+
+pub fn spawn_arrow_layer(mut commands: Commands, q_board: Query<Entity, With<UiBoard>>) {
+    let board = q_board.single().unwrap();
+    commands.spawn((
+        ArrowLayer,
+        debug_name!("Arrow Layer"),
+        arrow_node(),
+        Pickable::IGNORE,
+        GlobalZIndex(Z_ARROW),
+        ChildOf(board),
+    ));
+}
+
+/// Lay out the shaft and head of arrows that were added or changed, or all of them when the board
+/// is flipped.
+pub fn draw_arrows(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    orientation: Res<BoardOrientation>,
+    q_arrows: Query<(Entity, Ref<Arrow>)>,
+) {
+    let head_texture = asset_server.load("images/arrows/head.png");
+
+    for (entity, arrow) in &q_arrows {
+        if !arrow.is_changed() && !orientation.is_changed() {
+            continue;
+        }
+
+        let from = arrow.from.board_position(**orientation);
+        let to = arrow.to.board_position(**orientation);
+        let delta = to - from;
+        let Some(direction) = delta.try_normalize() else { continue };
+        let rotation = Transform::from_rotation(Quat::from_rotation_z(direction.to_angle()));
+
+        // The shaft ends where the head starts, so that they don't overlap when translucent
+        let head_length = ARROW_HEAD_LENGTH * SQUARE_PERCENT;
+        let shaft_length = (delta.length() - head_length).max(0.0);
+        let shaft_width = ARROW_SHAFT_WIDTH * SQUARE_PERCENT;
+        let shaft_center = from + direction * shaft_length / 2.0;
+        let head_width = ARROW_HEAD_WIDTH * SQUARE_PERCENT;
+        let head_center = to - direction * head_length / 2.0;
+
+        // Nodes are rotated around their center
+        let centered_node = |center: Vec2, length: f32, width: f32| Node {
+            position_type: PositionType::Absolute,
+            left: Val::Percent(center.x - length / 2.0),
+            top: Val::Percent(center.y - width / 2.0),
+            width: Val::Percent(length),
+            height: Val::Percent(width),
+            ..default()
+        };
+
+        commands.entity(entity).despawn_related::<Children>().with_children(|parent| {
+            parent.spawn((
+                centered_node(shaft_center, shaft_length, shaft_width),
+                rotation,
+                BackgroundColor(arrow.color),
+                Pickable::IGNORE,
+            ));
+            parent.spawn((
+                centered_node(head_center, head_length, head_width),
+                rotation,
+                ImageNode::new(head_texture.clone()).with_color(arrow.color),
+                Pickable::IGNORE,
+            ));
+        });
+    }
+}
+
+/// Lay out square marks that were added or changed, or all of them when the board is flipped.
+pub fn draw_square_marks(
+    mut commands: Commands,
+    orientation: Res<BoardOrientation>,
+    q_marks: Query<(Entity, Ref<SquareMark>)>,
+) {
+    for (entity, mark) in &q_marks {
+        if !mark.is_changed() && !orientation.is_changed() {
+            continue;
+        }
+
+        let center = mark.square.board_position(**orientation);
+        // Percentages of borders are relative to the width of the layer, i.e. of the board
+        commands.entity(entity).insert((
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(center.x - SQUARE_PERCENT / 2.0),
+                top: Val::Percent(center.y - SQUARE_PERCENT / 2.0),
+                width: Val::Percent(SQUARE_PERCENT),
+                height: Val::Percent(SQUARE_PERCENT),
+                border: UiRect::all(Val::Percent(SQUARE_MARK_WIDTH * SQUARE_PERCENT)),
+                ..default()
+            },
+            BorderColor(mark.color),
+            BorderRadius::MAX,
+        ));
+    }
+}
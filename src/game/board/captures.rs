@@ -1,17 +1,18 @@
 use std::ops::{Index, IndexMut};
 
 use bevy::prelude::*;
+use chess::ChessMove;
 
 use crate::{
     game::{
         LoadGame,
         board::{PieceColor, PieceType},
-        panels::MaterialAdvantageLabel,
+        panels::{CapturedPieceImage, MaterialAdvantageLabel},
     },
     utils::NoopExts,
 };
 
-use super::{BoardPlugin, PieceMeta};
+use super::{BoardPlugin, BoardState, HistoryEntry, MovePieceCompleted, PieceMeta, Theme};
 
 #[derive(Debug)]
 pub struct CapturePlugin;
@@ -24,10 +25,13 @@ impl Plugin for CapturePlugin {
 
         app.noop()
             // Resources
-            .init_resource::<CaptureState>()
+            .insert_resource(CaptureState::new())
             // Observers
             .add_observer(load_capture_state)
             .add_observer(captures)
+            .add_observer(sync_capture_state_on_move)
+            // Systems
+            .add_systems(Update, apply_capture_theme.run_if(resource_changed::<Theme>))
             .noop();
     }
 }
@@ -38,100 +42,24 @@ const CAPTURABLE_PIECES: [PieceType; chess::NUM_PIECES - 1] =
 #[derive(Deref, DerefMut, Resource)]
 pub struct CaptureState([ColorCaptures; 2]);
 
-impl FromWorld for CaptureState {
-    fn from_world(world: &mut World) -> Self {
-        let asset_server = world.resource::<AssetServer>();
-        let mut state = CaptureState::new();
-
-        state[PieceColor::BLACK][PieceType::PAWN].image_handles.extend([
-            default(),
-            asset_server.load("images/captures/white-pawns-1.png"),
-            asset_server.load("images/captures/white-pawns-2.png"),
-            asset_server.load("images/captures/white-pawns-3.png"),
-            asset_server.load("images/captures/white-pawns-4.png"),
-            asset_server.load("images/captures/white-pawns-5.png"),
-            asset_server.load("images/captures/white-pawns-6.png"),
-            asset_server.load("images/captures/white-pawns-7.png"),
-            asset_server.load("images/captures/white-pawns-8.png"),
-        ]);
-        state[PieceColor::BLACK][PieceType::BISHOP].image_handles.extend([
-            default(),
-            asset_server.load("images/captures/white-bishops-1.png"),
-            asset_server.load("images/captures/white-bishops-2.png"),
-        ]);
-        state[PieceColor::BLACK][PieceType::KNIGHT].image_handles.extend([
-            default(),
-            asset_server.load("images/captures/white-knights-1.png"),
-            asset_server.load("images/captures/white-knights-2.png"),
-        ]);
-        state[PieceColor::BLACK][PieceType::ROOK].image_handles.extend([
-            default(),
-            asset_server.load("images/captures/white-rooks-1.png"),
-            asset_server.load("images/captures/white-rooks-2.png"),
-        ]);
-        state[PieceColor::BLACK][PieceType::QUEEN]
-            .image_handles
-            .extend([default(), asset_server.load("images/captures/white-queen.png")]);
-
-        state[PieceColor::WHITE][PieceType::PAWN].image_handles.extend([
-            default(),
-            asset_server.load("images/captures/black-pawns-1.png"),
-            asset_server.load("images/captures/black-pawns-2.png"),
-            asset_server.load("images/captures/black-pawns-3.png"),
-            asset_server.load("images/captures/black-pawns-4.png"),
-            asset_server.load("images/captures/black-pawns-5.png"),
-            asset_server.load("images/captures/black-pawns-6.png"),
-            asset_server.load("images/captures/black-pawns-7.png"),
-            asset_server.load("images/captures/black-pawns-8.png"),
-        ]);
-        state[PieceColor::WHITE][PieceType::BISHOP].image_handles.extend([
-            default(),
-            asset_server.load("images/captures/black-bishops-1.png"),
-            asset_server.load("images/captures/black-bishops-2.png"),
-        ]);
-        state[PieceColor::WHITE][PieceType::KNIGHT].image_handles.extend([
-            default(),
-            asset_server.load("images/captures/black-knights-1.png"),
-            asset_server.load("images/captures/black-knights-2.png"),
-        ]);
-        state[PieceColor::WHITE][PieceType::ROOK].image_handles.extend([
-            default(),
-            asset_server.load("images/captures/black-rooks-1.png"),
-            asset_server.load("images/captures/black-rooks-2.png"),
-        ]);
-        state[PieceColor::WHITE][PieceType::QUEEN]
-            .image_handles
-            .extend([default(), asset_server.load("images/captures/black-queen.png")]);
-
-        state
-    }
-}
-
 impl CaptureState {
     pub fn new() -> Self {
         Self(default())
     }
 
+    /// The side with more material on the board, and by how much.
     pub fn get_advantage(&self) -> Option<(PieceColor, u8)> {
-        match (self[PieceColor::BLACK].score, self[PieceColor::WHITE].score) {
-            (black_score, white_score) if black_score > white_score => {
-                Some((PieceColor::BLACK, black_score - white_score))
+        match (self[PieceColor::BLACK].material, self[PieceColor::WHITE].material) {
+            (black_material, white_material) if black_material > white_material => {
+                Some((PieceColor::BLACK, black_material - white_material))
             }
-            (black_score, white_score) if white_score > black_score => {
-                Some((PieceColor::WHITE, white_score - black_score))
+            (black_material, white_material) if white_material > black_material => {
+                Some((PieceColor::WHITE, white_material - black_material))
             }
             _ => None,
         }
     }
 
-    pub fn patch(&mut self, update: CapStateUpdate) -> &CapState {
-        let color_caps = &mut self[update.color];
-        color_caps[update.typ].patch(update.diff);
-        color_caps.score =
-            CAPTURABLE_PIECES.into_iter().map(|typ| typ.value() * color_caps[typ].count).sum();
-        &color_caps[update.typ]
-    }
-
     #[cfg(debug_assertions)]
     #[allow(dead_code)]
     pub fn log_counts(&self) {
@@ -170,7 +98,8 @@ impl IndexMut<PieceColor> for CaptureState {
 pub struct ColorCaptures {
     #[deref]
     piece_captures: [CapState; 5],
-    score: u8,
+    /// The value of the side's pieces on the board.
+    material: u8,
 }
 
 impl Index<usize> for ColorCaptures {
@@ -201,160 +130,251 @@ impl IndexMut<PieceType> for ColorCaptures {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Copy)]
 pub struct CapState {
-    pub image_handles: Vec<Handle<Image>>,
-    pub image_entity: Entity,
+    /// The row of images of the captured pieces of this type.
+    pub strip_entity: Entity,
     pub count: u8,
 }
 
 impl Default for CapState {
     fn default() -> Self {
-        Self { image_handles: Vec::new(), image_entity: Entity::PLACEHOLDER, count: 0 }
+        Self { strip_entity: Entity::PLACEHOLDER, count: 0 }
     }
 }
 
-impl CapState {
-    /// Return the image handle for the current capture count.
-    fn handle(&self) -> Handle<Image> {
-        self.image_handles[self.count as usize].clone()
-    }
-
-    /// Apply the capture state diff. Return the image handle for the new
-    /// capture count.
-    fn patch(&mut self, diff: CapStateDiff) {
-        match diff {
-            CapStateDiff::Increment => {
-                self.count = (self.count + 1) % self.image_handles.len() as u8
-            }
-            CapStateDiff::Set(count) => self.count = count,
+/// Rebuild the captured pieces strips with the pieces of the current theme.
+fn apply_capture_theme(mut commands: Commands) {
+    for color in [PieceColor::WHITE, PieceColor::BLACK] {
+        for typ in CAPTURABLE_PIECES {
+            commands.queue(SyncCaptureStrip::new(color, typ));
         }
     }
 }
 
 fn load_capture_state(trigger: Trigger<LoadGame>, mut commands: Commands) {
-    let board = &trigger.event().board;
-    let kings_bb = board.pieces(chess::Piece::King);
-
-    for color in chess::ALL_COLORS {
-        let opponent_pieces_bb = *board.color_combined(!color) & !kings_bb;
-        let color = PieceColor(color);
+    let LoadGame { board, moves, .. } = trigger.event();
+    commands.queue(SyncCaptureState::new(*board, moves.clone()));
+}
 
-        for typ in CAPTURABLE_PIECES {
-            let opponent_pieces_of_type = opponent_pieces_bb & *board.pieces(typ.into());
-            let captured_count = typ.num_pieces() - opponent_pieces_of_type.popcnt() as u8;
-            let diff = CapStateDiff::Set(captured_count);
-            commands.queue(CapStateUpdate::new(color, typ, diff));
-        }
-    }
+fn sync_capture_state_on_move(
+    _trigger: Trigger<MovePieceCompleted>,
+    mut commands: Commands,
+    board_state: Res<BoardState>,
+) {
+    let ply = board_state.history().entries().len();
+    commands.queue(SyncCaptureState::at_ply(&board_state, ply));
 }
 
-#[derive(Clone, Copy, Debug)]
-pub struct CapStateUpdate {
-    color: PieceColor,
-    typ: PieceType,
-    diff: CapStateDiff,
+/// Set the capture counts of both players to the pieces captured in a game, and their material to
+/// what is left on the board at its end.
+pub struct SyncCaptureState {
+    /// The position the game started from, whose missing pieces count as captured.
+    start: chess::Board,
+    moves: Vec<ChessMove>,
 }
 
-impl CapStateUpdate {
-    pub fn new(color: PieceColor, typ: PieceType, diff: CapStateDiff) -> Self {
-        Self { color, typ, diff }
+impl SyncCaptureState {
+    pub fn new(start: chess::Board, moves: Vec<ChessMove>) -> Self {
+        Self { start, moves }
     }
-}
 
-#[derive(Clone, Copy, Debug)]
-pub enum CapStateDiff {
-    Increment,
-    Set(u8),
+    /// The game on the board up to `ply`.
+    pub fn at_ply(board_state: &BoardState, ply: usize) -> Self {
+        let entries = board_state.history().entries();
+        let start = entries.first().map_or(*board_state.board(), |entry| entry.board);
+        Self::new(start, entries[..ply].iter().map(|entry| entry.r#move).collect())
+    }
+
+    /// Continue the game with `moves`.
+    pub fn then(mut self, moves: impl IntoIterator<Item = ChessMove>) -> Self {
+        self.moves.extend(moves);
+        self
+    }
 }
 
-impl Command for CapStateUpdate {
+impl Command for SyncCaptureState {
     fn apply(self, world: &mut World) {
-        trace!(side = ?self.color, typ = ?self.typ, action = ?self.diff, "Update capture state");
+        // Captured pieces are counted as they were when captured, e.g. a promoted queen as a queen
+        let mut captured = missing_pieces(&self.start);
+        let mut board = self.start;
+        for r#move in self.moves {
+            captured.extend(HistoryEntry::new(&board, r#move, 0, 0).captured);
+            board = board.make_move_new(r#move);
+        }
 
         let mut state = world.resource_mut::<CaptureState>();
-        let cap = state.patch(self);
-        let count = cap.count;
-        let image_entity = cap.image_entity;
-        let handle = cap.handle();
-
-        if let Ok(mut image_entity) = world.get_entity_mut(image_entity) {
-            if let Some(mut node) = image_entity.get_mut::<Node>() {
-                node.display = match count {
-                    0 => Display::None,
-                    _ => Display::Flex,
-                };
+        for color in [PieceColor::WHITE, PieceColor::BLACK] {
+            let caps = &mut state[color];
+            caps.material = material(&board, color);
+            for typ in CAPTURABLE_PIECES {
+                // The pieces captured by a side are of the other side's color
+                let piece = PieceMeta::new(!color, typ);
+                caps[typ].count = captured.iter().filter(|&&p| p == piece).count() as u8;
             }
-            if let Some(mut image) = image_entity.get_mut::<ImageNode>() {
-                image.image = handle;
+        }
+
+        for color in [PieceColor::WHITE, PieceColor::BLACK] {
+            for typ in CAPTURABLE_PIECES {
+                SyncCaptureStrip::new(color, typ).apply(world);
             }
         }
+        sync_material_advantage_labels(world);
+    }
+}
+
+/// The pieces missing from `board` compared to the starting position, where the pieces beyond
+/// those of the starting position are assumed to be promoted pawns.
+fn missing_pieces(board: &chess::Board) -> Vec<PieceMeta> {
+    let mut missing = Vec::new();
+    for color in [PieceColor::WHITE, PieceColor::BLACK] {
+        let count = |typ: PieceType| {
+            (*board.color_combined(color.0) & *board.pieces(typ.into())).popcnt() as u8
+        };
+        let promoted: u8 = CAPTURABLE_PIECES
+            .into_iter()
+            .filter(|&typ| typ != PieceType::PAWN)
+            .map(|typ| count(typ).saturating_sub(typ.num_pieces()))
+            .sum();
+        for typ in CAPTURABLE_PIECES {
+            let on_board = if typ == PieceType::PAWN { count(typ) + promoted } else { count(typ) };
+            let n = typ.num_pieces().saturating_sub(on_board);
+            missing.extend(std::iter::repeat_n(PieceMeta::new(color, typ), n as usize));
+        }
+    }
+    missing
+}
+
+/// The value of the pieces of `color` on `board`.
+fn material(board: &chess::Board, color: PieceColor) -> u8 {
+    CAPTURABLE_PIECES
+        .into_iter()
+        .map(|typ| {
+            let pieces = *board.color_combined(color.0) & *board.pieces(typ.into());
+            typ.value() * pieces.popcnt() as u8
+        })
+        .sum()
+}
 
-        if let Some((color_with_adv, adv)) = world.resource::<CaptureState>().get_advantage() {
-            let mut q = world.query::<(&MaterialAdvantageLabel, &mut Visibility, &mut Text)>();
-            for (label, mut vis, mut text) in q.iter_mut(world) {
-                if **label == color_with_adv {
-                    *vis = Visibility::Visible;
-                    text.0 = format!("+{adv}");
-                } else {
-                    *vis = Visibility::Hidden;
-                }
+fn sync_material_advantage_labels(world: &mut World) {
+    if let Some((color_with_adv, adv)) = world.resource::<CaptureState>().get_advantage() {
+        let mut q = world.query::<(&MaterialAdvantageLabel, &mut Visibility, &mut Text)>();
+        for (label, mut vis, mut text) in q.iter_mut(world) {
+            if **label == color_with_adv {
+                *vis = Visibility::Visible;
+                text.0 = format!("+{adv}");
+            } else {
+                *vis = Visibility::Hidden;
             }
-        } else {
-            world
-                .query_filtered::<&mut Visibility, With<MaterialAdvantageLabel>>()
-                .iter_mut(world)
-                .for_each(|mut vis| *vis = Visibility::Hidden);
         }
+    } else {
+        world
+            .query_filtered::<&mut Visibility, With<MaterialAdvantageLabel>>()
+            .iter_mut(world)
+            .for_each(|mut vis| *vis = Visibility::Hidden);
+    }
+}
+
+/// Show one image for each piece of a type captured by a side, overlapping each other, in the
+/// captured pieces strip of that type.
+pub struct SyncCaptureStrip {
+    color: PieceColor,
+    typ: PieceType,
+}
+
+impl SyncCaptureStrip {
+    pub fn new(color: PieceColor, typ: PieceType) -> Self {
+        Self { color, typ }
+    }
+}
+
+impl Command for SyncCaptureStrip {
+    fn apply(self, world: &mut World) {
+        let CapState { strip_entity, count } =
+            world.resource::<CaptureState>()[self.color][self.typ];
+        if world.get_entity(strip_entity).is_err() {
+            return;
+        }
+        // The pieces captured by a side are of the other side's color
+        let piece = PieceMeta::new(!self.color, self.typ);
+        let image =
+            world.resource::<AssetServer>().load(world.resource::<Theme>().piece_path(piece));
+
+        let mut strip = world.entity_mut(strip_entity);
+        if let Some(mut node) = strip.get_mut::<Node>() {
+            node.display = match count {
+                0 => Display::None,
+                _ => Display::Flex,
+            };
+        }
+        strip.despawn_related::<Children>().with_children(|cmds| {
+            for _ in 0..count {
+                cmds.spawn((
+                    CapturedPieceImage,
+                    ImageNode::new(image.clone()),
+                    Node { height: Val::Percent(100.0), ..default() },
+                ));
+            }
+        });
     }
 }
 
 #[derive(Event)]
 pub struct Captured;
 
-pub fn captures(
-    trigger: Trigger<Captured>,
-    mut commands: Commands,
-    mut q_data: Query<(&PieceMeta, &mut Visibility)>,
-) {
-    let Ok((&PieceMeta { mut color, typ }, mut vis)) = q_data.get_mut(trigger.target()) else {
+/// Hide a captured piece. The capture counts are updated once the move is completed.
+pub fn captures(trigger: Trigger<Captured>, mut q_data: Query<(&PieceMeta, &mut Visibility)>) {
+    let Ok((&PieceMeta { color, typ }, mut vis)) = q_data.get_mut(trigger.target()) else {
         return;
     };
     trace!(?color, ?typ, "Capture piece");
 
     *vis = Visibility::Hidden;
+}
 
-    // The count and capture image need to be updated for the player who performed the capture,
-    // i.e. the one whose color is the opposite of that of the captured piece.
-    color = !color;
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
 
-    commands.queue(CapStateUpdate::new(color, typ, CapStateDiff::Increment));
-}
+    use chess::{Board, Square as ChessSquare};
 
-pub struct ResetCapturesUi;
+    use super::*;
 
-impl Command for ResetCapturesUi {
-    fn apply(self, world: &mut World) {
-        let mut capture_state = world.resource_mut::<CaptureState>();
-
-        let image_entities: Vec<Entity> = capture_state
-            .iter_mut()
-            .flat_map(|player_caps| player_caps.iter_mut())
-            .map(|player_caps| {
-                player_caps.count = 0;
-                player_caps.image_entity
-            })
-            .collect();
-
-        for img_entity in image_entities {
-            if let Some(mut node) = world.entity_mut(img_entity).get_mut::<Node>() {
-                node.display = Display::None;
-            }
-        }
+    fn sync(start: &str, moves: &[ChessMove]) -> CaptureState {
+        let mut world = World::new();
+        world.insert_resource(CaptureState::new());
+        SyncCaptureState::new(Board::from_str(start).unwrap(), moves.to_vec()).apply(&mut world);
+        world.remove_resource::<CaptureState>().unwrap()
+    }
 
-        world
-            .query_filtered::<&mut Visibility, With<MaterialAdvantageLabel>>()
-            .iter_mut(world)
-            .for_each(|mut vis| *vis = Visibility::Hidden);
+    fn counts(state: &CaptureState, color: PieceColor) -> [u8; 5] {
+        CAPTURABLE_PIECES.map(|typ| state[color][typ].count)
+    }
+
+    #[test]
+    fn counts_promoted_pieces_as_captured_promotions() {
+        // The pawn promotes to a queen, which the rook captures
+        let state = sync(
+            "r3k3/1P6/8/8/8/8/PPPPPP2/RNBQKBNR w - - 0 1",
+            &[
+                ChessMove::new(ChessSquare::B7, ChessSquare::B8, Some(chess::Piece::Queen)),
+                ChessMove::new(ChessSquare::A8, ChessSquare::B8, None),
+            ],
+        );
+        // Pawns, knights, bishops, rooks, queens
+        assert_eq!(counts(&state, PieceColor::BLACK), [1, 0, 0, 0, 1]);
+        assert_eq!(counts(&state, PieceColor::WHITE), [8, 2, 2, 1, 1]);
+        // 37 points of white pieces against a rook
+        assert_eq!(state.get_advantage(), Some((PieceColor::WHITE, 32)));
+    }
+
+    #[test]
+    fn infers_promotions_in_loaded_positions() {
+        // White has promoted a pawn to a second queen, and black has lost a pawn and a knight
+        let state = sync("rnbqkb1r/ppppppp1/8/8/8/8/PPPPPPP1/RNBQKBQR w - - 0 1", &[]);
+        assert_eq!(counts(&state, PieceColor::BLACK), [0, 1, 0, 0, 0]);
+        assert_eq!(counts(&state, PieceColor::WHITE), [1, 1, 0, 0, 0]);
+        // 44 points against 35
+        assert_eq!(state.get_advantage(), Some((PieceColor::WHITE, 9)));
     }
 }
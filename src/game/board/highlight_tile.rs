@@ -2,15 +2,19 @@ use bevy::prelude::*;
 
 use crate::{debug_name_f, game::consts::Z_HIGHLIGHT_TILE};
 
-use super::{BoardState, Square};
+use super::{BoardState, Square, Theme};
 
 #[derive(Component)]
 pub struct HighlightTile;
 
-/// The color used to highlight tiles.
+/// The color used to highlight tiles in the default theme.
 pub const COLOR_HIGHLIGHT: Color = Color::srgba(1.0, 1.0, 0.0, 0.5);
 
-pub fn spawn_highlight_tiles(mut commands: Commands, mut board_state: ResMut<BoardState>) {
+pub fn spawn_highlight_tiles(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    mut board_state: ResMut<BoardState>,
+) {
     let top = Val::Px(0.0);
     let left = Val::Px(0.0);
 
@@ -30,7 +34,7 @@ pub fn spawn_highlight_tiles(mut commands: Commands, mut board_state: ResMut<Boa
                     height: Val::Percent(100.0),
                     ..default()
                 },
-                BackgroundColor(COLOR_HIGHLIGHT),
+                BackgroundColor(theme.highlight),
                 Visibility::Hidden,
                 GlobalZIndex(Z_HIGHLIGHT_TILE),
             ))
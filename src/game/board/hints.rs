@@ -2,11 +2,15 @@ use bevy::prelude::*;
 
 use crate::{debug_name_f, game::consts::Z_MOVE_HINT};
 
-use super::{BoardState, Square};
+use super::{BoardState, Square, Theme};
 
 #[derive(Component)]
 pub struct Hint;
 
+/// The image of a hint, which is tinted with the theme's hint color.
+#[derive(Component)]
+pub struct HintImage;
+
 #[derive(Debug)]
 pub struct TileHints {
     pub move_entity: Entity,
@@ -16,6 +20,7 @@ pub struct TileHints {
 pub fn spawn_hints(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
+    theme: Res<Theme>,
     mut board_state: ResMut<BoardState>,
 ) {
     let move_hint_texture = asset_server.load("images/hints/move.png");
@@ -43,7 +48,8 @@ pub fn spawn_hints(
                 Visibility::Hidden,
                 GlobalZIndex(Z_MOVE_HINT),
                 children![(
-                    ImageNode::new(move_hint_texture.clone()),
+                    HintImage,
+                    ImageNode::new(move_hint_texture.clone()).with_color(theme.hint),
                     Node {
                         width: Val::Percent(100.0 / 3.0),
                         height: Val::Percent(100.0 / 3.0),
@@ -59,7 +65,8 @@ pub fn spawn_hints(
                 Hint,
                 debug_name_f!("Capture Hint ({square})"),
                 square,
-                ImageNode::new(capture_hint_texture.clone()),
+                HintImage,
+                ImageNode::new(capture_hint_texture.clone()).with_color(theme.hint),
                 Node {
                     position_type: PositionType::Absolute,
                     top: Val::Px(0.0),
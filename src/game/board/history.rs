@@ -0,0 +1,514 @@
+use bevy::prelude::*;
+use chess::{Board, ChessMove};
+
+use crate::{
+    game::{
+        LoadGame, clock::ChessClock, menu::MenuState, mouse::Dragging,
+        move_entry::keyboard_not_captured,
+    },
+    utils::{NoopExts, ctrl_pressed},
+};
+
+use super::{
+    BoardState, ChessBoardExts, MovePiece, MovePlugin, PieceMeta, PieceType, PromotingPiece,
+    SelectionEvent, SelectionState, SpawnPieces, Square, SyncCaptureState,
+};
+
+#[derive(Debug)]
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<MovePlugin>() {
+            panic!("Attempted to add plugin without required dependency: {MovePlugin:?}");
+        }
+
+        app.noop()
+            // Resources
+            .init_resource::<ViewedPly>()
+            // Observers
+            .add_observer(undo_move)
+            .add_observer(redo_move)
+            .add_observer(view_ply)
+            .add_observer(view_live_position_on_load_game)
+            // Systems
+            .add_systems(
+                Update,
+                (history_shortcuts, browse_shortcuts)
+                    .run_if(in_state(MenuState::Game).and(keyboard_not_captured)),
+            )
+            .noop();
+    }
+}
+
+/// A single move that was made on the board along with everything needed to take it back.
+#[derive(Clone, Debug)]
+pub struct HistoryEntry {
+    /// The move that was made.
+    pub r#move: ChessMove,
+    /// The piece that was moved, as it was before the move (i.e. a pawn for promotions).
+    pub piece: PieceMeta,
+    /// The piece that was captured by the move, if any, including en passant captures.
+    pub captured: Option<PieceMeta>,
+    /// The board before the move was made.
+    pub board: Board,
+    /// The halfmove clock before the move was made.
+    pub half_move_clock: u8,
+    /// The fullmove count before the move was made.
+    pub full_move_count: u16,
+    /// The clocks before the move was made, once they are recorded when the clock is pressed.
+    pub clock: Option<ChessClock>,
+}
+
+impl HistoryEntry {
+    /// Create an entry for `move` being made on `board`.
+    ///
+    /// The move is assumed to be legal.
+    pub fn new(
+        board: &Board,
+        r#move: ChessMove,
+        half_move_clock: u8,
+        full_move_count: u16,
+    ) -> Self {
+        let from_sq = Square::new(r#move.get_source());
+        let to_sq = Square::new(r#move.get_dest());
+
+        let piece = board
+            .get_piece_meta(from_sq)
+            .unwrap_or_else(|| panic!("no piece at the source square of move {move}"));
+
+        let is_en_passant = piece.typ == PieceType::PAWN
+            && from_sq.get_file() != to_sq.get_file()
+            && board.piece_on(to_sq.0).is_none();
+
+        let captured = if is_en_passant {
+            to_sq.backward(piece.color).and_then(|sq| board.get_piece_meta(sq))
+        } else {
+            board.get_piece_meta(to_sq)
+        };
+
+        Self {
+            r#move,
+            piece,
+            captured,
+            board: *board,
+            half_move_clock,
+            full_move_count,
+            clock: None,
+        }
+    }
+
+    pub fn source(&self) -> Square {
+        Square::new(self.r#move.get_source())
+    }
+
+    pub fn dest(&self) -> Square {
+        Square::new(self.r#move.get_dest())
+    }
+
+    pub fn promotion(&self) -> Option<PieceType> {
+        self.r#move.get_promotion().map(PieceType)
+    }
+
+    /// Whether the move resets the halfmove clock, i.e. it is a pawn move or a capture.
+    pub fn resets_half_move_clock(&self) -> bool {
+        self.piece.typ == PieceType::PAWN || self.captured.is_some()
+    }
+}
+
+/// The moves that have been played, and the moves that have been taken back and can be replayed.
+#[derive(Default)]
+pub struct MoveHistory {
+    entries: Vec<HistoryEntry>,
+    /// Moves that were taken back, the last element being the next one to be replayed.
+    redo_stack: Vec<HistoryEntry>,
+}
+
+impl MoveHistory {
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.redo_stack.clear();
+    }
+
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    pub fn last(&self) -> Option<&HistoryEntry> {
+        self.entries().last()
+    }
+
+    pub fn next_redo(&self) -> Option<&HistoryEntry> {
+        self.redo_stack.last()
+    }
+
+    /// Record a move. If it is the next move to be redone its entry, with its clocks, and the rest
+    /// of the redo stack are kept, otherwise the game has diverged and the redo stack is discarded.
+    pub fn push(&mut self, entry: HistoryEntry) {
+        let entry = match self.redo_stack.last() {
+            Some(redo) if redo.r#move == entry.r#move => self.redo_stack.pop().unwrap_or(entry),
+            _ => {
+                self.redo_stack.clear();
+                entry
+            }
+        };
+        self.entries.push(entry);
+    }
+
+    /// Record the clocks before the last move was made, unless it was replayed and already has
+    /// them. Return whether they were recorded.
+    pub fn set_last_clock(&mut self, clock: &ChessClock) -> bool {
+        match self.entries.last_mut() {
+            Some(entry) if entry.clock.is_none() => {
+                entry.clock = Some(clock.clone());
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Take back the last move, moving it onto the redo stack.
+    pub fn undo(&mut self) -> Option<&HistoryEntry> {
+        let entry = self.entries.pop()?;
+        self.redo_stack.push(entry);
+        self.redo_stack.last()
+    }
+}
+
+/// The ply of the position shown on the board while browsing past positions, or `None` when the
+/// live position is shown.
+///
+/// Ply `n` is the position after the first `n` moves in the history. While previewing an analysis
+/// line it is the live ply, as the board shows a position that is not in the history.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Resource)]
+pub struct ViewedPly(pub Option<usize>);
+
+impl ViewedPly {
+    pub fn is_live(&self) -> bool {
+        self.0.is_none()
+    }
+}
+
+/// Run condition for systems that may only run while the live position is shown.
+pub fn viewing_live_position(viewed_ply: Option<Res<ViewedPly>>) -> bool {
+    viewed_ply.is_none_or(|ply| ply.is_live())
+}
+
+/// Show the position at the given ply on the board, read-only. Plies at or past the end of the
+/// history show the live position.
+#[derive(Event, Debug)]
+pub struct ViewPly(pub usize);
+
+#[derive(Event, Debug)]
+pub struct UndoMove;
+
+#[derive(Event, Debug)]
+pub struct RedoMove;
+
+fn undo_move(
+    _trigger: Trigger<UndoMove>,
+    mut commands: Commands,
+    mut board_state: ResMut<BoardState>,
+    mut selection_state: ResMut<SelectionState>,
+    clock: Option<ResMut<ChessClock>>,
+    viewed_ply: Res<ViewedPly>,
+    q_promo: Query<(), With<PromotingPiece>>,
+    q_dragging: Query<(), With<Dragging>>,
+) {
+    if !viewed_ply.is_live() || !q_promo.is_empty() || !q_dragging.is_empty() {
+        return;
+    }
+
+    let Some(entry) = board_state.undo_board_move() else { return };
+    trace!(r#move = %entry.r#move, "Undo move");
+
+    if let (Some(mut clock), Some(prev_clock)) = (clock, &entry.clock) {
+        *clock = prev_clock.clone();
+    }
+
+    *selection_state = SelectionState::Unselected;
+    commands.trigger(SelectionEvent::Unselect);
+    match board_state.history().last() {
+        Some(prev) => commands.trigger(SelectionEvent::UpdateLastMove(prev.source(), prev.dest())),
+        None => commands.trigger(SelectionEvent::UnsetLastMove),
+    }
+
+    let ply = board_state.history().entries().len();
+    commands.queue(SpawnPieces::new(*board_state.board()));
+    commands.queue(SyncCaptureState::at_ply(&board_state, ply));
+}
+
+fn redo_move(
+    _trigger: Trigger<RedoMove>,
+    mut commands: Commands,
+    board_state: Res<BoardState>,
+    mut selection_state: ResMut<SelectionState>,
+    viewed_ply: Res<ViewedPly>,
+    q_promo: Query<(), With<PromotingPiece>>,
+    q_dragging: Query<(), With<Dragging>>,
+) {
+    if !viewed_ply.is_live() || !q_promo.is_empty() || !q_dragging.is_empty() {
+        return;
+    }
+
+    let Some(entry) = board_state.history().next_redo() else { return };
+    trace!(r#move = %entry.r#move, "Redo move");
+
+    *selection_state = SelectionState::Unselected;
+
+    let (from_sq, to_sq) = (entry.source(), entry.dest());
+    let piece = board_state.piece(from_sq);
+    commands.trigger_targets(MovePiece::new(from_sq, to_sq, entry.promotion(), true), piece);
+}
+
+fn view_ply(
+    trigger: Trigger<ViewPly>,
+    mut commands: Commands,
+    board_state: Res<BoardState>,
+    mut viewed_ply: ResMut<ViewedPly>,
+    mut selection_state: ResMut<SelectionState>,
+    q_promo: Query<(), With<PromotingPiece>>,
+    q_dragging: Query<(), With<Dragging>>,
+) {
+    if !q_promo.is_empty() || !q_dragging.is_empty() {
+        return;
+    }
+
+    let entries = board_state.history().entries();
+    let ply = trigger.event().0.min(entries.len());
+    let new_viewed_ply = ViewedPly((ply < entries.len()).then_some(ply));
+    if *viewed_ply == new_viewed_ply {
+        return;
+    }
+    trace!(ply, live = new_viewed_ply.is_live(), "View ply");
+    *viewed_ply = new_viewed_ply;
+
+    *selection_state = SelectionState::Unselected;
+    commands.trigger(SelectionEvent::Unselect);
+    match ply.checked_sub(1).map(|i| &entries[i]) {
+        Some(prev) => commands.trigger(SelectionEvent::UpdateLastMove(prev.source(), prev.dest())),
+        None => commands.trigger(SelectionEvent::UnsetLastMove),
+    }
+
+    let board = entries.get(ply).map_or(*board_state.board(), |entry| entry.board);
+    commands.queue(SpawnPieces::new(board));
+    commands.queue(SyncCaptureState::at_ply(&board_state, ply));
+}
+
+fn view_live_position_on_load_game(_trigger: Trigger<LoadGame>, mut viewed_ply: ResMut<ViewedPly>) {
+    *viewed_ply = ViewedPly::default();
+}
+
+fn history_shortcuts(mut commands: Commands, keys: Res<ButtonInput<KeyCode>>) {
+    if !ctrl_pressed(&keys) {
+        return;
+    }
+
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    if keys.just_pressed(KeyCode::KeyZ) {
+        if shift {
+            commands.trigger(RedoMove);
+        } else {
+            commands.trigger(UndoMove);
+        }
+    } else if keys.just_pressed(KeyCode::KeyY) {
+        commands.trigger(RedoMove);
+    }
+}
+
+/// Step through past positions with the arrow keys, Home, and End.
+fn browse_shortcuts(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    board_state: Res<BoardState>,
+    viewed_ply: Res<ViewedPly>,
+) {
+    let live_ply = board_state.history().entries().len();
+    let ply = viewed_ply.0.unwrap_or(live_ply);
+
+    let target = if keys.just_pressed(KeyCode::ArrowLeft) {
+        ply.saturating_sub(1)
+    } else if keys.just_pressed(KeyCode::ArrowRight) {
+        ply + 1
+    } else if keys.just_pressed(KeyCode::Home) {
+        0
+    } else if keys.just_pressed(KeyCode::End) {
+        live_ply
+    } else {
+        return;
+    };
+
+    if (target < live_ply).then_some(target) != viewed_ply.0 {
+        commands.trigger(ViewPly(target));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{
+        board::{CaptureState, PieceColor},
+        core::{build_app, make_move},
+        move_entry::KeyboardCaptured,
+    };
+
+    use bevy::input::{
+        ButtonState,
+        keyboard::{Key, KeyboardInput, NativeKey},
+    };
+
+    use super::*;
+
+    fn assert_piece_at(app: &mut App, square: Square, expected: PieceMeta) {
+        let piece = app.world().resource::<BoardState>().piece(square);
+        let actual = app.world().entity(piece).get::<PieceMeta>().copied();
+        assert_eq!(actual, Some(expected), "piece at {square}");
+    }
+
+    #[test]
+    fn undo_restores_board_and_pieces() {
+        let mut app = build_app(());
+        make_move(&mut app, Square::E2, Square::E4);
+
+        app.world_mut().trigger(UndoMove);
+        app.update();
+
+        let board_state = app.world().resource::<BoardState>();
+        assert_eq!(*board_state.board(), Board::default());
+        assert!(board_state.history().entries().is_empty());
+        assert!(!board_state.has_piece_at(Square::E4));
+        assert_piece_at(&mut app, Square::E2, PieceMeta::new(PieceColor::WHITE, PieceType::PAWN));
+    }
+
+    #[test]
+    fn undo_restores_captured_piece() {
+        let mut app = build_app(());
+        make_move(&mut app, Square::E2, Square::E4);
+        make_move(&mut app, Square::D7, Square::D5);
+        make_move(&mut app, Square::E4, Square::D5);
+
+        let captures = &app.world().resource::<CaptureState>()[PieceColor::WHITE];
+        assert_eq!(captures[PieceType::PAWN].count, 1);
+
+        app.world_mut().trigger(UndoMove);
+        app.update();
+
+        assert_piece_at(&mut app, Square::D5, PieceMeta::new(PieceColor::BLACK, PieceType::PAWN));
+        assert_piece_at(&mut app, Square::E4, PieceMeta::new(PieceColor::WHITE, PieceType::PAWN));
+        let captures = &app.world().resource::<CaptureState>()[PieceColor::WHITE];
+        assert_eq!(captures[PieceType::PAWN].count, 0);
+    }
+
+    #[test]
+    fn redo_replays_undone_move() {
+        let mut app = build_app(());
+        make_move(&mut app, Square::G1, Square::F3);
+        let expected = *app.world().resource::<BoardState>().board();
+
+        app.world_mut().trigger(UndoMove);
+        app.update();
+        app.world_mut().trigger(RedoMove);
+        app.update();
+
+        let board_state = app.world().resource::<BoardState>();
+        assert_eq!(*board_state.board(), expected);
+        assert_eq!(board_state.history().entries().len(), 1);
+        assert!(board_state.history().next_redo().is_none());
+        assert_piece_at(&mut app, Square::F3, PieceMeta::new(PieceColor::WHITE, PieceType::KNIGHT));
+    }
+
+    #[test]
+    fn new_move_discards_redo_stack() {
+        let mut app = build_app(());
+        make_move(&mut app, Square::E2, Square::E4);
+
+        app.world_mut().trigger(UndoMove);
+        app.update();
+        make_move(&mut app, Square::D2, Square::D4);
+
+        let board_state = app.world().resource::<BoardState>();
+        assert!(board_state.history().next_redo().is_none());
+        assert_eq!(board_state.history().entries().len(), 1);
+    }
+
+    #[test]
+    fn view_ply_shows_past_position_read_only() {
+        let mut app = build_app(());
+        make_move(&mut app, Square::E2, Square::E4);
+        make_move(&mut app, Square::E7, Square::E5);
+
+        app.world_mut().trigger(ViewPly(1));
+        app.update();
+
+        assert_eq!(*app.world().resource::<ViewedPly>(), ViewedPly(Some(1)));
+        assert!(!app.world().resource::<BoardState>().has_piece_at(Square::E5));
+        assert_piece_at(&mut app, Square::E7, PieceMeta::new(PieceColor::BLACK, PieceType::PAWN));
+
+        // The live game is untouched and cannot be changed while browsing
+        app.world_mut().trigger(UndoMove);
+        app.update();
+        assert_eq!(app.world().resource::<BoardState>().history().entries().len(), 2);
+
+        app.world_mut().trigger(ViewPly(2));
+        app.update();
+
+        assert!(app.world().resource::<ViewedPly>().is_live());
+        assert_piece_at(&mut app, Square::E5, PieceMeta::new(PieceColor::BLACK, PieceType::PAWN));
+    }
+
+    #[test]
+    fn arrow_keys_browse_positions() {
+        let mut app = build_app(());
+        make_move(&mut app, Square::E2, Square::E4);
+        make_move(&mut app, Square::E7, Square::E5);
+
+        let press = |app: &mut App, key_code: KeyCode| {
+            for state in [ButtonState::Pressed, ButtonState::Released] {
+                app.world_mut().send_event(KeyboardInput {
+                    key_code,
+                    logical_key: Key::Unidentified(NativeKey::Unidentified),
+                    state,
+                    text: None,
+                    repeat: false,
+                    window: Entity::PLACEHOLDER,
+                });
+                app.update();
+            }
+            *app.world().resource::<ViewedPly>()
+        };
+
+        assert_eq!(press(&mut app, KeyCode::ArrowLeft), ViewedPly(Some(1)));
+        assert_eq!(press(&mut app, KeyCode::Home), ViewedPly(Some(0)));
+        assert_eq!(press(&mut app, KeyCode::ArrowLeft), ViewedPly(Some(0)));
+        assert_piece_at(&mut app, Square::E2, PieceMeta::new(PieceColor::WHITE, PieceType::PAWN));
+        assert_eq!(press(&mut app, KeyCode::ArrowRight), ViewedPly(Some(1)));
+        assert_eq!(press(&mut app, KeyCode::End), ViewedPly(None));
+        assert_eq!(press(&mut app, KeyCode::ArrowRight), ViewedPly(None));
+        assert_piece_at(&mut app, Square::E5, PieceMeta::new(PieceColor::BLACK, PieceType::PAWN));
+
+        // The keys move the cursor of the move entry instead
+        app.insert_resource(KeyboardCaptured(true));
+        assert_eq!(press(&mut app, KeyCode::ArrowLeft), ViewedPly(None));
+    }
+
+    #[test]
+    fn load_game_with_moves_replays_history() {
+        let mut app = build_app(());
+        let moves = vec![
+            ChessMove::new(Square::E2.0, Square::E4.0, None),
+            ChessMove::new(Square::E7.0, Square::E5.0, None),
+        ];
+        app.world_mut().trigger(LoadGame::in_game(Board::default(), 0, 1).with_moves(moves));
+        app.update();
+
+        assert_piece_at(&mut app, Square::E5, PieceMeta::new(PieceColor::BLACK, PieceType::PAWN));
+
+        app.world_mut().trigger(UndoMove);
+        app.update();
+
+        let board_state = app.world().resource::<BoardState>();
+        assert_eq!(board_state.side_to_move(), PieceColor::BLACK);
+        assert!(!board_state.has_piece_at(Square::E5));
+        assert_piece_at(&mut app, Square::E4, PieceMeta::new(PieceColor::WHITE, PieceType::PAWN));
+    }
+}
@@ -6,7 +6,7 @@ use crate::{
     game::{LoadGame, consts::Z_END_GAME_ICONS},
 };
 
-use super::{BoardState, PieceColor, Square, Tile};
+use super::{BoardOrientation, BoardState, PieceColor, Square, Tile};
 
 #[derive(Component)]
 pub(super) struct EndGameIcon;
@@ -92,14 +92,17 @@ pub(super) fn spawn_end_game_icons(
     commands.entity(board_state.tile(START_SQ)).add_child(white_draw_entity);
 }
 
+/// Show the winner and loser icons, for a checkmate or any other loss.
 #[derive(Debug)]
-pub struct ShowCheckmateIcons;
+pub struct ShowCheckmateIcons {
+    pub loser: PieceColor,
+}
 
 impl Command for ShowCheckmateIcons {
     fn apply(self, world: &mut World) {
         let board_state = world.resource::<BoardState>();
 
-        let loser_color = board_state.side_to_move();
+        let loser_color = self.loser;
         let loser_square = board_state.king_square(loser_color);
         let loser_tile_entity = board_state.tile(loser_square);
 
@@ -149,15 +152,20 @@ fn set_end_game_icon<IconMarker: Component>(
     if icon_parent.parent() != tile_entity {
         world.entity_mut(tile_entity).add_children(&[icon_entity]);
     }
+    // The icons sit above the top right corner of the tile, unless it is on the top or right edge
+    let (top_rank, right_file) = match **world.resource::<BoardOrientation>() {
+        PieceColor::WHITE => (Rank::Eighth, File::H),
+        PieceColor::BLACK => (Rank::First, File::A),
+    };
     let mut icon = world.entity_mut(icon_entity);
     *icon.get_mut::<Visibility>().unwrap() = Visibility::Visible;
     let mut node = icon.get_mut::<Node>().unwrap();
-    if square.get_rank() == Rank::Eighth {
+    if square.get_rank() == top_rank {
         node.top = Val::Percent(3.0);
     } else {
         node.top = Val::Percent(-14.0);
     }
-    if square.get_file() == File::H {
+    if square.get_file() == right_file {
         node.left = Val::Percent(57.0);
     } else {
         node.left = Val::Percent(74.0);
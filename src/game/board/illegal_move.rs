@@ -0,0 +1,232 @@
+use std::{fmt, time::Duration};
+
+use bevy::prelude::*;
+use chess::{BitBoard, Board, EMPTY, File, Piece};
+
+use crate::{
+    debug_name,
+    game::{audio::PlayGameAudio, consts::Z_HIGHLIGHT_TILE, toast::ShowToast},
+};
+
+use super::{BoardState, PieceColor, PieceType, Square};
+
+/// The color the king's square flashes when a move is illegal because of it.
+const COLOR_KING_FLASH: Color = Color::srgba(0.9, 0.15, 0.15, 0.8);
+
+/// How long the king's square flashes for.
+const KING_FLASH_DURATION: Duration = Duration::from_millis(900);
+
+/// How long each blink of the king's square lasts, on and off.
+const KING_FLASH_BLINK: Duration = Duration::from_millis(150);
+
+/// Why a piece can't be moved to a square.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IllegalMove {
+    /// The piece belongs to the side that isn't to move.
+    NotYourTurn(PieceColor),
+    /// The piece doesn't move that way, or its path is blocked.
+    Unreachable(PieceType),
+    /// The move doesn't get the king out of check.
+    KingInCheck,
+    /// The piece would leave the line between its king and the piece pinning it.
+    Pinned,
+    /// The king would move into or through check.
+    KingWouldBeInCheck,
+}
+
+impl fmt::Display for IllegalMove {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NotYourTurn(side_to_move) => write!(f, "It's {side_to_move}'s turn"),
+            Self::Unreachable(piece) => write!(f, "{piece} can't move there"),
+            Self::KingInCheck => f.write_str("King is in check"),
+            Self::Pinned => f.write_str("Piece is pinned to the king"),
+            Self::KingWouldBeInCheck => f.write_str("King would be in check"),
+        }
+    }
+}
+
+impl IllegalMove {
+    /// Why the piece on `from` can't move to `to`, or `None` if it can or there is nothing to
+    /// explain, e.g. the piece was dropped back on its square or on a piece of its own color.
+    pub fn find(board_state: &BoardState, from: Square, to: Square) -> Option<Self> {
+        let board = board_state.board();
+        let piece = board.piece_on(from.0)?;
+        let color = board.color_on(from.0)?;
+        if from == to || board.color_on(to.0) == Some(color) || board_state.move_is_valid(from, to)
+        {
+            return None;
+        }
+
+        if color != board.side_to_move() {
+            return Some(Self::NotYourTurn(board_state.side_to_move()));
+        }
+        if reachable(board, from, piece) & BitBoard::from_square(to.0) == EMPTY {
+            return Some(Self::Unreachable(PieceType(piece)));
+        }
+
+        // The piece could move there if it weren't for its king
+        let is_castle = piece == Piece::King
+            && from.get_file().to_index().abs_diff(to.get_file().to_index()) == 2;
+        let reason = if *board.checkers() != EMPTY && (piece != Piece::King || is_castle) {
+            Self::KingInCheck
+        } else if board.pinned() & BitBoard::from_square(from.0) != EMPTY {
+            Self::Pinned
+        } else {
+            Self::KingWouldBeInCheck
+        };
+        Some(reason)
+    }
+
+    /// Whether the move is illegal because of the king's safety.
+    pub fn is_king_in_danger(&self) -> bool {
+        matches!(self, Self::KingInCheck | Self::Pinned | Self::KingWouldBeInCheck)
+    }
+}
+
+/// The squares the piece on `from` could move to, ignoring its king's safety.
+fn reachable(board: &Board, from: Square, piece: Piece) -> BitBoard {
+    let color = board.side_to_move();
+    let blockers = *board.combined();
+    let moves = match piece {
+        Piece::Pawn => {
+            let en_passant = board
+                .en_passant()
+                .and_then(|square| square.forward(color))
+                .map_or(EMPTY, BitBoard::from_square);
+            chess::get_pawn_quiets(from.0, color, blockers)
+                | chess::get_pawn_attacks(from.0, color, *board.color_combined(!color) | en_passant)
+        }
+        Piece::Knight => chess::get_knight_moves(from.0),
+        Piece::Bishop => chess::get_bishop_moves(from.0, blockers),
+        Piece::Rook => chess::get_rook_moves(from.0, blockers),
+        Piece::Queen => {
+            chess::get_bishop_moves(from.0, blockers) | chess::get_rook_moves(from.0, blockers)
+        }
+        Piece::King => {
+            let rights = board.my_castle_rights();
+            let back_rank = PieceColor(color).to_my_backrank();
+            let mut castles = EMPTY;
+            if rights.has_kingside() && rights.kingside_squares(color) & blockers == EMPTY {
+                castles |= BitBoard::from_square(Square::from_coords(back_rank, File::G).0);
+            }
+            if rights.has_queenside() && rights.queenside_squares(color) & blockers == EMPTY {
+                castles |= BitBoard::from_square(Square::from_coords(back_rank, File::C).0);
+            }
+            chess::get_king_moves(from.0) | castles
+        }
+    };
+    moves & !board.color_combined(color)
+}
+
+/// Blinks the square of the king that made a move illegal.
+#[derive(Component, Deref, DerefMut)]
+pub struct KingFlash(Timer);
+
+/// Tell the player why their move is illegal: play the illegal move sound, explain it in a toast,
+/// and flash the king if the move is illegal because of it.
+pub struct ShowIllegalMove(pub IllegalMove);
+
+impl Command for ShowIllegalMove {
+    fn apply(self, world: &mut World) {
+        let Self(reason) = self;
+        debug!(%reason, "Illegal move");
+
+        PlayGameAudio::Illegal.apply(world);
+
+        if reason.is_king_in_danger() {
+            let flashes =
+                world.query_filtered::<Entity, With<KingFlash>>().iter(world).collect::<Vec<_>>();
+            for entity in flashes {
+                world.despawn(entity);
+            }
+
+            let board_state = world.resource::<BoardState>();
+            let tile = board_state.tile(board_state.king_square(board_state.side_to_move()));
+            world.spawn((
+                KingFlash(Timer::new(KING_FLASH_DURATION, TimerMode::Once)),
+                debug_name!("King Flash"),
+                Node {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(0.0),
+                    left: Val::Px(0.0),
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                BackgroundColor(COLOR_KING_FLASH),
+                GlobalZIndex(Z_HIGHLIGHT_TILE),
+                ChildOf(tile),
+            ));
+        }
+
+        world.trigger(ShowToast(reason.to_string()));
+    }
+}
+
+pub(super) fn blink_king_flash(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut q_flashes: Query<(Entity, &mut KingFlash, &mut Visibility)>,
+) {
+    for (entity, mut flash, mut visibility) in &mut q_flashes {
+        if flash.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let blink = flash.elapsed().as_millis() / KING_FLASH_BLINK.as_millis();
+        visibility.set_if_neq(if blink.is_multiple_of(2) {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn find(fen: &str, from: Square, to: Square) -> Option<IllegalMove> {
+        let mut board_state = BoardState::from_world(&mut World::new());
+        board_state.set_board(&Board::from_str(fen).unwrap(), 0, 1);
+        IllegalMove::find(&board_state, from, to)
+    }
+
+    #[test]
+    fn explains_illegal_moves() {
+        const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert_eq!(find(START, Square::E2, Square::E4), None);
+        assert_eq!(find(START, Square::E2, Square::E2), None);
+        assert_eq!(find(START, Square::D1, Square::D2), None);
+        assert_eq!(
+            find(START, Square::E7, Square::E5),
+            Some(IllegalMove::NotYourTurn(PieceColor::WHITE))
+        );
+        assert_eq!(
+            find(START, Square::E2, Square::E5),
+            Some(IllegalMove::Unreachable(PieceType::PAWN))
+        );
+        assert_eq!(
+            find(START, Square::D1, Square::D3),
+            Some(IllegalMove::Unreachable(PieceType::QUEEN))
+        );
+
+        // The bishop on b4 checks the king, and pins the knight on c3 when it is blocked
+        const CHECK: &str = "4k3/8/8/8/1b6/8/8/4K1N1 w - - 0 1";
+        assert_eq!(find(CHECK, Square::G1, Square::F3), Some(IllegalMove::KingInCheck));
+        assert_eq!(find(CHECK, Square::E1, Square::D2), Some(IllegalMove::KingWouldBeInCheck));
+        assert_eq!(find(CHECK, Square::E1, Square::F2), None);
+        const PIN: &str = "4k3/8/8/8/1b6/2N5/8/4K3 w - - 0 1";
+        assert_eq!(find(PIN, Square::C3, Square::E4), Some(IllegalMove::Pinned));
+
+        // The rook on f8 guards f1 and g1
+        const CASTLE: &str = "4kr2/8/8/8/8/8/8/4K2R w K - 0 1";
+        assert_eq!(find(CASTLE, Square::E1, Square::F1), Some(IllegalMove::KingWouldBeInCheck));
+        assert_eq!(find(CASTLE, Square::E1, Square::G1), Some(IllegalMove::KingWouldBeInCheck));
+        assert!(IllegalMove::KingWouldBeInCheck.is_king_in_danger());
+        assert!(!IllegalMove::Unreachable(PieceType::QUEEN).is_king_in_danger());
+    }
+}
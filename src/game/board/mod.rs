@@ -4,22 +4,30 @@ use bevy_startup_tree::{AddStartupTree, startup_tree};
 use crate::utils::NoopExts;
 
 pub use self::{
-    captures::*, highlight_tile::*, hints::*, icons::*, moves::*, pieces::*, promoter::*,
-    selection::*, square::*, state::*, tile::*, ui::*,
+    annotations::*, arrows::*, captures::*, highlight_tile::*, hints::*, history::*, icons::*,
+    illegal_move::*, moves::*, orientation::*, pieces::*, premove::*, promoter::*, selection::*,
+    square::*, state::*, theme::*, tile::*, ui::*,
 };
 
-use super::ui::spawn_ui;
+use super::{menu::MenuState, move_entry::keyboard_not_captured, ui::spawn_ui};
 
+mod annotations;
+mod arrows;
 mod captures;
 mod highlight_tile;
 mod hints;
+mod history;
 mod icons;
+mod illegal_move;
 mod moves;
+mod orientation;
 mod pieces;
+mod premove;
 mod promoter;
 mod selection;
 mod square;
 mod state;
+mod theme;
 mod tile;
 mod ui;
 
@@ -31,21 +39,42 @@ impl Plugin for BoardPlugin {
         app.noop()
             // Resources
             .init_resource::<BoardState>()
+            .init_resource::<BoardOrientation>()
+            .init_resource::<Theme>()
+            .init_resource::<Themes>()
             // Observers
             .add_observer(set_board_on_load_game)
             .add_observer(spawn_pieces_on_load_game)
             .add_observer(hide_end_game_icons_on_load_game)
+            .add_observer(flip_board)
             // Systems
             .add_startup_tree(startup_tree! {
                 spawn_board.after(spawn_ui) => {
                     spawn_tiles => {
                         spawn_highlight_tiles,
+                        spawn_premove_tiles,
                         spawn_hints,
                         spawn_promoters,
                         spawn_end_game_icons,
                     },
+                    spawn_arrow_layer,
                 },
             })
+            .add_systems(Update, (draw_arrows, draw_square_marks).in_set(DrawArrowsSystem))
+            .add_systems(
+                Update,
+                flip_board_shortcut.run_if(in_state(MenuState::Game).and(keyboard_not_captured)),
+            )
+            .add_systems(
+                Update,
+                (orient_board, orient_coordinates)
+                    .after(flip_board_shortcut)
+                    .run_if(resource_changed::<BoardOrientation>),
+            )
+            .add_systems(
+                Update,
+                (apply_board_theme, apply_piece_theme).run_if(resource_changed::<Theme>),
+            )
             .add_systems(PostUpdate, end_game_icon_size.before(UiSystem::Layout))
             .noop();
     }
@@ -8,6 +8,7 @@ use crate::{
 
 use super::{
     BoardState, Captured, PieceColor, PieceMeta, PieceType, PromotingPiece, SelectionEvent, Square,
+    Theme,
 };
 
 #[derive(Debug)]
@@ -25,7 +26,7 @@ impl Plugin for MovePlugin {
     }
 }
 
-#[derive(Event, Debug, PartialEq, Eq)]
+#[derive(Clone, Event, Debug, PartialEq, Eq)]
 pub struct MovePiece {
     from_sq: Square,
     to_sq: Square,
@@ -48,6 +49,7 @@ pub fn move_piece(
     trigger: Trigger<MovePiece>,
     mut commands: Commands,
     asset_server: Res<AssetServer>,
+    theme: Res<Theme>,
     board_state: Res<BoardState>,
     mut q_info: Query<(&PieceMeta, &mut ImageNode)>,
 ) {
@@ -58,9 +60,10 @@ pub fn move_piece(
     trace!(?color, ?typ, %from_sq, %to_sq, ?promotion, "Move piece");
 
     if let Some(promo_typ) = promotion {
-        // Update the piece texture
-        let new_asset_path = PieceMeta::new(color, promo_typ).asset_path();
-        image.image = asset_server.load(new_asset_path);
+        // Update the piece and its texture
+        let promoted = PieceMeta::new(color, promo_typ);
+        image.image = asset_server.load(theme.piece_path(promoted));
+        commands.entity(entity).insert(promoted);
     } else if typ == PieceType::PAWN && to_sq.get_rank() == color.to_their_backrank() {
         // Start promotion
         commands.entity(entity).insert(PromotingPiece::new(from_sq, to_sq));
@@ -106,7 +109,7 @@ pub fn move_piece(
         }
     }
 
-    commands.queue(UpdateBoardState::new(from_sq, to_sq, color, typ, promotion, is_capture));
+    commands.queue(UpdateBoardState::new(from_sq, to_sq, promotion));
 
     // Play audio
     commands.queue(if promotion.is_some() {
@@ -181,43 +184,23 @@ impl Command for UpdatePieceState {
 struct UpdateBoardState {
     from_sq: Square,
     to_sq: Square,
-    color: PieceColor,
-    typ: PieceType,
     promotion: Option<PieceType>,
-    is_capture: bool,
 }
 
 impl UpdateBoardState {
-    fn new(
-        from_sq: Square,
-        to_sq: Square,
-        color: PieceColor,
-        typ: PieceType,
-        promotion: Option<PieceType>,
-        is_capture: bool,
-    ) -> Self {
-        Self { from_sq, to_sq, color, typ, promotion, is_capture }
+    fn new(from_sq: Square, to_sq: Square, promotion: Option<PieceType>) -> Self {
+        Self { from_sq, to_sq, promotion }
     }
 }
 
 impl Command for UpdateBoardState {
     fn apply(self, world: &mut World) {
-        let Self { from_sq, to_sq, color, typ, promotion, is_capture } = self;
+        let Self { from_sq, to_sq, promotion } = self;
         let mut board_state = world.resource_mut::<BoardState>();
 
-        // Update `chess::Board`
+        // Update `chess::Board`, move trackers, and history
         board_state.make_board_move(from_sq, to_sq, promotion);
 
-        if typ == PieceType::PAWN || is_capture {
-            board_state.reset_half_move_clock();
-        } else {
-            board_state.inc_half_move_clock();
-        }
-
-        if color == PieceColor::BLACK {
-            board_state.inc_full_move_count();
-        }
-
         board_state.sync_status();
 
         if board_state.is_game_over() {
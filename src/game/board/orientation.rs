@@ -0,0 +1,104 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::ctrl_pressed;
+
+use super::{PieceColor, UiBoard};
+
+/// The side whose pieces start at the bottom of the board.
+#[derive(Clone, Copy, Debug, Deref, PartialEq, Eq, Resource, Serialize, Deserialize)]
+pub struct BoardOrientation(pub PieceColor);
+
+impl Default for BoardOrientation {
+    fn default() -> Self {
+        Self(PieceColor::WHITE)
+    }
+}
+
+/// Turn the board around, so that the other side's pieces start at the bottom.
+#[derive(Debug, Event)]
+pub struct FlipBoard;
+
+pub(super) fn flip_board(_trigger: Trigger<FlipBoard>, mut orientation: ResMut<BoardOrientation>) {
+    orientation.0 = !orientation.0;
+    debug!(orientation = %orientation.0, "Flip board");
+}
+
+/// Flip the board with `Ctrl+F`.
+pub(super) fn flip_board_shortcut(mut commands: Commands, keys: Res<ButtonInput<KeyCode>>) {
+    if ctrl_pressed(&keys) && keys.just_pressed(KeyCode::KeyF) {
+        commands.trigger(FlipBoard);
+    }
+}
+
+/// Wrap the tiles, which are spawned from a1 to h8, from the bottom left corner of the board when
+/// white is at the bottom, and from the top right corner when black is.
+pub(super) fn orient_board(
+    orientation: Res<BoardOrientation>,
+    mut q_board: Query<&mut Node, With<UiBoard>>,
+) {
+    let Ok(mut node) = q_board.single_mut() else { return };
+    (node.flex_direction, node.flex_wrap) = match **orientation {
+        PieceColor::WHITE => (FlexDirection::Row, FlexWrap::WrapReverse),
+        PieceColor::BLACK => (FlexDirection::RowReverse, FlexWrap::Wrap),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{
+        board::{CoordinateMarker, HistoryPlugin, MovePlugin, Square},
+        core::{GameHeadlessPlugin, GameTestPlugin},
+        menu::test::TestMenuStateInGamePlugin,
+        ui::{BoardAndPanelsContainer, GameUiPlugin},
+    };
+
+    use super::*;
+
+    /// The coordinate markers that are shown, as `label@square`.
+    fn shown_markers(app: &mut App) -> Vec<String> {
+        let mut q_markers =
+            app.world_mut().query_filtered::<(&Text, &Node, &ChildOf), With<CoordinateMarker>>();
+        let mut q_squares = app.world_mut().query::<&Square>();
+        q_markers
+            .iter(app.world())
+            .filter(|(_, node, _)| node.display != Display::None)
+            .map(|(text, _, child_of)| {
+                let square = q_squares.get(app.world(), child_of.parent()).unwrap();
+                format!("{}@{square}", text.0)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn flips_board_tiles_coordinates_and_panels() {
+        let mut app = App::new();
+        app.add_plugins((GameHeadlessPlugin, GameTestPlugin))
+            .add_plugins(TestMenuStateInGamePlugin)
+            .add_plugins((MovePlugin, HistoryPlugin))
+            .add_plugins(GameUiPlugin);
+        app.update();
+
+        let white_markers = shown_markers(&mut app);
+        assert!(white_markers.contains(&"a@a1".to_string()));
+        assert!(white_markers.contains(&"8@a8".to_string()));
+        assert_eq!(white_markers.len(), 16);
+
+        app.world_mut().trigger(FlipBoard);
+        app.update();
+        assert_eq!(**app.world().resource::<BoardOrientation>(), PieceColor::BLACK);
+
+        let black_markers = shown_markers(&mut app);
+        assert!(black_markers.contains(&"a@a8".to_string()));
+        assert!(black_markers.contains(&"1@h1".to_string()));
+        assert_eq!(black_markers.len(), 16);
+
+        let mut q_board = app.world_mut().query_filtered::<&Node, With<UiBoard>>();
+        let board = q_board.single(app.world()).unwrap();
+        assert_eq!(board.flex_direction, FlexDirection::RowReverse);
+        let mut q_container =
+            app.world_mut().query_filtered::<&Node, With<BoardAndPanelsContainer>>();
+        let container = q_container.single(app.world()).unwrap();
+        assert_eq!(container.flex_direction, FlexDirection::ColumnReverse);
+    }
+}
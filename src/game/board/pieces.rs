@@ -5,21 +5,23 @@ use bevy::{
     prelude::*,
 };
 use chess::{Piece, Rank};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
 
 use crate::{
     debug_name_f,
     game::{
         LoadGame,
         consts::{Z_PIECE, Z_PIECE_SELECTED},
+        settings::Settings,
     },
     utils::{NoopExts, hook},
 };
 
-use super::{BoardState, ChessBoardExts, square::Square};
+use super::{BoardState, ChessBoardExts, Theme, square::Square};
 
-macro_rules! asset_path {
+macro_rules! file_name {
     ($color:literal, $type:literal) => {
-        concat!("images/pieces/", $color, "-", $type, ".png")
+        concat!($color, "-", $type, ".png")
     };
 }
 
@@ -34,20 +36,21 @@ impl PieceMeta {
         Self { color, typ }
     }
 
-    pub fn asset_path(self) -> &'static str {
+    /// The name of the piece's image in a piece set, see [`Theme::piece_path`].
+    pub fn file_name(self) -> &'static str {
         match (self.color, self.typ) {
-            (PieceColor::BLACK, PieceType::BISHOP) => asset_path!("black", "bishop"),
-            (PieceColor::BLACK, PieceType::KING) => asset_path!("black", "king"),
-            (PieceColor::BLACK, PieceType::KNIGHT) => asset_path!("black", "knight"),
-            (PieceColor::BLACK, PieceType::PAWN) => asset_path!("black", "pawn"),
-            (PieceColor::BLACK, PieceType::QUEEN) => asset_path!("black", "queen"),
-            (PieceColor::BLACK, PieceType::ROOK) => asset_path!("black", "rook"),
-            (PieceColor::WHITE, PieceType::BISHOP) => asset_path!("white", "bishop"),
-            (PieceColor::WHITE, PieceType::KING) => asset_path!("white", "king"),
-            (PieceColor::WHITE, PieceType::KNIGHT) => asset_path!("white", "knight"),
-            (PieceColor::WHITE, PieceType::PAWN) => asset_path!("white", "pawn"),
-            (PieceColor::WHITE, PieceType::QUEEN) => asset_path!("white", "queen"),
-            (PieceColor::WHITE, PieceType::ROOK) => asset_path!("white", "rook"),
+            (PieceColor::BLACK, PieceType::BISHOP) => file_name!("black", "bishop"),
+            (PieceColor::BLACK, PieceType::KING) => file_name!("black", "king"),
+            (PieceColor::BLACK, PieceType::KNIGHT) => file_name!("black", "knight"),
+            (PieceColor::BLACK, PieceType::PAWN) => file_name!("black", "pawn"),
+            (PieceColor::BLACK, PieceType::QUEEN) => file_name!("black", "queen"),
+            (PieceColor::BLACK, PieceType::ROOK) => file_name!("black", "rook"),
+            (PieceColor::WHITE, PieceType::BISHOP) => file_name!("white", "bishop"),
+            (PieceColor::WHITE, PieceType::KING) => file_name!("white", "king"),
+            (PieceColor::WHITE, PieceType::KNIGHT) => file_name!("white", "knight"),
+            (PieceColor::WHITE, PieceType::PAWN) => file_name!("white", "pawn"),
+            (PieceColor::WHITE, PieceType::QUEEN) => file_name!("white", "queen"),
+            (PieceColor::WHITE, PieceType::ROOK) => file_name!("white", "rook"),
         }
     }
 }
@@ -99,6 +102,26 @@ impl fmt::Display for PieceColor {
     }
 }
 
+/// Colors are written as `"white"` and `"black"`, e.g. in the settings file.
+impl Serialize for PieceColor {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(match *self {
+            Self::WHITE => "white",
+            Self::BLACK => "black",
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for PieceColor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match String::deserialize(deserializer)?.as_str() {
+            "white" => Ok(Self::WHITE),
+            "black" => Ok(Self::BLACK),
+            other => Err(de::Error::unknown_variant(other, &["white", "black"])),
+        }
+    }
+}
+
 impl PieceColor {
     pub const BLACK: Self = Self(chess::Color::Black);
     pub const WHITE: Self = Self(chess::Color::White);
@@ -159,6 +182,7 @@ impl PieceType {
     pub const QUEEN: Self = Self(chess::Piece::Queen);
     pub const KING: Self = Self(chess::Piece::King);
 
+    /// The number of pieces of this type that a side has in the starting position.
     pub fn num_pieces(self) -> u8 {
         match self {
             Self::PAWN => 8,
@@ -178,41 +202,62 @@ impl PieceType {
     }
 }
 
-pub(super) fn spawn_pieces_on_load_game(
-    trigger: Trigger<LoadGame>,
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut board_state: ResMut<BoardState>,
-    q_pieces: Query<Entity, With<PieceMeta>>,
-) {
-    // Despawn all pieces
-    board_state.clear_pieces();
-    q_pieces.iter().for_each(|e| commands.entity(e).despawn());
-
-    for square in chess::ALL_SQUARES.map(Square::new) {
-        let Some(info) = trigger.event().board.get_piece_meta(square) else { continue };
-        let image_path = info.asset_path();
-
-        let piece_entity = commands
-            .spawn((
-                info,
-                debug_name_f!("Piece ({} {}) ({square})", info.color, info.typ),
-                square,
-                ImageNode::new(asset_server.load(image_path)),
-                Node {
-                    position_type: PositionType::Absolute,
-                    top: Val::Px(0.0),
-                    left: Val::Px(0.0),
-                    width: Val::Percent(100.0),
-                    height: Val::Percent(100.0),
-                    ..default()
-                },
-                GlobalZIndex(Z_PIECE),
-            ))
-            .id();
-
-        commands.entity(board_state.tile(square)).add_child(piece_entity);
-        board_state.set_piece(square, piece_entity);
+pub(super) fn spawn_pieces_on_load_game(trigger: Trigger<LoadGame>, mut commands: Commands) {
+    commands.queue(SpawnPieces::new(trigger.event().final_board()));
+}
+
+/// Despawn all UI pieces and spawn new ones for the pieces on `board`.
+pub struct SpawnPieces {
+    board: chess::Board,
+}
+
+impl SpawnPieces {
+    pub fn new(board: chess::Board) -> Self {
+        Self { board }
+    }
+}
+
+impl Command for SpawnPieces {
+    fn apply(self, world: &mut World) {
+        trace!("Spawn pieces");
+
+        // Despawn all pieces
+        world.resource_mut::<BoardState>().clear_pieces();
+        let pieces: Vec<Entity> =
+            world.query_filtered::<Entity, With<PieceMeta>>().iter(world).collect();
+        for piece in pieces {
+            world.despawn(piece);
+        }
+
+        let asset_server = world.resource::<AssetServer>().clone();
+        let theme = world.resource::<Theme>().clone();
+
+        for square in chess::ALL_SQUARES.map(Square::new) {
+            let Some(info) = self.board.get_piece_meta(square) else { continue };
+            let image_path = theme.piece_path(info);
+            let tile = world.resource::<BoardState>().tile(square);
+
+            let piece_entity = world
+                .spawn((
+                    info,
+                    debug_name_f!("Piece ({} {}) ({square})", info.color, info.typ),
+                    square,
+                    ImageNode::new(asset_server.load(image_path)),
+                    Node {
+                        position_type: PositionType::Absolute,
+                        top: Val::Px(0.0),
+                        left: Val::Px(0.0),
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    GlobalZIndex(Z_PIECE),
+                    ChildOf(tile),
+                ))
+                .id();
+
+            world.resource_mut::<BoardState>().set_piece(square, piece_entity);
+        }
     }
 }
 
@@ -347,14 +392,16 @@ impl Animating {
 fn animate_pieces(
     mut commands: Commands,
     time: Res<Time>,
+    settings: Option<Res<Settings>>,
     mut q_animating: Query<(Entity, &mut Animating, &mut Node)>,
 ) {
+    let speed = settings.map_or(1.0, |settings| settings.animation_speed);
     for (entity, mut animating, mut node) in &mut q_animating {
         if animating.timer.finished() {
             continue;
         }
 
-        animating.timer.tick(time.delta());
+        animating.timer.tick(time.delta().mul_f32(speed));
 
         if animating.timer.just_finished() {
             commands.entity(entity).remove::<Animating>();
@@ -372,9 +419,11 @@ fn on_remove_animating(
     mut commands: Commands,
     mut q_node: Query<&mut Node>,
 ) {
+    // The piece may have been despawned mid-animation, e.g. when the pieces are respawned
+    let Ok(mut node) = q_node.get_mut(entity) else { return };
+
     commands.entity(entity).insert(ChildOf(animating.to_entity));
 
-    let mut node = q_node.get_mut(entity).unwrap();
     node.left = Val::Px(0.0);
     node.top = Val::Px(0.0);
     node.width = Val::Percent(100.0);
@@ -0,0 +1,244 @@
+use bevy::prelude::*;
+use chess::{BitBoard, ChessMove, EMPTY, File, Piece};
+
+use crate::{
+    debug_name_f,
+    game::{
+        LoadGame, consts::Z_HIGHLIGHT_TILE, menu::MenuState, mouse::Dragging,
+        stockfish::StockfishOpponent,
+    },
+    utils::NoopExts,
+};
+
+use super::{
+    BoardState, MovePiece, PieceColor, PieceType, PromotingPiece, Square, Theme,
+    viewing_live_position,
+};
+
+pub struct PremovePlugin;
+
+impl Plugin for PremovePlugin {
+    fn build(&self, app: &mut App) {
+        app.noop()
+            // Resources
+            .init_resource::<Premove>()
+            // Observers
+            .add_observer(clear_premove_on_load_game)
+            // Systems
+            .add_systems(
+                Update,
+                play_premove.run_if(in_state(MenuState::Game)).run_if(viewing_live_position),
+            )
+            .add_systems(Update, show_premove.run_if(resource_changed::<Premove>))
+            .noop();
+    }
+}
+
+/// The color used to highlight the squares of a premove in the default theme.
+pub const COLOR_PREMOVE: Color = Color::srgba(0.1, 0.35, 0.8, 0.5);
+
+/// A move made during Stockfish's turn, as (from, to) squares, which is played as soon as it is
+/// the player's turn if it is legal then.
+///
+/// The premove is cancelled with the right mouse button.
+#[derive(Clone, Copy, Debug, Default, Deref, DerefMut, PartialEq, Eq, Resource)]
+pub struct Premove(pub Option<(Square, Square)>);
+
+/// Marks a square with a premove from or to it.
+#[derive(Component)]
+pub struct PremoveTile;
+
+/// The side that may premove: the player's side while Stockfish is to move.
+pub fn premove_side(
+    board_state: &BoardState,
+    opponent: Option<&StockfishOpponent>,
+) -> Option<PieceColor> {
+    let engine_color = opponent.and_then(|opponent| opponent.side.color())?;
+    let is_engine_turn = engine_color == board_state.side_to_move();
+    (is_engine_turn && !board_state.is_game_over()).then_some(!engine_color)
+}
+
+/// Whether the piece on `from` could move to `to` in some later position, i.e. if the pieces in
+/// the way moved and an opponent piece stood on `to` for pawn captures.
+///
+/// `to` may hold one of the player's own pieces, which Stockfish could capture first, e.g. for a
+/// recapture.
+pub fn is_premove(board_state: &BoardState, color: PieceColor, from: Square, to: Square) -> bool {
+    let board = board_state.board();
+    if from == to || board.color_on(from.0) != Some(color.0) {
+        return false;
+    }
+    let Some(piece) = board.piece_on(from.0) else { return false };
+
+    let reachable = match piece {
+        Piece::Pawn => {
+            chess::get_pawn_quiets(from.0, color.0, EMPTY)
+                | chess::get_pawn_attacks(from.0, color.0, !EMPTY)
+        }
+        Piece::Knight => chess::get_knight_moves(from.0),
+        Piece::Bishop => chess::get_bishop_rays(from.0),
+        Piece::Rook => chess::get_rook_rays(from.0),
+        Piece::Queen => chess::get_bishop_rays(from.0) | chess::get_rook_rays(from.0),
+        Piece::King => {
+            let back_rank = color.to_my_backrank();
+            let castles = if from == Square::from_coords(back_rank, File::E) {
+                BitBoard::from_square(Square::from_coords(back_rank, File::G).0)
+                    | BitBoard::from_square(Square::from_coords(back_rank, File::C).0)
+            } else {
+                EMPTY
+            };
+            chess::get_king_moves(from.0) | castles
+        }
+    };
+    reachable & BitBoard::from_square(to.0) != EMPTY
+}
+
+pub fn spawn_premove_tiles(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    board_state: Res<BoardState>,
+) {
+    for square in chess::ALL_SQUARES {
+        let square = Square::new(square);
+
+        let premove_tile_entity = commands
+            .spawn((
+                PremoveTile,
+                debug_name_f!("Premove Tile ({square})"),
+                square,
+                Node {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(0.0),
+                    left: Val::Px(0.0),
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                BackgroundColor(theme.premove),
+                Visibility::Hidden,
+                GlobalZIndex(Z_HIGHLIGHT_TILE),
+            ))
+            .id();
+
+        commands.entity(board_state.tile(square)).add_child(premove_tile_entity);
+    }
+}
+
+fn clear_premove_on_load_game(_trigger: Trigger<LoadGame>, mut premove: ResMut<Premove>) {
+    **premove = None;
+}
+
+/// Play the premove once it is the player's turn, or drop it if it isn't legal.
+fn play_premove(
+    mut commands: Commands,
+    mut premove: ResMut<Premove>,
+    board_state: Res<BoardState>,
+    opponent: Option<Res<StockfishOpponent>>,
+    q_busy: Query<(), Or<(With<Dragging>, With<PromotingPiece>)>>,
+) {
+    let Some((from_sq, to_sq)) = **premove else { return };
+    if premove_side(&board_state, opponent.as_deref()).is_some() || !q_busy.is_empty() {
+        return;
+    }
+    **premove = None;
+
+    let board = board_state.board();
+    let r#move = [None, Some(Piece::Queen)]
+        .into_iter()
+        .map(|promotion| ChessMove::new(from_sq.0, to_sq.0, promotion))
+        .find(|&r#move| board.legal(r#move));
+    let Some(r#move) = r#move.filter(|_| !board_state.is_game_over()) else {
+        debug!(%from_sq, %to_sq, "Drop illegal premove");
+        return;
+    };
+
+    let promotion = r#move.get_promotion().map(PieceType);
+    debug!(%from_sq, %to_sq, ?promotion, "Play premove");
+    let piece = board_state.piece(from_sq);
+    commands.trigger_targets(MovePiece::new(from_sq, to_sq, promotion, true), piece);
+}
+
+fn show_premove(
+    premove: Res<Premove>,
+    mut q_tiles: Query<(&Square, &mut Visibility), With<PremoveTile>>,
+) {
+    for (square, mut visibility) in &mut q_tiles {
+        let is_shown = premove.is_some_and(|(from, to)| *square == from || *square == to);
+        visibility.set_if_neq(if is_shown { Visibility::Visible } else { Visibility::Hidden });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{
+        board::MouseSelectionEvent,
+        core::{build_app, make_move},
+        stockfish::OpponentSide,
+    };
+
+    use super::*;
+
+    fn build_premove_app() -> App {
+        build_app((PremovePlugin, |app: &mut App| {
+            app.insert_resource(StockfishOpponent { side: OpponentSide::Black, ..default() });
+        }))
+    }
+
+    fn drag(app: &mut App, from: Square, to: Square) {
+        app.world_mut().send_event(MouseSelectionEvent::MouseDown(from));
+        app.update();
+        app.world_mut().send_event(MouseSelectionEvent::MouseUp(to));
+        app.update();
+    }
+
+    fn piece_on(app: &App, square: Square) -> Option<Piece> {
+        app.world().resource::<BoardState>().board().piece_on(square.0)
+    }
+
+    #[test]
+    fn plays_premove_on_players_turn() {
+        let mut app = build_premove_app();
+        make_move(&mut app, Square::E2, Square::E4);
+
+        // The knight can't reach e4, and the rook is blocked until later
+        drag(&mut app, Square::G1, Square::E4);
+        assert_eq!(**app.world().resource::<Premove>(), None);
+        drag(&mut app, Square::A1, Square::A5);
+        assert_eq!(**app.world().resource::<Premove>(), Some((Square::A1, Square::A5)));
+
+        drag(&mut app, Square::D2, Square::D4);
+        assert_eq!(**app.world().resource::<Premove>(), Some((Square::D2, Square::D4)));
+        assert_eq!(piece_on(&app, Square::D4), None);
+
+        make_move(&mut app, Square::E7, Square::E5);
+        app.update();
+        assert_eq!(**app.world().resource::<Premove>(), None);
+        assert_eq!(piece_on(&app, Square::D4), Some(Piece::Pawn));
+        assert_eq!(app.world().resource::<BoardState>().side_to_move(), PieceColor::BLACK);
+
+        // Recapture on a square held by the player's own piece
+        drag(&mut app, Square::D1, Square::D4);
+        assert_eq!(**app.world().resource::<Premove>(), Some((Square::D1, Square::D4)));
+
+        make_move(&mut app, Square::E5, Square::D4);
+        app.update();
+        assert_eq!(**app.world().resource::<Premove>(), None);
+        assert_eq!(piece_on(&app, Square::D4), Some(Piece::Queen));
+        assert_eq!(app.world().resource::<BoardState>().side_to_move(), PieceColor::BLACK);
+    }
+
+    #[test]
+    fn drops_illegal_premove() {
+        let mut app = build_premove_app();
+        make_move(&mut app, Square::E2, Square::E4);
+
+        drag(&mut app, Square::E4, Square::E5);
+        assert_eq!(**app.world().resource::<Premove>(), Some((Square::E4, Square::E5)));
+
+        // The pawn is blocked
+        make_move(&mut app, Square::E7, Square::E5);
+        app.update();
+        assert_eq!(**app.world().resource::<Premove>(), None);
+        assert_eq!(app.world().resource::<BoardState>().side_to_move(), PieceColor::WHITE);
+    }
+}
@@ -13,7 +13,7 @@ use crate::{
     utils::{NoopExts, hook},
 };
 
-use super::{BoardState, PieceColor, PieceMeta, PieceType, Square, Tile};
+use super::{BoardOrientation, BoardState, PieceColor, PieceMeta, PieceType, Square, Theme, Tile};
 
 pub struct PromotionPlugin;
 
@@ -45,9 +45,14 @@ pub struct PromotionButton(PieceType);
 #[derive(Component)]
 pub struct PromotionCancelButton;
 
+/// The image of a piece that can be promoted to.
+#[derive(Component, Deref)]
+pub struct PromotionPiece(PieceMeta);
+
 pub fn spawn_promoters(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
+    theme: Res<Theme>,
     board_state: Res<BoardState>,
 ) {
     for (color, flex_direction) in [
@@ -79,7 +84,7 @@ pub fn spawn_promoters(
 
                 for typ in [PieceType::QUEEN, PieceType::KNIGHT, PieceType::ROOK, PieceType::BISHOP]
                 {
-                    let asset_path = PieceMeta::new(color, typ).asset_path();
+                    let piece = PieceMeta::new(color, typ);
 
                     cmds.spawn((
                         debug_name_f!("Promotion Button ({color}) ({typ})"),
@@ -89,7 +94,8 @@ pub fn spawn_promoters(
                         BackgroundColor(PROMO_TILE_COLOR),
                         children![(
                             debug_name_f!("Promotion Piece ({color}) ({typ})"),
-                            ImageNode::new(asset_server.load(asset_path)),
+                            PromotionPiece(piece),
+                            ImageNode::new(asset_server.load(theme.piece_path(piece))),
                             Node {
                                 width: Val::Percent(100.0),
                                 height: Val::Percent(100.0),
@@ -170,9 +176,10 @@ pub fn start_promotion(
     In((piece, promoting)): In<(Entity, PromotingPiece)>,
     mut commands: Commands,
     board_state: Res<BoardState>,
+    orientation: Res<BoardOrientation>,
     q_piece_meta: Query<&PieceMeta>,
     mut q_visibility: Query<&mut Visibility>,
-    mut q_promoters: Query<(Entity, &PromotionUi)>,
+    mut q_promoters: Query<(Entity, &PromotionUi, &mut Node)>,
 ) {
     let PromotingPiece { from_sq, to_sq } = promoting;
     let Ok(&PieceMeta { color, .. }) = q_piece_meta.get(piece) else { return };
@@ -186,8 +193,15 @@ pub fn start_promotion(
     // Hide the piece
     *vis = Visibility::Hidden;
 
-    // Show the promoter UI
-    if let Some((entity, _)) = q_promoters.iter_mut().find(|(_, promo)| promo.0 == color) {
+    // Show the promoter UI, extending from the promotion square towards the middle of the board
+    if let Some((entity, _, mut node)) =
+        q_promoters.iter_mut().find(|(_, promo, _)| promo.0 == color)
+    {
+        (node.top, node.bottom, node.flex_direction) = if color == **orientation {
+            (Val::Px(0.0), Val::Auto, FlexDirection::Column)
+        } else {
+            (Val::Auto, Val::Px(0.0), FlexDirection::ColumnReverse)
+        };
         commands.entity(entity).insert(ChildOf(board_state.tile(to_sq)));
         if let Ok(mut vis) = q_visibility.get_mut(entity) {
             *vis = Visibility::Visible;
@@ -9,11 +9,17 @@ use bevy::{
 };
 
 use crate::{
-    game::{LoadGame, board::MovePiece, menu::MenuState, mouse::Dragging},
+    game::{
+        LoadGame, audio::PlayGameAudio, board::MovePiece, menu::MenuState, mouse::Dragging,
+        stockfish::StockfishOpponent,
+    },
     utils::NoopExts,
 };
 
-use super::{BoardState, HighlightTile, Hint, Square};
+use super::{
+    BoardState, HighlightTile, Hint, IllegalMove, Premove, ShowIllegalMove, Square,
+    blink_king_flash, is_premove, premove_side, viewing_live_position,
+};
 
 pub struct SelectionPlugin;
 
@@ -29,7 +35,13 @@ impl Plugin for SelectionPlugin {
             .add_observer(unset_selections_on_load_game)
             .add_observer(handle_selection_events)
             // Systems
-            .add_systems(Update, handle_mouse_selection_events.run_if(in_state(MenuState::Game)))
+            .add_systems(
+                Update,
+                handle_mouse_selection_events
+                    .run_if(in_state(MenuState::Game))
+                    .run_if(viewing_live_position),
+            )
+            .add_systems(Update, blink_king_flash)
             .noop();
     }
 }
@@ -46,9 +58,10 @@ pub enum SelectionState {
 #[derive(Clone, Debug)]
 pub enum SelectionStateAction {
     ChangeSelection(Square),
-    DropSelect(Square),
+    DropSelect { from_sq: Square, to_sq: Square },
     Move { from_sq: Square, to_sq: Square, animate: bool },
     None,
+    Premove { from_sq: Square, to_sq: Square },
     StartSelectedDragging(Square),
     StartSelectingDragging(Square),
     Unselect(Square),
@@ -58,12 +71,17 @@ impl fmt::Display for SelectionStateAction {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             Self::ChangeSelection(sq) => f.write_fmt(format_args!("ChangeSelection(=> {sq})")),
-            Self::DropSelect(sq) => f.write_fmt(format_args!("DropSelect({sq})")),
+            Self::DropSelect { from_sq, to_sq } => {
+                f.write_fmt(format_args!("DropSelect({from_sq} => {to_sq})"))
+            }
             Self::Move { from_sq, to_sq, animate } => f.write_fmt(format_args!(
                 "Move({from_sq} => {to_sq}, {})",
                 if animate { "anim" } else { "no_anim" }
             )),
             Self::None => f.write_str("None"),
+            Self::Premove { from_sq, to_sq } => {
+                f.write_fmt(format_args!("Premove({from_sq} => {to_sq})"))
+            }
             Self::StartSelectedDragging(sq) => {
                 f.write_fmt(format_args!("StartSelectedDragging({sq})"))
             }
@@ -94,8 +112,17 @@ fn handle_mouse_selection_events(
     mut commands: Commands,
     mut selection_state: ResMut<SelectionState>,
     board_state: Res<BoardState>,
+    opponent: Option<Res<StockfishOpponent>>,
+    mut premove: Option<ResMut<Premove>>,
     mut event_reader: EventReader<MouseSelectionEvent>,
 ) {
+    // Moves made during Stockfish's turn are premoves, if premoves are enabled
+    let premove_side =
+        premove_side(&board_state, opponent.as_deref()).filter(|_| premove.is_some());
+    let is_premove = |from_sq, to_sq| {
+        premove_side.is_some_and(|color| is_premove(&board_state, color, from_sq, to_sq))
+    };
+
     for &event in event_reader.read() {
         trace!(%event, "Processing mouse selection");
         let action = match *selection_state {
@@ -118,8 +145,10 @@ fn handle_mouse_selection_events(
                             to_sq: square,
                             animate: false,
                         }
+                    } else if is_premove(selecting_sq, square) {
+                        SelectionStateAction::Premove { from_sq: selecting_sq, to_sq: square }
                     } else {
-                        SelectionStateAction::DropSelect(selecting_sq)
+                        SelectionStateAction::DropSelect { from_sq: selecting_sq, to_sq: square }
                     }
                 }
             },
@@ -133,6 +162,8 @@ fn handle_mouse_selection_events(
                             to_sq: square,
                             animate: true,
                         }
+                    } else if is_premove(selected_sq, square) {
+                        SelectionStateAction::Premove { from_sq: selected_sq, to_sq: square }
                     } else if board_state.has_piece_at(square) {
                         SelectionStateAction::ChangeSelection(square)
                     } else {
@@ -152,8 +183,10 @@ fn handle_mouse_selection_events(
                             to_sq: square,
                             animate: false,
                         }
+                    } else if is_premove(selected_sq, square) {
+                        SelectionStateAction::Premove { from_sq: selected_sq, to_sq: square }
                     } else {
-                        SelectionStateAction::DropSelect(selected_sq)
+                        SelectionStateAction::DropSelect { from_sq: selected_sq, to_sq: square }
                     }
                 }
             },
@@ -173,12 +206,16 @@ fn handle_mouse_selection_events(
                 // Set state to SelectingDragging
                 *selection_state = SelectionState::SelectingDragging(to_sq);
             }
-            SelectionStateAction::DropSelect(square) => {
+            SelectionStateAction::DropSelect { from_sq, to_sq } => {
                 // Drop piece
-                let piece = board_state.piece(square);
+                let piece = board_state.piece(from_sq);
                 commands.entity(piece).remove::<Dragging>();
+                // Explain why the piece can't move where it was dropped
+                if let Some(reason) = IllegalMove::find(&board_state, from_sq, to_sq) {
+                    commands.queue(ShowIllegalMove(reason));
+                }
                 // Set state to Selected
-                *selection_state = SelectionState::Selected(square);
+                *selection_state = SelectionState::Selected(from_sq);
             }
             SelectionStateAction::Move { from_sq, to_sq, animate } => {
                 let piece = board_state.piece(from_sq);
@@ -186,6 +223,20 @@ fn handle_mouse_selection_events(
                 // Set state to Unselected
                 *selection_state = SelectionState::Unselected;
             }
+            SelectionStateAction::Premove { from_sq, to_sq } => {
+                // Drop piece
+                let piece = board_state.piece(from_sq);
+                commands.entity(piece).remove::<Dragging>();
+                // Unselect square & remove hints
+                commands.trigger(SelectionEvent::Unselect);
+                // Queue the move until it's the player's turn
+                if let Some(premove) = premove.as_mut() {
+                    ***premove = Some((from_sq, to_sq));
+                    commands.queue(PlayGameAudio::Premove);
+                }
+                // Set state to Unselected
+                *selection_state = SelectionState::Unselected;
+            }
             SelectionStateAction::StartSelectedDragging(square) => {
                 // Start dragging piece
                 let piece = board_state.piece(square);
@@ -262,8 +313,12 @@ impl fmt::Display for SelectionEvent {
     }
 }
 
-fn unset_selections_on_load_game(_trigger: Trigger<LoadGame>, mut commands: Commands) {
+fn unset_selections_on_load_game(trigger: Trigger<LoadGame>, mut commands: Commands) {
     commands.trigger(SelectionEvent::UnsetAll);
+    if let Some(last_move) = trigger.event().moves.last() {
+        let (from_sq, to_sq) = (last_move.get_source().into(), last_move.get_dest().into());
+        commands.trigger(SelectionEvent::UpdateLastMove(from_sq, to_sq));
+    }
 }
 
 fn handle_selection_events(
@@ -357,34 +412,33 @@ fn on_remove_showing_indicator(mut world: DeferredWorld, ctx: HookContext) {
 
 #[cfg(test)]
 mod tests {
-    use crate::game::board::{PieceColor, PieceType};
+    use std::str::FromStr;
+
+    use chess::Board;
+
+    use crate::game::board::{KingFlash, PieceColor, PieceType};
 
     use super::*;
 
     mod utils {
         use bevy::platform::collections::HashSet;
 
-        use crate::game::{
-            board::{MovePlugin, PieceMeta},
-            core::{GameHeadlessPlugin, GameTestPlugin},
-            menu::test::TestMenuStateInGamePlugin,
-            mouse::DragContainer,
-            ui::GameUiPlugin,
-        };
+        use crate::game::{board::PieceMeta, core, mouse::DragContainer};
 
         use super::*;
 
+        /// The moves triggered on the pieces, oldest first.
+        #[derive(Default, Resource)]
+        pub struct TriggeredMoves(Vec<MovePiece>);
+
         pub fn build_app() -> App {
-            let mut app = App::new();
-            app.add_plugins((GameHeadlessPlugin, GameTestPlugin))
-                .add_plugins(TestMenuStateInGamePlugin)
-                .add_plugins(GameUiPlugin)
-                .add_plugins(SelectionPlugin)
-                .add_plugins(MovePlugin);
-            app.update();
-            app.world_mut().trigger(LoadGame::in_game_default());
-            app.update();
-            app
+            core::build_app(|app: &mut App| {
+                app.init_resource::<TriggeredMoves>().add_observer(
+                    |trigger: Trigger<MovePiece>, mut moves: ResMut<TriggeredMoves>| {
+                        moves.0.push(trigger.event().clone());
+                    },
+                );
+            })
         }
 
         pub fn get_tagged_entity<Tag: Component>(app: &mut App) -> Entity {
@@ -432,9 +486,9 @@ mod tests {
             }
 
             fn set_piece_to_drag_container(&mut self, square: Square) {
-                let parent = get_tagged_entity::<DragContainer>(self);
-                let child = self.board_state().piece(square);
-                self.world_mut().entity_mut(parent).add_child(child);
+                let piece = self.board_state().piece(square);
+                self.world_mut().entity_mut(piece).insert(Dragging { original_square: square });
+                self.world_mut().flush();
             }
 
             fn assert_state(&self, expected: SelectionState) {
@@ -461,7 +515,8 @@ mod tests {
             }
 
             fn assert_piece_move_marker(&mut self, expected: MovePiece) {
-                let actual = self.world_mut().resource_mut::<Events<MovePiece>>().drain().next();
+                let mut moves = self.world_mut().resource_mut::<TriggeredMoves>();
+                let actual = moves.0.drain(..).next();
                 assert_eq!(actual, Some(expected), "move piece event on piece");
             }
 
@@ -718,6 +773,31 @@ mod tests {
         app.assert_hints([Square::D3, Square::D4]);
     }
 
+    #[test]
+    fn selecting_dragging_flashes_king_on_mouse_up_when_pinned() {
+        let mut app = build_app();
+        // The bishop on b4 pins the knight on c3
+        let board = Board::from_str("4k3/8/8/8/1b6/2N5/8/4K3 w - - 0 1").unwrap();
+        app.world_mut().trigger(LoadGame::in_game(board, 0, 1));
+        app.update();
+
+        app.world_mut().send_event(MouseSelectionEvent::MouseDown(Square::C3));
+        app.update();
+        app.world_mut().send_event(MouseSelectionEvent::MouseUp(Square::E4));
+        app.update();
+
+        app.assert_state(SelectionState::Selected(Square::C3));
+        app.assert_piece_on_tile(Square::C3, PieceColor::WHITE, PieceType::KNIGHT);
+        let king_tile = app.board_state().tile(Square::E1);
+        let flashes = app
+            .world_mut()
+            .query_filtered::<&ChildOf, With<KingFlash>>()
+            .iter(app.world())
+            .map(ChildOf::parent)
+            .collect::<Vec<_>>();
+        assert_eq!(flashes, [king_tile]);
+    }
+
     #[test]
     fn dragging_removed_on_piece_move() {
         // Bug reproduction:
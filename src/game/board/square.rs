@@ -62,6 +62,16 @@ impl Square {
         Self::rank_to_char(self.get_rank())
     }
 
+    /// The center of the square in percent of the board size, from the top-left corner of the board
+    /// when it is viewed from `orientation`'s side.
+    pub fn board_position(self, orientation: PieceColor) -> Vec2 {
+        let file = self.get_file().to_index() as f32;
+        let rank = self.get_rank().to_index() as f32;
+        let (column, row) =
+            if orientation == PieceColor::WHITE { (file, 7.0 - rank) } else { (7.0 - file, rank) };
+        (Vec2::new(column, row) + 0.5) * (100.0 / 8.0)
+    }
+
     pub fn forward(self, color: PieceColor) -> Option<Self> {
         self.0.forward(color.0).map(Self)
     }
@@ -4,11 +4,14 @@ use std::{
 };
 
 use bevy::prelude::*;
-use chess::{BitBoard, Board, BoardStatus, CastleRights, ChessMove, EMPTY, MoveGen};
+use chess::{BitBoard, Board, BoardStatus, CastleRights, ChessMove, EMPTY, MoveGen, Piece};
 
-use crate::{cli::CliArgs, game::LoadGame};
+use crate::{
+    cli::CliArgs,
+    game::{LoadGame, clock::ChessClock},
+};
 
-use super::{PieceColor, PieceMeta, PieceType, Square, TileHints};
+use super::{HistoryEntry, MoveHistory, PieceColor, PieceMeta, PieceType, Square, TileHints};
 
 #[derive(Resource)]
 pub struct BoardState {
@@ -21,6 +24,7 @@ pub struct BoardState {
     half_move_clock: u8,
     full_move_count: u16,
     piece_state_counters: HashMap<u64, u8>,
+    history: MoveHistory,
 }
 
 impl FromWorld for BoardState {
@@ -51,6 +55,7 @@ impl FromWorld for BoardState {
             half_move_clock,
             full_move_count,
             piece_state_counters,
+            history: MoveHistory::default(),
         }
     }
 }
@@ -76,12 +81,31 @@ pub fn parse_fen(fen: &str) -> Result<(Board, u8, u16), chess::Error> {
     }
 }
 
+/// Write the FEN of `board` with the given move trackers.
+pub fn to_fen(board: &Board, half_move_clock: u8, full_move_count: u16) -> String {
+    use std::fmt::Write;
+    let mut fen = board.to_string();
+    fen.truncate(fen.len() - 3);
+    write!(&mut fen, "{half_move_clock} {full_move_count}")
+        .expect("Write halfmove clock & fullmove count to FEN");
+    fen
+}
+
 pub(super) fn set_board_on_load_game(
     trigger: Trigger<LoadGame>,
     mut board_state: ResMut<BoardState>,
 ) {
     let e = trigger.event();
     board_state.set_board(&e.board, e.half_move_clock, e.full_move_count);
+    for r#move in &e.moves {
+        let promotion = r#move.get_promotion().map(PieceType);
+        board_state.make_board_move(
+            r#move.get_source().into(),
+            r#move.get_dest().into(),
+            promotion,
+        );
+    }
+    board_state.sync_status();
 }
 
 //==================================================
@@ -98,25 +122,30 @@ impl BoardState {
     }
 
     pub fn is_game_over(&self) -> bool {
-        matches!(
-            self.status,
-            GameStatus::GameOverCheckmate
-                | GameStatus::GameOverStalemate
-                | GameStatus::GameOverRepetition
-                | GameStatus::GameOver50Moves
-        )
+        self.status != GameStatus::Ongoing
+    }
+
+    /// The side that lost the game, or `None` if the game is ongoing or drawn.
+    pub fn loser(&self) -> Option<PieceColor> {
+        match self.status {
+            GameStatus::GameOverCheckmate | GameStatus::GameOverTimeout => {
+                Some(self.side_to_move())
+            }
+            GameStatus::GameOverResignation(color) => Some(color),
+            _ => None,
+        }
     }
 
-    #[cfg(feature = "stockfish-player")]
     pub fn fen(&self) -> String {
-        use std::fmt::Write;
-        let mut fen = self.board.to_string();
-        fen.truncate(fen.len() - 3);
-        let half_move = self.half_move_clock;
-        let full_move = self.full_move_count;
-        write!(&mut fen, "{half_move} {full_move}")
-            .expect("Write halfmove clock & fullmove count to FEN");
-        fen
+        to_fen(&self.board, self.half_move_clock, self.full_move_count)
+    }
+
+    pub fn half_move_clock(&self) -> u8 {
+        self.half_move_clock
+    }
+
+    pub fn full_move_count(&self) -> u16 {
+        self.full_move_count
     }
 
     pub fn side_to_move(&self) -> PieceColor {
@@ -177,6 +206,23 @@ impl BoardState {
         self.board = *board;
         self.half_move_clock = half_move_clock;
         self.full_move_count = full_move_count;
+        self.piece_state_counters.clear();
+        self.piece_state_counters.insert(board.get_hash(), 1);
+        self.history.clear();
+        self.sync_status();
+    }
+
+    //------------------------------
+    // History
+    //------------------------------
+
+    pub fn history(&self) -> &MoveHistory {
+        &self.history
+    }
+
+    /// See [`MoveHistory::set_last_clock`].
+    pub fn set_last_move_clock(&mut self, clock: &ChessClock) -> bool {
+        self.history.set_last_clock(clock)
     }
 
     //------------------------------
@@ -265,22 +311,63 @@ impl BoardState {
         self.status = match self.board.status() {
             BoardStatus::Checkmate => GameStatus::GameOverCheckmate,
             BoardStatus::Stalemate => GameStatus::GameOverStalemate,
-            BoardStatus::Ongoing if self.is_game_over_50_moves() => GameStatus::GameOver50Moves,
-            BoardStatus::Ongoing if self.is_game_over_repetition() => {
-                GameStatus::GameOverRepetition
+            BoardStatus::Ongoing if self.board.is_insufficient_material() => {
+                GameStatus::GameOverInsufficientMaterial
+            }
+            BoardStatus::Ongoing if self.half_move_clock >= 150 => GameStatus::GameOver75Moves,
+            BoardStatus::Ongoing if self.repetitions() >= 5 => {
+                GameStatus::GameOverFivefoldRepetition
             }
             BoardStatus::Ongoing => GameStatus::Ongoing,
         };
     }
 
-    /// Internal helper. **Only to be used by `Self::sync_status`**.
-    fn is_game_over_50_moves(&self) -> bool {
-        self.half_move_clock >= 100
+    /// End the game with a loss on time for the side to move, or a draw if the other side can't
+    /// checkmate.
+    pub fn time_out(&mut self) {
+        self.status = if self.board.has_mating_material(!self.side_to_move()) {
+            GameStatus::GameOverTimeout
+        } else {
+            GameStatus::GameOverTimeoutVsInsufficientMaterial
+        };
+    }
+
+    /// End the game with a loss for `color`.
+    pub fn resign(&mut self, color: PieceColor) {
+        self.status = GameStatus::GameOverResignation(color);
+    }
+
+    /// End the game in a draw agreed by both sides.
+    pub fn agree_draw(&mut self) {
+        self.status = GameStatus::GameOverAgreement;
     }
 
-    /// Internal helper. **Only to be used by `Self::sync_status`**.
-    fn is_game_over_repetition(&self) -> bool {
-        self.piece_state_counters.values().copied().any(|count| count >= 3)
+    /// The draw the side to move may claim: after 50 moves without a capture or pawn move, or
+    /// when the position has occurred three times. Unlike the 75 move and fivefold repetition
+    /// rules, these draws are not automatic.
+    pub fn claimable_draw(&self) -> Option<GameStatus> {
+        if self.is_game_over() {
+            None
+        } else if self.half_move_clock >= 100 {
+            Some(GameStatus::GameOver50Moves)
+        } else if self.repetitions() >= 3 {
+            Some(GameStatus::GameOverRepetition)
+        } else {
+            None
+        }
+    }
+
+    /// End the game in a draw if one can be claimed, see [`Self::claimable_draw`]. Return whether
+    /// the claim was accepted.
+    pub fn claim_draw(&mut self) -> bool {
+        let Some(status) = self.claimable_draw() else { return false };
+        self.status = status;
+        true
+    }
+
+    /// How many times the current position has occurred.
+    fn repetitions(&self) -> u8 {
+        self.piece_state_counters.get(&self.board.get_hash()).copied().unwrap_or_default()
     }
 
     //------------------------------
@@ -319,22 +406,48 @@ impl BoardState {
         promotion: Option<PieceType>,
     ) {
         let r#move = ChessMove::new(from_sq.0, to_sq.0, promotion.map(|p| p.0));
+        let entry =
+            HistoryEntry::new(&self.board, r#move, self.half_move_clock, self.full_move_count);
+
         self.board = self.board.make_move_new(r#move);
 
+        if entry.resets_half_move_clock() {
+            self.half_move_clock = 0;
+        } else {
+            self.half_move_clock += 1;
+        }
+
+        if entry.piece.color == PieceColor::BLACK {
+            self.full_move_count += 1;
+        }
+
         let hash = self.board.get_hash();
         *self.piece_state_counters.entry(hash).or_default() += 1;
-    }
 
-    pub fn inc_half_move_clock(&mut self) {
-        self.half_move_clock += 1;
+        self.history.push(entry);
     }
 
-    pub fn reset_half_move_clock(&mut self) {
-        self.half_move_clock = 0;
-    }
+    /// Take back the last move, restoring the board, move trackers, and repetition counters to
+    /// what they were before it was made. Return the history entry of the move.
+    pub fn undo_board_move(&mut self) -> Option<&HistoryEntry> {
+        let hash = self.board.get_hash();
+        let entry = self.history.undo()?;
+        let (board, half_move_clock, full_move_count) =
+            (entry.board, entry.half_move_clock, entry.full_move_count);
+
+        if let Entry::Occupied(mut counter) = self.piece_state_counters.entry(hash) {
+            *counter.get_mut() -= 1;
+            if *counter.get() == 0 {
+                counter.remove();
+            }
+        }
+
+        self.board = board;
+        self.half_move_clock = half_move_clock;
+        self.full_move_count = full_move_count;
+        self.sync_status();
 
-    pub fn inc_full_move_count(&mut self) {
-        self.full_move_count += 1;
+        self.history.next_redo()
     }
 
     pub fn move_is_en_passant(&self, color: PieceColor, to_sq: Square) -> bool {
@@ -385,12 +498,39 @@ pub enum GameStatus {
     Ongoing,
     GameOverCheckmate,
     GameOverStalemate,
+    /// A draw claimed after 50 moves without a capture or pawn move.
     GameOver50Moves,
+    /// A draw claimed on the third occurrence of a position.
     GameOverRepetition,
+    /// An automatic draw after 75 moves without a capture or pawn move.
+    GameOver75Moves,
+    /// An automatic draw on the fifth occurrence of a position.
+    GameOverFivefoldRepetition,
+    /// A draw agreed by both sides.
+    GameOverAgreement,
+    /// A draw because neither side has the material to checkmate.
+    GameOverInsufficientMaterial,
+    /// The side to move ran out of time.
+    GameOverTimeout,
+    /// A draw because the side to move ran out of time, but the other side can't checkmate.
+    GameOverTimeoutVsInsufficientMaterial,
+    /// This side resigned.
+    GameOverResignation(PieceColor),
 }
 
+/// The light squares of the board, i.e. b1, d1, ..., a2, c2, ...
+const LIGHT_SQUARES: BitBoard = BitBoard(0x55AA_55AA_55AA_55AA);
+
 pub trait ChessBoardExts {
     fn get_piece_meta(&self, square: Square) -> Option<PieceMeta>;
+
+    /// Whether neither side can checkmate by any sequence of legal moves: only kings and a single
+    /// knight or bishop are left, or only kings and bishops that are all on squares of one color.
+    fn is_insufficient_material(&self) -> bool;
+
+    /// Whether `color` can checkmate by some sequence of legal moves, even with the help of the
+    /// other side. A lone king or a single minor piece against a lone king can't.
+    fn has_mating_material(&self, color: PieceColor) -> bool;
 }
 
 impl ChessBoardExts for chess::Board {
@@ -402,6 +542,40 @@ impl ChessBoardExts for chess::Board {
             _ => None,
         }
     }
+
+    fn is_insufficient_material(&self) -> bool {
+        let majors_and_pawns =
+            self.pieces(Piece::Pawn) | self.pieces(Piece::Rook) | self.pieces(Piece::Queen);
+        if majors_and_pawns != EMPTY {
+            return false;
+        }
+
+        let knights = *self.pieces(Piece::Knight);
+        let bishops = *self.pieces(Piece::Bishop);
+        (knights | bishops).popcnt() <= 1
+            || (knights == EMPTY
+                && (bishops & LIGHT_SQUARES == EMPTY || bishops & !LIGHT_SQUARES == EMPTY))
+    }
+
+    fn has_mating_material(&self, color: PieceColor) -> bool {
+        let kings = *self.pieces(Piece::King);
+        let own = self.color_combined(color.0) & !kings;
+        let other = self.color_combined(!color.0) & !kings;
+        if own == EMPTY || self.is_insufficient_material() {
+            return false;
+        }
+
+        let majors_and_pawns =
+            self.pieces(Piece::Pawn) | self.pieces(Piece::Rook) | self.pieces(Piece::Queen);
+        let own_bishops = own & self.pieces(Piece::Bishop);
+        let same_colored_bishops = own_bishops == own
+            && (own_bishops & LIGHT_SQUARES == EMPTY || own_bishops & !LIGHT_SQUARES == EMPTY);
+        // A single minor piece, or bishops on one color, can only mate with the other side's
+        // pieces blocking its king in
+        own & majors_and_pawns != EMPTY
+            || (own.popcnt() >= 2 && !same_colored_bishops)
+            || other != EMPTY
+    }
 }
 
 #[allow(dead_code)]
@@ -470,3 +644,45 @@ impl ChessBoardExtsDebug for chess::BoardBuilder {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(fen: &str) -> Board {
+        Board::from_str(fen).unwrap()
+    }
+
+    #[test]
+    fn detects_insufficient_material() {
+        for fen in [
+            "8/8/4k3/8/8/3K4/8/8 w - - 0 1",
+            "8/8/4k3/8/8/3KB3/8/8 w - - 0 1",
+            "8/8/4kn2/8/8/3K4/8/8 w - - 0 1",
+            // Bishops on dark squares only
+            "8/8/4k3/8/5b2/3KB3/8/8 w - - 0 1",
+        ] {
+            assert!(board(fen).is_insufficient_material(), "{fen}");
+        }
+
+        for fen in [
+            "8/8/4k3/8/8/3K4/4P3/8 w - - 0 1",
+            "8/8/4kn2/8/8/3KN3/8/8 w - - 0 1",
+            // Bishops on squares of both colors
+            "8/8/4k3/3b4/8/3KB3/8/8 w - - 0 1",
+        ] {
+            assert!(!board(fen).is_insufficient_material(), "{fen}");
+        }
+    }
+
+    #[test]
+    fn detects_mating_material() {
+        let knight_vs_pawn = board("8/8/4k3/4p3/8/3KN3/8/8 w - - 0 1");
+        assert!(knight_vs_pawn.has_mating_material(PieceColor::WHITE));
+        let knight_vs_king = board("8/8/4k3/8/8/3KN3/8/8 w - - 0 1");
+        assert!(!knight_vs_king.has_mating_material(PieceColor::WHITE));
+        let rook_vs_king = board("8/8/4k3/8/8/3K4/8/R7 w - - 0 1");
+        assert!(rook_vs_king.has_mating_material(PieceColor::WHITE));
+        assert!(!rook_vs_king.has_mating_material(PieceColor::BLACK));
+    }
+}
@@ -0,0 +1,271 @@
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::{color::Srgba, prelude::*};
+use serde::{Deserialize, Deserializer, de};
+
+use crate::{game::settings::DEFAULT_THEME, utils::assets_dir};
+
+use super::{
+    COLOR_BLACK, COLOR_HIGHLIGHT, COLOR_PREMOVE, COLOR_WHITE, CoordinateMarker, HighlightTile,
+    HintImage, PieceMeta, PremoveTile, PromotionPiece, Square, Tile,
+};
+
+/// The directory in the assets folder with a directory for each theme.
+const THEMES_DIR: &str = "themes";
+
+/// The name of the manifest file in a theme's directory.
+const THEME_FILE_NAME: &str = "theme.toml";
+
+/// The board colors and piece set, read from `assets/themes/<name>/theme.toml`.
+///
+/// Colors are written as hex strings, e.g. `"#eeeed2"` or `"#ffff0080"`, and paths are relative to
+/// the assets folder. Anything missing from the manifest is taken from the default theme.
+#[derive(Clone, Debug, PartialEq, Resource, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    /// The name of the theme, i.e. its directory.
+    #[serde(skip)]
+    pub name: String,
+    /// The color of the light squares, and of the coordinates on the dark squares.
+    #[serde(deserialize_with = "hex_color")]
+    pub light: Color,
+    /// The color of the dark squares, and of the coordinates on the light squares.
+    #[serde(deserialize_with = "hex_color")]
+    pub dark: Color,
+    /// An image drawn over the light squares.
+    pub light_texture: Option<String>,
+    /// An image drawn over the dark squares.
+    pub dark_texture: Option<String>,
+    /// The color of the selected square and the last move.
+    #[serde(deserialize_with = "hex_color")]
+    pub highlight: Color,
+    #[serde(deserialize_with = "hex_color")]
+    pub premove: Color,
+    /// The tint of the move and capture hints.
+    #[serde(deserialize_with = "hex_color")]
+    pub hint: Color,
+    /// The directory with an image for each piece, e.g. `white-pawn.png`, which are also used for
+    /// the captured pieces.
+    pub pieces: String,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            name: DEFAULT_THEME.to_string(),
+            light: COLOR_WHITE,
+            dark: COLOR_BLACK,
+            light_texture: None,
+            dark_texture: None,
+            highlight: COLOR_HIGHLIGHT,
+            premove: COLOR_PREMOVE,
+            hint: Color::WHITE,
+            pieces: "images/pieces".to_string(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ThemeError {
+    Io(io::Error),
+    Toml(toml::de::Error),
+}
+
+impl fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "failed to read theme: {err}"),
+            Self::Toml(err) => write!(f, "invalid theme: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ThemeError {}
+
+impl Theme {
+    /// Read the theme named `name` from the themes directory.
+    ///
+    /// The default theme is built in, so it doesn't need a manifest.
+    pub fn load(name: &str) -> Result<Self, ThemeError> {
+        let path = themes_dir().join(name).join(THEME_FILE_NAME);
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound && name == DEFAULT_THEME => {
+                return Ok(default());
+            }
+            Err(err) => return Err(ThemeError::Io(err)),
+        };
+        let theme = toml::from_str(&contents).map_err(ThemeError::Toml)?;
+        Ok(Self { name: name.to_string(), ..theme })
+    }
+
+    /// Read the theme named `name`, or the default theme if it can't be read.
+    pub fn load_or_default(name: &str) -> Self {
+        Self::load(name).unwrap_or_else(|err| {
+            warn!(name, %err, "Failed to load theme, using the default theme");
+            default()
+        })
+    }
+
+    pub fn piece_path(&self, piece: PieceMeta) -> String {
+        format!("{}/{}", self.pieces, piece.file_name())
+    }
+
+    pub fn square_color(&self, square: Square) -> Color {
+        if is_light(square) { self.light } else { self.dark }
+    }
+
+    pub fn square_texture(&self, square: Square) -> Option<&str> {
+        if is_light(square) { self.light_texture.as_deref() } else { self.dark_texture.as_deref() }
+    }
+
+    /// The color of the file and rank labels on `square`.
+    pub fn coordinate_color(&self, square: Square) -> Color {
+        if is_light(square) { self.dark } else { self.light }
+    }
+}
+
+fn is_light(square: Square) -> bool {
+    (square.get_rank().to_index() + square.get_file().to_index()) % 2 == 1
+}
+
+fn hex_color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+    let hex = String::deserialize(deserializer)?;
+    Srgba::hex(&hex).map(Color::from).map_err(de::Error::custom)
+}
+
+fn themes_dir() -> PathBuf {
+    assets_dir().join(THEMES_DIR)
+}
+
+/// The names of the themes that can be chosen, i.e. the default theme and the directories in the
+/// themes directory with a manifest.
+#[derive(Clone, Debug, Deref, Resource)]
+pub struct Themes(Vec<String>);
+
+impl Default for Themes {
+    fn default() -> Self {
+        Self::discover(&themes_dir())
+    }
+}
+
+impl Themes {
+    fn discover(dir: &Path) -> Self {
+        let mut names = match fs::read_dir(dir) {
+            Ok(entries) => entries
+                .filter_map(Result::ok)
+                .filter(|entry| entry.path().join(THEME_FILE_NAME).is_file())
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .collect(),
+            Err(err) => {
+                debug!(dir = %dir.display(), %err, "No themes directory");
+                Vec::new()
+            }
+        };
+        names.sort();
+        if !names.iter().any(|name| name == DEFAULT_THEME) {
+            names.insert(0, DEFAULT_THEME.to_string());
+        }
+        debug!(?names, "Discover themes");
+        Self(names)
+    }
+}
+
+/// Recolor the board for the current theme.
+pub fn apply_board_theme(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    asset_server: Res<AssetServer>,
+    mut q_tiles: Query<(Entity, &Square, &mut BackgroundColor), With<Tile>>,
+    mut q_highlights: Query<
+        (&mut BackgroundColor, Has<PremoveTile>),
+        (Or<(With<HighlightTile>, With<PremoveTile>)>, Without<Tile>),
+    >,
+    mut q_hints: Query<&mut ImageNode, With<HintImage>>,
+    mut q_coordinates: Query<(&ChildOf, &mut TextColor), With<CoordinateMarker>>,
+) {
+    debug!(name = theme.name, "Apply theme");
+
+    for (entity, &square, mut bg) in &mut q_tiles {
+        bg.0 = theme.square_color(square);
+        match theme.square_texture(square) {
+            Some(path) => commands.entity(entity).insert(ImageNode::new(asset_server.load(path))),
+            None => commands.entity(entity).remove::<ImageNode>(),
+        };
+    }
+    for (mut bg, is_premove) in &mut q_highlights {
+        bg.0 = if is_premove { theme.premove } else { theme.highlight };
+    }
+    for mut image in &mut q_hints {
+        image.color = theme.hint;
+    }
+    for (child_of, mut color) in &mut q_coordinates {
+        if let Ok((_, &square, _)) = q_tiles.get(child_of.parent()) {
+            color.0 = theme.coordinate_color(square);
+        }
+    }
+}
+
+/// Swap the piece images for those of the current theme.
+pub fn apply_piece_theme(
+    theme: Res<Theme>,
+    asset_server: Res<AssetServer>,
+    mut q_pieces: Query<(&PieceMeta, &mut ImageNode)>,
+    mut q_promotion_pieces: Query<(&PromotionPiece, &mut ImageNode), Without<PieceMeta>>,
+) {
+    for (&piece, mut image) in &mut q_pieces {
+        image.image = asset_server.load(theme.piece_path(piece));
+    }
+    for (piece, mut image) in &mut q_promotion_pieces {
+        image.image = asset_server.load(theme.piece_path(**piece));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::board::{PieceColor, PieceType};
+
+    use super::*;
+
+    #[test]
+    fn reads_theme_manifests() {
+        let theme: Theme = toml::from_str(
+            r##"
+                light = "#f0d9b5"
+                dark = "#b58863"
+                highlight = "#cdd26a80"
+                pieces = "themes/wood/pieces"
+            "##,
+        )
+        .unwrap();
+        assert_eq!(
+            theme,
+            Theme {
+                light: Srgba::rgb_u8(0xf0, 0xd9, 0xb5).into(),
+                dark: Srgba::rgb_u8(0xb5, 0x88, 0x63).into(),
+                highlight: Srgba::rgba_u8(0xcd, 0xd2, 0x6a, 0x80).into(),
+                pieces: "themes/wood/pieces".to_string(),
+                ..default()
+            }
+        );
+        assert_eq!(
+            theme.piece_path(PieceMeta::new(PieceColor::WHITE, PieceType::KNIGHT)),
+            "themes/wood/pieces/white-knight.png"
+        );
+
+        assert!(toml::from_str::<Theme>("light = \"beige\"").is_err());
+    }
+
+    #[test]
+    fn discovers_shipped_themes() {
+        let themes = Themes::default();
+        assert_eq!(themes[0], DEFAULT_THEME);
+        for name in themes.iter() {
+            let theme = Theme::load(name).unwrap();
+            assert_eq!(&theme.name, name);
+        }
+    }
+}
@@ -3,15 +3,22 @@ use chess::{File, Rank};
 
 use crate::{
     debug_name_f,
-    game::consts::{FONT_PATH, Z_TILE},
+    game::{
+        consts::{FONT_PATH, Z_TILE},
+        layout::ScaledFont,
+    },
 };
 
-use super::{BoardState, Square, UiBoard};
+use super::{BoardOrientation, BoardState, PieceColor, Square, Theme, UiBoard};
 
 #[derive(Component)]
 pub struct Tile;
 
-/// The "black" bord color.
+/// A file or rank label, which is shown on the edge of the board when this side is at the bottom.
+#[derive(Component, Deref)]
+pub struct CoordinateMarker(PieceColor);
+
+/// The "black" bord color of the default theme.
 ///
 /// `#769656`
 pub const COLOR_BLACK: Color = Color::srgb(
@@ -20,7 +27,7 @@ pub const COLOR_BLACK: Color = Color::srgb(
     0x56 as f32 / u8::MAX as f32,
 );
 
-/// The "white" bord color.
+/// The "white" bord color of the default theme.
 ///
 /// `#eeeed2`
 pub const COLOR_WHITE: Color = Color::srgb(
@@ -32,18 +39,13 @@ pub const COLOR_WHITE: Color = Color::srgb(
 pub fn spawn_tiles(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
+    theme: Res<Theme>,
     mut board_state: ResMut<BoardState>,
     q_board: Query<Entity, With<UiBoard>>,
 ) {
     let board = q_board.single().unwrap();
 
     for square in chess::ALL_SQUARES.map(Square::new) {
-        let file = square.get_file();
-        let rank = square.get_rank();
-
-        let file_rank_sum = rank.to_index() + file.to_index();
-        let color = if file_rank_sum % 2 == 0 { COLOR_BLACK } else { COLOR_WHITE };
-
         let tile_entity = commands
             .spawn((
                 Tile,
@@ -55,7 +57,7 @@ pub fn spawn_tiles(
                     height: Val::Percent(100.0 / 8.0),
                     ..default()
                 },
-                BackgroundColor(color),
+                BackgroundColor(theme.square_color(square)),
                 GlobalZIndex(Z_TILE),
             ))
             .with_children(|cmds| {
@@ -66,12 +68,18 @@ pub fn spawn_tiles(
                     font: asset_server.load(FONT_PATH),
                     ..default()
                 };
-                let text_color =
-                    TextColor(if file_rank_sum % 2 == 0 { COLOR_WHITE } else { COLOR_BLACK });
+                let text_color = TextColor(theme.coordinate_color(square));
 
-                // File markers
-                if square.get_rank() == Rank::First {
+                // File markers, along the bottom edge
+                for (rank, color) in
+                    [(Rank::First, PieceColor::WHITE), (Rank::Eighth, PieceColor::BLACK)]
+                {
+                    if square.get_rank() != rank {
+                        continue;
+                    }
                     cmds.spawn((
+                        CoordinateMarker(color),
+                        ScaledFont(BOARD_TEXT_FONT_SIZE),
                         Text(square.file_char().to_string()),
                         text_font.clone(),
                         text_color,
@@ -84,9 +92,14 @@ pub fn spawn_tiles(
                     ));
                 }
 
-                // Rank markers
-                if square.get_file() == File::A {
+                // Rank markers, along the left edge
+                for (file, color) in [(File::A, PieceColor::WHITE), (File::H, PieceColor::BLACK)] {
+                    if square.get_file() != file {
+                        continue;
+                    }
                     cmds.spawn((
+                        CoordinateMarker(color),
+                        ScaledFont(BOARD_TEXT_FONT_SIZE),
                         Text(square.rank_char().to_string()),
                         text_font.clone(),
                         text_color,
@@ -101,7 +114,20 @@ pub fn spawn_tiles(
             })
             .id();
 
+        if let Some(path) = theme.square_texture(square) {
+            commands.entity(tile_entity).insert(ImageNode::new(asset_server.load(path)));
+        }
         commands.entity(board).add_child(tile_entity);
         board_state.set_tile(square, tile_entity);
     }
 }
+
+/// Show the file and rank labels of the edges that are at the bottom and left of the board.
+pub fn orient_coordinates(
+    orientation: Res<BoardOrientation>,
+    mut q_markers: Query<(&CoordinateMarker, &mut Node)>,
+) {
+    for (marker, mut node) in &mut q_markers {
+        node.display = if **marker == **orientation { Display::Flex } else { Display::None };
+    }
+}
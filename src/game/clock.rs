@@ -0,0 +1,503 @@
+use std::{fmt, str::FromStr, time::Duration};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+
+use crate::{cli::CliArgs, utils::NoopExts};
+
+use super::{
+    LoadGame,
+    audio::PlayGameAudio,
+    board::{BoardState, MovePieceCompleted, PieceColor},
+    consts::COLOR_PANEL,
+    game_over::GameOver,
+    menu::MenuState,
+    panels::ClockLabel,
+    stockfish::{GoParams, StockfishOpponent},
+};
+
+pub struct ClockPlugin;
+
+impl Plugin for ClockPlugin {
+    fn build(&self, app: &mut App) {
+        app.noop()
+            // Resources
+            .init_resource::<ChessClock>()
+            .init_resource::<DefaultTimeControl>()
+            // Observers
+            .add_observer(reset_clock_on_load_game)
+            .add_observer(press_clock_on_move)
+            // Systems
+            .add_systems(Startup, init_clock_from_cli)
+            .add_systems(
+                Update,
+                apply_default_time_control.run_if(resource_changed::<DefaultTimeControl>),
+            )
+            .add_systems(
+                Update,
+                (tick_clock.run_if(in_state(MenuState::Game)), update_clock_labels).chain(),
+            )
+            .noop();
+    }
+}
+
+const CLOCK_COLOR_RUNNING: Color = Color::WHITE;
+
+/// `#989795`
+const CLOCK_TEXT_COLOR_IDLE: Color = Color::srgb_u8(0x98, 0x97, 0x95);
+
+/// Clocks show tenths of a second when there is less time than this left.
+const CLOCK_TENTHS_THRESHOLD: Duration = Duration::from_secs(10);
+
+/// The low time sound is played when a clock runs down past this.
+const CLOCK_LOW_TIME_THRESHOLD: Duration = Duration::from_secs(20);
+
+/// The time each side gets to play its moves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeControl {
+    /// The time on the clocks at the start of the game, and added at the start of each period.
+    pub base: Duration,
+    /// The time added to a side's clock after each of its moves.
+    pub increment: Duration,
+    /// How long a side may think before its clock starts running down, on each move.
+    pub delay: Duration,
+    /// The number of moves in each period, after which `base` is added again, or `None` if the
+    /// whole game is one period.
+    pub moves_to_go: Option<u32>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseTimeControlError(String);
+
+impl fmt::Display for ParseTimeControlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid time control '{}', expected [MOVES/]MINUTES[+INCREMENT|dDELAY], e.g. 5+3",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParseTimeControlError {}
+
+impl FromStr for TimeControl {
+    type Err = ParseTimeControlError;
+
+    /// Parse a time control like `5+3` (5 minutes with a 3 second increment), `3d2` (3 minutes
+    /// with a 2 second delay), or `40/90+30` (90 minutes for 40 moves with a 30 second increment).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseTimeControlError(s.to_string());
+        let secs = |value: &str| -> Result<Duration, ParseTimeControlError> {
+            let secs: f32 = value.parse().map_err(|_| err())?;
+            Duration::try_from_secs_f32(secs).map_err(|_| err())
+        };
+
+        let (moves_to_go, rest) = match s.split_once('/') {
+            Some((moves, rest)) => (Some(moves.parse().map_err(|_| err())?), rest),
+            None => (None, s),
+        };
+        if moves_to_go == Some(0) {
+            return Err(err());
+        }
+
+        let (minutes, increment, delay) = if let Some((minutes, increment)) = rest.split_once('+') {
+            (minutes, secs(increment)?, Duration::ZERO)
+        } else if let Some((minutes, delay)) = rest.split_once('d') {
+            (minutes, Duration::ZERO, secs(delay)?)
+        } else {
+            (rest, Duration::ZERO, Duration::ZERO)
+        };
+        let base = secs(minutes)? * 60;
+        if base.is_zero() {
+            return Err(err());
+        }
+
+        Ok(Self { base, increment, delay, moves_to_go })
+    }
+}
+
+impl fmt::Display for TimeControl {
+    /// Format the time control as it is parsed, e.g. `5+3`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(moves_to_go) = self.moves_to_go {
+            write!(f, "{moves_to_go}/")?;
+        }
+        write!(f, "{}", self.base.as_secs_f32() / 60.0)?;
+        if !self.increment.is_zero() {
+            write!(f, "+{}", self.increment.as_secs_f32())?;
+        } else if !self.delay.is_zero() {
+            write!(f, "d{}", self.delay.as_secs_f32())?;
+        }
+        Ok(())
+    }
+}
+
+/// Time controls are written as they are parsed, e.g. in the settings file.
+impl Serialize for TimeControl {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for TimeControl {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+    }
+}
+
+/// The time control of new games, or `None` to play without clocks.
+#[derive(Clone, Copy, Debug, Default, Deref, PartialEq, Eq, Resource)]
+pub struct DefaultTimeControl(pub Option<TimeControl>);
+
+/// The clocks of both sides.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Resource)]
+pub struct ChessClock {
+    time_control: Option<TimeControl>,
+    /// The time left of white and black, by color index.
+    remaining: [Duration; 2],
+    /// The moves each side has played in the current period.
+    period_moves: [u32; 2],
+    /// The delay left before the running clock starts running down.
+    delay_left: Duration,
+}
+
+impl ChessClock {
+    pub fn new(time_control: Option<TimeControl>) -> Self {
+        let mut clock = Self { time_control, ..default() };
+        clock.reset();
+        clock
+    }
+
+    pub fn remaining(&self, color: PieceColor) -> Duration {
+        self.remaining[color.0.to_index()]
+    }
+
+    /// Set both clocks to the start of the game.
+    pub fn reset(&mut self) {
+        let Some(tc) = self.time_control else { return };
+        self.remaining = [tc.base; 2];
+        self.period_moves = [0; 2];
+        self.delay_left = tc.delay;
+    }
+
+    /// Run down the clock of `color` by `delta`, after the delay. Return whether it ran out.
+    pub fn tick(&mut self, color: PieceColor, delta: Duration) -> bool {
+        if self.time_control.is_none() {
+            return false;
+        }
+        let delay = delta.min(self.delay_left);
+        self.delay_left -= delay;
+        let delta = delta - delay;
+        let remaining = &mut self.remaining[color.0.to_index()];
+        *remaining = remaining.saturating_sub(delta);
+        remaining.is_zero()
+    }
+
+    /// End the move of `color`: add its increment and the time of its next period, and start the
+    /// delay of the other side.
+    pub fn press(&mut self, color: PieceColor) {
+        let Some(tc) = self.time_control else { return };
+        let i = color.0.to_index();
+        self.remaining[i] += tc.increment;
+        if let Some(moves_to_go) = tc.moves_to_go {
+            self.period_moves[i] += 1;
+            if self.period_moves[i] == moves_to_go {
+                self.period_moves[i] = 0;
+                self.remaining[i] += tc.base;
+            }
+        }
+        self.delay_left = tc.delay;
+    }
+
+    /// Search parameters that let Stockfish manage the time left on the clocks, for its move as
+    /// `side_to_move`, or `None` if the game is untimed.
+    pub fn go_params(&self, side_to_move: PieceColor) -> Option<GoParams> {
+        let tc = self.time_control?;
+        let ms = |time: Duration| Some(u32::try_from(time.as_millis()).unwrap_or(u32::MAX));
+        let [wtime, btime] = self.remaining.map(ms);
+        let moves_to_go =
+            tc.moves_to_go.map(|moves| moves - self.period_moves[side_to_move.0.to_index()]);
+        Some(GoParams {
+            wtime,
+            btime,
+            winc: ms(tc.increment),
+            binc: ms(tc.increment),
+            movestogo: moves_to_go,
+            ..default()
+        })
+    }
+}
+
+/// Format a clock time as `m:ss`, or `h:mm:ss` from an hour, with tenths of a second when it is
+/// low.
+pub fn format_clock(time: Duration) -> String {
+    if time < CLOCK_TENTHS_THRESHOLD {
+        return format!("0:{:02}.{}", time.as_secs(), time.subsec_millis() / 100);
+    }
+    let secs = time.as_secs();
+    let (hours, minutes, secs) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{secs:02}")
+    } else {
+        format!("{minutes}:{secs:02}")
+    }
+}
+
+fn init_clock_from_cli(
+    cli_args: Option<Res<CliArgs>>,
+    mut default_tc: ResMut<DefaultTimeControl>,
+    mut clock: ResMut<ChessClock>,
+) {
+    if let Some(tc) = cli_args.and_then(|cli| cli.time_control) {
+        *default_tc = DefaultTimeControl(Some(tc));
+        *clock = ChessClock::new(Some(tc));
+    }
+}
+
+/// Switch to a new default time control right away if the game hasn't started, otherwise from the
+/// next game.
+fn apply_default_time_control(
+    default_tc: Res<DefaultTimeControl>,
+    board_state: Res<BoardState>,
+    mut clock: ResMut<ChessClock>,
+) {
+    if clock.time_control != **default_tc && board_state.history().entries().is_empty() {
+        *clock = ChessClock::new(**default_tc);
+    }
+}
+
+fn reset_clock_on_load_game(
+    _trigger: Trigger<LoadGame>,
+    default_tc: Res<DefaultTimeControl>,
+    mut clock: ResMut<ChessClock>,
+) {
+    *clock = ChessClock::new(**default_tc);
+}
+
+/// Record the clocks in the history and press the clock of the side that moved, except when a move
+/// is replayed: undoing it restored the clocks from before it was made and they aren't pressed
+/// again.
+fn press_clock_on_move(
+    _trigger: Trigger<MovePieceCompleted>,
+    mut board_state: ResMut<BoardState>,
+    mut clock: ResMut<ChessClock>,
+) {
+    if board_state.set_last_move_clock(&clock) {
+        clock.press(!board_state.side_to_move());
+    }
+}
+
+/// Run down the clock of the side to move once the first move has been played, and end the game
+/// when it runs out.
+fn tick_clock(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut board_state: ResMut<BoardState>,
+    mut clock: ResMut<ChessClock>,
+    opponent: Option<Res<StockfishOpponent>>,
+) {
+    if clock.time_control.is_none()
+        || board_state.is_game_over()
+        || board_state.history().entries().is_empty()
+    {
+        return;
+    }
+
+    let side_to_move = board_state.side_to_move();
+    let remaining = clock.remaining(side_to_move);
+    let flag_fall = clock.tick(side_to_move, time.delta());
+
+    // Only warn about the player's clock when playing Stockfish
+    let is_engine = opponent.and_then(|opponent| opponent.side.color()) == Some(side_to_move);
+    if remaining >= CLOCK_LOW_TIME_THRESHOLD
+        && clock.remaining(side_to_move) < CLOCK_LOW_TIME_THRESHOLD
+        && !is_engine
+    {
+        commands.queue(PlayGameAudio::LowTime);
+    }
+
+    if flag_fall {
+        debug!(%side_to_move, "Flag fall");
+        board_state.time_out();
+        commands.queue(GameOver);
+    }
+}
+
+fn update_clock_labels(
+    clock: Res<ChessClock>,
+    board_state: Res<BoardState>,
+    mut q_labels: Query<(&ClockLabel, &mut Node, &mut BackgroundColor, &Children)>,
+    mut q_text: Query<(&mut Text, &mut TextColor)>,
+) {
+    for (label, mut node, mut bg, children) in &mut q_labels {
+        if clock.time_control.is_none() {
+            node.display = Display::None;
+            continue;
+        }
+        node.display = Display::Flex;
+
+        let is_running = !board_state.is_game_over()
+            && !board_state.history().entries().is_empty()
+            && board_state.side_to_move() == **label;
+        bg.0 = if is_running { CLOCK_COLOR_RUNNING } else { COLOR_PANEL };
+
+        let Ok((mut text, mut color)) = q_text.get_mut(children[0]) else { continue };
+        text.0 = format_clock(clock.remaining(**label));
+        color.0 = if is_running { COLOR_PANEL } else { CLOCK_TEXT_COLOR_IDLE };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{
+        board::{GameStatus, RedoMove, Square, UndoMove},
+        core::{build_app, make_move},
+    };
+
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+
+    const fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn parses_time_controls() {
+        let tc = |base, increment, delay, moves_to_go| TimeControl {
+            base: secs(base),
+            increment: secs(increment),
+            delay: secs(delay),
+            moves_to_go,
+        };
+        assert_eq!("5+3".parse(), Ok(tc(300, 3, 0, None)));
+        assert_eq!("15+10".parse(), Ok(tc(900, 10, 0, None)));
+        assert_eq!("3d2".parse(), Ok(tc(180, 0, 2, None)));
+        assert_eq!("40/90+30".parse(), Ok(tc(5400, 30, 0, Some(40))));
+        assert_eq!("0.5".parse(), Ok(tc(30, 0, 0, None)));
+        for invalid in ["", "0+2", "5+", "x+3", "0/90", "5+-1"] {
+            assert!(invalid.parse::<TimeControl>().is_err(), "{invalid}");
+        }
+        for s in ["5+3", "3d2", "40/90+30", "0.5", "2+0.5"] {
+            assert_eq!(s.parse::<TimeControl>().unwrap().to_string(), s);
+        }
+    }
+
+    #[test]
+    fn formats_clock_times() {
+        assert_eq!(format_clock(secs(300)), "5:00");
+        assert_eq!(format_clock(secs(5400)), "1:30:00");
+        assert_eq!(format_clock(secs(61)), "1:01");
+        assert_eq!(format_clock(Duration::from_millis(9_870)), "0:09.8");
+    }
+
+    #[test]
+    fn applies_increment_delay_and_periods() {
+        let tc = TimeControl {
+            base: secs(60),
+            increment: secs(2),
+            delay: secs(3),
+            moves_to_go: Some(2),
+        };
+        let mut clock = ChessClock::new(Some(tc));
+
+        // The delay is used up before the clock runs down
+        assert!(!clock.tick(PieceColor::WHITE, secs(2)));
+        assert_eq!(clock.remaining(PieceColor::WHITE), secs(60));
+        assert!(!clock.tick(PieceColor::WHITE, secs(4)));
+        assert_eq!(clock.remaining(PieceColor::WHITE), secs(57));
+        clock.press(PieceColor::WHITE);
+        assert_eq!(clock.remaining(PieceColor::WHITE), secs(59));
+
+        // The second move of the period adds the base time
+        clock.press(PieceColor::WHITE);
+        assert_eq!(clock.remaining(PieceColor::WHITE), secs(121));
+
+        assert!(clock.tick(PieceColor::BLACK, secs(64)));
+        assert_eq!(clock.remaining(PieceColor::BLACK), Duration::ZERO);
+    }
+
+    #[test]
+    fn searches_with_the_time_left() {
+        assert_eq!(ChessClock::new(None).go_params(PieceColor::WHITE), None);
+
+        let tc = TimeControl {
+            base: secs(300),
+            increment: secs(3),
+            delay: Duration::ZERO,
+            moves_to_go: Some(40),
+        };
+        let mut clock = ChessClock::new(Some(tc));
+        clock.tick(PieceColor::WHITE, secs(10));
+        clock.press(PieceColor::WHITE);
+        let params = clock.go_params(PieceColor::BLACK).unwrap();
+        assert_eq!(
+            params.to_string(),
+            "go wtime 293000 btime 300000 winc 3000 binc 3000 movestogo 40"
+        );
+        assert_eq!(clock.go_params(PieceColor::WHITE).unwrap().movestogo, Some(39));
+        assert!(params.is_bounded());
+    }
+
+    #[test]
+    fn flag_fall_ends_game() {
+        let mut app = build_app(ClockPlugin);
+
+        let tc = TimeControl {
+            base: secs(60),
+            increment: secs(1),
+            delay: Duration::ZERO,
+            moves_to_go: None,
+        };
+        app.insert_resource(ChessClock::new(Some(tc)));
+        make_move(&mut app, Square::E2, Square::E4);
+
+        let clock = app.world().resource::<ChessClock>();
+        assert_eq!(clock.remaining(PieceColor::WHITE), secs(61));
+        assert_eq!(app.world().resource::<BoardState>().status(), GameStatus::Ongoing);
+
+        app.world_mut().resource_mut::<ChessClock>().remaining[PieceColor::BLACK.0.to_index()] =
+            Duration::ZERO;
+        app.update();
+        assert_eq!(app.world().resource::<BoardState>().status(), GameStatus::GameOverTimeout);
+    }
+
+    #[test]
+    fn undo_restores_clocks_and_redo_does_not_press_them() {
+        let mut app = build_app(ClockPlugin);
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::ZERO));
+        let tc = TimeControl {
+            base: secs(60),
+            increment: secs(2),
+            delay: Duration::ZERO,
+            moves_to_go: None,
+        };
+        app.insert_resource(ChessClock::new(Some(tc)));
+
+        make_move(&mut app, Square::E2, Square::E4);
+        app.world_mut().resource_mut::<ChessClock>().remaining[PieceColor::BLACK.0.to_index()] =
+            secs(50);
+        make_move(&mut app, Square::E7, Square::E5);
+        let clock = app.world().resource::<ChessClock>();
+        assert_eq!(clock.remaining(PieceColor::WHITE), secs(62));
+        assert_eq!(clock.remaining(PieceColor::BLACK), secs(52));
+
+        app.world_mut().trigger(UndoMove);
+        app.update();
+        let clock = app.world().resource::<ChessClock>();
+        assert_eq!(clock.remaining(PieceColor::WHITE), secs(62));
+        assert_eq!(clock.remaining(PieceColor::BLACK), secs(50));
+
+        app.world_mut().trigger(RedoMove);
+        app.update();
+        let clock = app.world().resource::<ChessClock>();
+        assert_eq!(clock.remaining(PieceColor::BLACK), secs(50));
+
+        app.world_mut().trigger(UndoMove);
+        app.update();
+        app.world_mut().trigger(UndoMove);
+        app.update();
+        assert_eq!(*app.world().resource::<ChessClock>(), ChessClock::new(Some(tc)));
+    }
+}
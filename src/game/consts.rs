@@ -18,6 +18,26 @@ pub const COLOR_BG: Color = Color::srgb(
     0x2b as f32 / u8::MAX as f32,
 );
 
+/// The background color of the panels around the board, e.g. the move list.
+///
+/// `#262421`
+pub const COLOR_PANEL: Color = Color::srgb_u8(0x26, 0x24, 0x21);
+
+/// The background color of every other row of a panel.
+///
+/// `#2b2927`
+pub const COLOR_PANEL_ROW_ODD: Color = Color::srgb_u8(0x2b, 0x29, 0x27);
+
+/// The background color of a panel's header and highlighted entries.
+///
+/// `#484644`
+pub const COLOR_PANEL_HIGHLIGHT: Color = Color::srgb_u8(0x48, 0x46, 0x44);
+
+/// The color of the text in a panel.
+///
+/// `#c0c0bf`
+pub const COLOR_PANEL_TEXT: Color = Color::srgb_u8(0xc0, 0xc0, 0xbf);
+
 pub const FONT_PATH: &str = "fonts/montserrat-700.otf";
 
 pub const TITLE_FONT_PATH: &str = "fonts/montserrat-800.otf";
@@ -29,12 +49,12 @@ pub const DEFAULT_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQk
 //==================================================
 
 #[cfg(not(feature = "debug"))]
-pub const INIT_WIN_WIDTH: f32 = 698.0;
+pub const INIT_WIN_WIDTH: f32 = 698.0 + UI_GAP + MOVE_LIST_WIDTH;
 #[cfg(not(feature = "debug"))]
 pub const INIT_WIN_HEIGHT: f32 = 750.0;
 
 #[cfg(feature = "debug")]
-pub const INIT_WIN_WIDTH: f32 = 698.0 * 2.0;
+pub const INIT_WIN_WIDTH: f32 = (698.0 + UI_GAP + MOVE_LIST_WIDTH) * 2.0;
 #[cfg(feature = "debug")]
 pub const INIT_WIN_HEIGHT: f32 = 750.0;
 
@@ -43,6 +63,8 @@ pub const UI_GAP_VAL: Val = Val::Px(UI_GAP);
 
 pub const CAPTURES_PANEL_HEIGHT: f32 = 32.0;
 
+pub const MOVE_LIST_WIDTH: f32 = 176.0;
+
 pub const MENU_WIDTH_RATIO: f32 = 544.0 / INIT_WIN_WIDTH;
 pub const MENU_HEIGHT_RATIO: f32 = 448.0 / INIT_WIN_HEIGHT;
 
@@ -55,6 +77,13 @@ pub const INIT_MENU_BUTTON_TEXT_SIZE: f32 = 48.0;
 
 pub const MIN_BOARD_SIZE: Val = Val::Px(256.0);
 
+pub const EVAL_BAR_WIDTH: f32 = 20.0;
+
+/// The size of the board in the initial window, which fonts that scale with the board are sized
+/// for.
+pub const BOARD_REFERENCE_SIZE: f32 =
+    INIT_WIN_HEIGHT - 2.0 * UI_GAP - 2.0 * (CAPTURES_PANEL_HEIGHT + UI_GAP);
+
 //==================================================
 // Z-Values
 //==================================================
@@ -69,6 +98,8 @@ pub const Z_PROMOTER: i32 = 16;
 
 pub const Z_END_GAME_ICONS: i32 = 15;
 
+pub const Z_ARROW: i32 = 12;
+
 // Pieces --------------------
 
 pub const Z_PIECE_SELECTED: i32 = 11;
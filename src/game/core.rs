@@ -1,6 +1,5 @@
 use bevy::{
     app::{App, Plugin},
-    prelude::AssetApp,
     utils::default,
     window::{Window, WindowResolution},
 };
@@ -62,10 +61,53 @@ impl Plugin for GameHeadPlugin {
     }
 }
 
+#[cfg(test)]
 pub struct GameTestPlugin;
 
+#[cfg(test)]
 impl Plugin for GameTestPlugin {
     fn build(&self, app: &mut App) {
+        use bevy::prelude::AssetApp;
+
         app.add_plugins(bevy::prelude::ImagePlugin::default()).init_asset::<bevy::text::Font>();
     }
 }
+
+/// Build an app that has loaded a default game, with the board, selection, moves and history, and
+/// `plugins` for the module under test.
+#[cfg(test)]
+pub fn build_app<M>(plugins: impl bevy::app::Plugins<M>) -> App {
+    use crate::game::{
+        LoadGame,
+        board::{HistoryPlugin, MovePlugin, SelectionPlugin},
+        menu::test::TestMenuStateInGamePlugin,
+        ui::GameUiPlugin,
+    };
+
+    let mut app = App::new();
+    app.add_plugins((GameHeadlessPlugin, GameTestPlugin))
+        .add_plugins(TestMenuStateInGamePlugin)
+        .add_plugins(MovePlugin)
+        .add_plugins(HistoryPlugin)
+        .add_plugins(GameUiPlugin)
+        .add_plugins(SelectionPlugin)
+        .add_plugins(plugins);
+    app.update();
+    app.world_mut().trigger(LoadGame::in_game_default());
+    app.update();
+    app
+}
+
+/// Move the piece on `from_sq` to `to_sq` and run a frame.
+#[cfg(test)]
+pub fn make_move(
+    app: &mut App,
+    from_sq: crate::game::board::Square,
+    to_sq: crate::game::board::Square,
+) {
+    use crate::game::board::{BoardState, MovePiece};
+
+    let piece = app.world().resource::<BoardState>().piece(from_sq);
+    app.world_mut().trigger_targets(MovePiece::new(from_sq, to_sq, None, false), piece);
+    app.update();
+}
@@ -3,7 +3,10 @@ use bevy::prelude::*;
 use crate::{
     debug_name,
     game::{
-        consts::{CAPTURES_PANEL_HEIGHT, MIN_BOARD_SIZE},
+        LoadGame,
+        board::{BoardOrientation, PieceColor},
+        consts::{CAPTURES_PANEL_HEIGHT, EVAL_BAR_WIDTH, FONT_PATH, MIN_BOARD_SIZE},
+        stockfish::Score,
         ui::EvaluationBarContainer,
     },
     utils::{NoopExts, ReparentInTag},
@@ -17,48 +20,141 @@ impl Plugin for EvaluationBarPlugin {
     fn build(&self, app: &mut App) {
         app.noop()
             .add_event::<EvaluationUpdate>()
+            .init_resource::<Evaluation>()
+            .add_observer(reset_evaluation_on_load_game)
             .add_systems(Startup, spawn_eval_bar.after(spawn_ui))
-            .add_systems(Update, update_eval_bar)
+            .add_systems(
+                Update,
+                (
+                    update_evaluation,
+                    (update_eval_bar_label, update_eval_bar_anchor).run_if(
+                        resource_changed::<Evaluation>.or(resource_changed::<BoardOrientation>),
+                    ),
+                    animate_eval_bar,
+                )
+                    .chain(),
+            )
             .noop();
     }
 }
 
+/// `#403d39`
+const EVAL_BAR_COLOR_BLACK: Color = Color::srgb_u8(0x40, 0x3d, 0x39);
+
+const EVAL_BAR_COLOR_WHITE: Color = Color::WHITE;
+
+const EVAL_BAR_LABEL_FONT_SIZE: f32 = 9.0;
+
+/// How quickly the bar moves towards a new evaluation, per second.
+const EVAL_BAR_ANIMATION_SPEED: f32 = 8.0;
+
+/// The coefficient of the logistic curve that maps centipawns to a win probability.
+///
+/// See [Lichess's accuracy metric][1].
+///
+/// [1]: https://lichess.org/page/accuracy
+const WIN_PROBABILITY_COEFFICIENT: f32 = 0.003_682_08;
+
 #[derive(Component)]
 pub struct EvaluationBar;
 
+#[derive(Component)]
+pub struct EvaluationBarLabel;
+
+/// The evaluation of the live position from white's point of view.
 #[derive(Event)]
-pub struct EvaluationUpdate(pub f32);
+pub struct EvaluationUpdate(pub Score);
+
+/// The evaluation shown by the bar, from white's point of view.
+#[derive(Clone, Copy, Debug, Resource)]
+pub struct Evaluation(pub Score);
+
+impl Default for Evaluation {
+    fn default() -> Self {
+        Self(Score::Centipawns(0))
+    }
+}
+
+impl Evaluation {
+    /// The share of the bar that is white, from 0 to 100, i.e. white's chance of winning.
+    pub fn white_percent(self) -> f32 {
+        match self.0 {
+            Score::Centipawns(cp) => {
+                let win_chance =
+                    2.0 / (1.0 + (-WIN_PROBABILITY_COEFFICIENT * cp as f32).exp()) - 1.0;
+                50.0 + 50.0 * win_chance
+            }
+            Score::Mate(moves) if moves > 0 => 100.0,
+            Score::Mate(_) => 0.0,
+        }
+    }
+
+    /// The side that is ahead, which is white when the position is even.
+    pub fn leader(self) -> PieceColor {
+        match self.0 {
+            Score::Centipawns(cp) if cp >= 0 => PieceColor::WHITE,
+            Score::Mate(moves) if moves > 0 => PieceColor::WHITE,
+            _ => PieceColor::BLACK,
+        }
+    }
+
+    /// The evaluation for the side that is ahead, e.g. `1.5` or `M3`.
+    pub fn label(self) -> String {
+        match self.0 {
+            Score::Centipawns(cp) => format!("{:.1}", cp.unsigned_abs() as f32 / 100.0),
+            Score::Mate(moves) => format!("M{}", moves.unsigned_abs()),
+        }
+    }
+}
 
-pub fn spawn_eval_bar(mut commands: Commands) {
+pub fn spawn_eval_bar(mut commands: Commands, asset_server: Res<AssetServer>) {
     const SPACER_H: Val = Val::Px(CAPTURES_PANEL_HEIGHT);
     let spacer_bundle = || Node { height: SPACER_H, flex_shrink: 0.0, ..default() };
 
     let spacer_top =
         commands.spawn((debug_name!("Evaluation Bar Spacer (Top)"), spacer_bundle())).id();
 
+    let font = asset_server.load(FONT_PATH);
+
     let bar = commands
         .spawn((
             debug_name!("Evaluation Bar Background (black)"),
             Node {
                 position_type: PositionType::Relative,
-                width: Val::Px(20.0),
+                width: Val::Px(EVAL_BAR_WIDTH),
                 flex_grow: 1.0,
                 min_height: MIN_BOARD_SIZE,
                 ..default()
             },
-            BackgroundColor(Color::srgb_u8(0x40, 0x3d, 0x39)),
-            children![(
-                EvaluationBar,
-                debug_name!("Evaluation Bar (white)"),
-                Node {
-                    position_type: PositionType::Absolute,
-                    bottom: Val::Px(0.0),
-                    width: Val::Percent(100.0),
-                    height: Val::Percent(50.0),
-                    ..default()
-                },
-                BackgroundColor(Color::WHITE),
-            )],
+            BackgroundColor(EVAL_BAR_COLOR_BLACK),
+            children![
+                (
+                    EvaluationBar,
+                    debug_name!("Evaluation Bar (white)"),
+                    Node {
+                        position_type: PositionType::Absolute,
+                        bottom: Val::Px(0.0),
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(50.0),
+                        ..default()
+                    },
+                    BackgroundColor(EVAL_BAR_COLOR_WHITE),
+                ),
+                (
+                    EvaluationBarLabel,
+                    debug_name!("Evaluation Bar Label"),
+                    Node {
+                        position_type: PositionType::Absolute,
+                        bottom: Val::Px(4.0),
+                        width: Val::Percent(100.0),
+                        ..default()
+                    },
+                    Text(Evaluation::default().label()),
+                    TextFont { font, font_size: EVAL_BAR_LABEL_FONT_SIZE, ..default() },
+                    TextColor(EVAL_BAR_COLOR_BLACK),
+                    TextLayout::new_with_justify(JustifyText::Center),
+                ),
+            ],
         ))
         .id();
 
@@ -68,15 +164,115 @@ pub fn spawn_eval_bar(mut commands: Commands) {
     commands.reparent_in_tag::<EvaluationBarContainer>([spacer_top, bar, spacer_bot]);
 }
 
-pub fn update_eval_bar(
+fn reset_evaluation_on_load_game(_trigger: Trigger<LoadGame>, mut evaluation: ResMut<Evaluation>) {
+    *evaluation = default();
+}
+
+fn update_evaluation(
     mut reader: EventReader<EvaluationUpdate>,
+    mut evaluation: ResMut<Evaluation>,
+) {
+    if let Some(update) = reader.read().last() {
+        evaluation.0 = update.0;
+    }
+}
+
+/// Whether `color`'s end of the bar is at the bottom.
+fn is_at_bottom(color: PieceColor, orientation: BoardOrientation) -> bool {
+    color == orientation.0
+}
+
+/// Show the evaluation at the leading side's end of the bar.
+fn update_eval_bar_label(
+    evaluation: Res<Evaluation>,
+    orientation: Res<BoardOrientation>,
+    mut q_label: Query<(&mut Node, &mut Text, &mut TextColor), With<EvaluationBarLabel>>,
+) {
+    let Ok((mut node, mut text, mut color)) = q_label.single_mut() else { return };
+
+    let leader = evaluation.leader();
+    const INSET: Val = Val::Px(4.0);
+    if is_at_bottom(leader, *orientation) {
+        (node.top, node.bottom) = (Val::Auto, INSET);
+    } else {
+        (node.top, node.bottom) = (INSET, Val::Auto);
+    }
+
+    text.0 = evaluation.label();
+    color.0 = match leader {
+        PieceColor::WHITE => EVAL_BAR_COLOR_BLACK,
+        PieceColor::BLACK => EVAL_BAR_COLOR_WHITE,
+    };
+}
+
+/// Grow the white part of the bar from whichever end white is at.
+fn update_eval_bar_anchor(
+    orientation: Res<BoardOrientation>,
+    mut q_bar: Query<&mut Node, With<EvaluationBar>>,
+) {
+    let Ok(mut node) = q_bar.single_mut() else { return };
+
+    if is_at_bottom(PieceColor::WHITE, *orientation) {
+        (node.top, node.bottom) = (Val::Auto, Val::Px(0.0));
+    } else {
+        (node.top, node.bottom) = (Val::Px(0.0), Val::Auto);
+    }
+}
+
+fn animate_eval_bar(
+    time: Res<Time>,
+    evaluation: Res<Evaluation>,
     mut q_bar: Query<&mut Node, With<EvaluationBar>>,
 ) {
-    let Ok(mut bar_node) = q_bar.single_mut() else { return };
+    let Ok(mut node) = q_bar.single_mut() else { return };
+
+    let target = evaluation.white_percent();
+    let Val::Percent(current) = node.height else {
+        node.height = Val::Percent(target);
+        return;
+    };
+    if current == target {
+        return;
+    }
+
+    let t = 1.0 - (-EVAL_BAR_ANIMATION_SPEED * time.delta_secs()).exp();
+    let next = current + (target - current) * t;
+    node.height = Val::Percent(if (target - next).abs() < 0.05 { target } else { next });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_scores_to_win_probability() {
+        assert_eq!(Evaluation(Score::Centipawns(0)).white_percent(), 50.0);
+        assert_eq!(Evaluation(Score::Mate(3)).white_percent(), 100.0);
+        assert_eq!(Evaluation(Score::Mate(-1)).white_percent(), 0.0);
+
+        let pawn_up = Evaluation(Score::Centipawns(100)).white_percent();
+        let pawn_down = Evaluation(Score::Centipawns(-100)).white_percent();
+        assert!((pawn_up - 59.1).abs() < 0.1, "{pawn_up}");
+        assert!((pawn_up + pawn_down - 100.0).abs() < 0.001);
+
+        let big = Evaluation(Score::Centipawns(1000)).white_percent();
+        let huge = Evaluation(Score::Centipawns(2000)).white_percent();
+        assert!(big > 95.0 && huge > big && huge < 100.0);
+    }
 
-    for update in reader.read() {
-        // Map eval stat in [-10.0,10.0] to [0.0,100.0]
-        let percent = (update.0 + 10.0).clamp(0.0, 20.0) * 5.0;
-        bar_node.height = Val::Percent(percent);
+    #[test]
+    fn labels_leading_side() {
+        let labels = [
+            (Score::Centipawns(0), PieceColor::WHITE, "0.0"),
+            (Score::Centipawns(153), PieceColor::WHITE, "1.5"),
+            (Score::Centipawns(-420), PieceColor::BLACK, "4.2"),
+            (Score::Mate(3), PieceColor::WHITE, "M3"),
+            (Score::Mate(-2), PieceColor::BLACK, "M2"),
+        ];
+        for (score, leader, label) in labels {
+            let evaluation = Evaluation(score);
+            assert_eq!(evaluation.leader(), leader, "{score:?}");
+            assert_eq!(evaluation.label(), label, "{score:?}");
+        }
     }
 }
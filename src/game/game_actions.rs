@@ -0,0 +1,330 @@
+use bevy::prelude::*;
+
+use crate::utils::{NoopExts, ctrl_pressed};
+
+use super::{
+    LoadGame,
+    board::{BoardState, MovePieceCompleted, PieceColor},
+    game_over::GameOver,
+    menu::MenuState,
+    move_entry::keyboard_not_captured,
+    stockfish::{EngineAnalysis, Score, StockfishOpponent},
+};
+
+pub struct GameActionsPlugin;
+
+impl Plugin for GameActionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.noop()
+            // Resources
+            .init_resource::<DrawOffer>()
+            // Observers
+            .add_observer(handle_game_action)
+            .add_observer(clear_draw_offer_on_load_game)
+            .add_observer(expire_draw_offer_on_move)
+            // Systems
+            .add_systems(
+                Update,
+                game_action_shortcuts.run_if(in_state(MenuState::Game).and(keyboard_not_captured)),
+            )
+            .add_systems(
+                Update,
+                update_game_action_buttons.after(game_action_shortcuts).run_if(
+                    resource_changed::<BoardState>
+                        .or(resource_changed::<DrawOffer>)
+                        .or(resource_exists_and_changed::<StockfishOpponent>),
+                ),
+            )
+            .noop();
+    }
+}
+
+/// Stockfish accepts a draw offer unless it is ahead by more than this many centipawns.
+const ENGINE_DRAW_ACCEPT_CENTIPAWNS: i32 = 0;
+
+/// An action that ends the game at the request of a side rather than because of the position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Event)]
+pub enum GameAction {
+    /// This side resigns.
+    Resign(PieceColor),
+    /// This side claims a draw if it can, accepts the draw offered by the other side, or else
+    /// offers one.
+    Draw(PieceColor),
+}
+
+/// The side that offered a draw which the other side has yet to answer.
+///
+/// The offer is declined when the other side makes its move instead of accepting it.
+#[derive(Clone, Copy, Debug, Default, Deref, DerefMut, PartialEq, Eq, Resource)]
+pub struct DrawOffer(pub Option<PieceColor>);
+
+/// A panel button that triggers its action when clicked.
+#[derive(Clone, Copy, Component, Debug, Deref)]
+pub struct GameActionButton(pub GameAction);
+
+pub fn trigger_game_action_on_click(
+    trigger: Trigger<Pointer<Click>>,
+    mut commands: Commands,
+    q_buttons: Query<&GameActionButton>,
+) {
+    if let Ok(button) = q_buttons.get(trigger.target()) {
+        commands.trigger(**button);
+    }
+}
+
+/// Stockfish's evaluation of the live position from white's point of view, if it has evaluated
+/// it yet.
+fn engine_evaluation(analysis: &EngineAnalysis, board_state: &BoardState) -> Option<Score> {
+    if analysis.fen.as_deref() != Some(board_state.fen().as_str()) {
+        return None;
+    }
+    let score = analysis.lines.first()?.score?.score;
+    Some(score.for_white(board_state.side_to_move()))
+}
+
+/// Whether Stockfish, playing `color`, accepts a draw given the evaluation from white's point of
+/// view.
+fn engine_accepts_draw(color: PieceColor, evaluation: Score) -> bool {
+    // Flipping the score for black converts it to black's point of view just as well
+    match evaluation.for_white(color) {
+        Score::Centipawns(cp) => cp <= ENGINE_DRAW_ACCEPT_CENTIPAWNS,
+        Score::Mate(moves) => moves < 0,
+    }
+}
+
+fn handle_game_action(
+    trigger: Trigger<GameAction>,
+    mut commands: Commands,
+    mut board_state: ResMut<BoardState>,
+    mut draw_offer: ResMut<DrawOffer>,
+    opponent: Option<Res<StockfishOpponent>>,
+    analysis: Option<Res<EngineAnalysis>>,
+) {
+    if board_state.is_game_over() {
+        return;
+    }
+
+    match *trigger.event() {
+        GameAction::Resign(color) => {
+            debug!(%color, "Resign");
+            board_state.resign(color);
+        }
+        GameAction::Draw(color) => {
+            let engine_color = opponent.and_then(|opponent| opponent.side.color());
+            if color == board_state.side_to_move() && board_state.claim_draw() {
+                debug!(%color, status = ?board_state.status(), "Claim draw");
+            } else if **draw_offer == Some(!color) {
+                debug!(%color, "Accept draw");
+                board_state.agree_draw();
+            } else if engine_color == Some(!color) {
+                // Stockfish declines until it has evaluated the position
+                let accepts = analysis
+                    .and_then(|analysis| engine_evaluation(&analysis, &board_state))
+                    .is_some_and(|evaluation| engine_accepts_draw(!color, evaluation));
+                debug!(%color, accepts, "Offer draw to Stockfish");
+                if !accepts {
+                    return;
+                }
+                board_state.agree_draw();
+            } else {
+                debug!(%color, "Offer draw");
+                **draw_offer = Some(color);
+                return;
+            }
+        }
+    }
+
+    **draw_offer = None;
+    commands.queue(GameOver);
+}
+
+fn clear_draw_offer_on_load_game(_trigger: Trigger<LoadGame>, mut draw_offer: ResMut<DrawOffer>) {
+    **draw_offer = None;
+}
+
+fn expire_draw_offer_on_move(
+    _trigger: Trigger<MovePieceCompleted>,
+    board_state: Res<BoardState>,
+    mut draw_offer: ResMut<DrawOffer>,
+) {
+    // The offering side is to move again once the other side has made a move
+    if **draw_offer == Some(board_state.side_to_move()) {
+        **draw_offer = None;
+    }
+}
+
+/// The side that the keyboard acts for: the side that Stockfish doesn't play, or else the side to
+/// move.
+fn shortcut_side(board_state: &BoardState, opponent: Option<&StockfishOpponent>) -> PieceColor {
+    match opponent.and_then(|opponent| opponent.side.color()) {
+        Some(engine_color) => !engine_color,
+        None => board_state.side_to_move(),
+    }
+}
+
+/// Offer, accept, or claim a draw with `Ctrl+D`, and resign with `Ctrl+Shift+R`.
+fn game_action_shortcuts(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    board_state: Res<BoardState>,
+    opponent: Option<Res<StockfishOpponent>>,
+) {
+    if !ctrl_pressed(&keys) {
+        return;
+    }
+
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let color = shortcut_side(&board_state, opponent.as_deref());
+
+    if keys.just_pressed(KeyCode::KeyD) {
+        commands.trigger(GameAction::Draw(color));
+    } else if shift && keys.just_pressed(KeyCode::KeyR) {
+        commands.trigger(GameAction::Resign(color));
+    }
+}
+
+/// Hide the buttons of the side Stockfish plays and of finished games, and label the draw buttons
+/// with what they do.
+fn update_game_action_buttons(
+    board_state: Res<BoardState>,
+    draw_offer: Res<DrawOffer>,
+    opponent: Option<Res<StockfishOpponent>>,
+    mut q_buttons: Query<(&GameActionButton, &mut Node, &Children)>,
+    mut q_text: Query<&mut Text>,
+) {
+    let engine_color = opponent.and_then(|opponent| opponent.side.color());
+
+    for (button, mut node, children) in &mut q_buttons {
+        let (GameAction::Resign(color) | GameAction::Draw(color)) = **button;
+        let is_shown = !board_state.is_game_over() && engine_color != Some(color);
+        node.display = if is_shown { Display::Flex } else { Display::None };
+
+        let label = match **button {
+            GameAction::Resign(_) => "Resign",
+            GameAction::Draw(_)
+                if color == board_state.side_to_move()
+                    && board_state.claimable_draw().is_some() =>
+            {
+                "Claim draw"
+            }
+            GameAction::Draw(_) if **draw_offer == Some(!color) => "Accept draw",
+            GameAction::Draw(_) if **draw_offer == Some(color) => "Draw offered",
+            GameAction::Draw(_) => "Offer draw",
+        };
+        if let Ok(mut text) = q_text.get_mut(children[0]) {
+            text.set_if_neq(Text::new(label));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chess::ChessMove;
+
+    use crate::game::{
+        board::{GameStatus, Square},
+        core::{build_app, make_move},
+        stockfish::{EngineInfo, EngineScore, OpponentSide, ScoreBound},
+    };
+
+    use super::*;
+
+    fn status(app: &App) -> GameStatus {
+        app.world().resource::<BoardState>().status()
+    }
+
+    #[test]
+    fn resigns_and_agrees_draws() {
+        let mut app = build_app(GameActionsPlugin);
+        app.world_mut().trigger(GameAction::Resign(PieceColor::BLACK));
+        app.update();
+        assert_eq!(status(&app), GameStatus::GameOverResignation(PieceColor::BLACK));
+        assert_eq!(app.world().resource::<BoardState>().loser(), Some(PieceColor::BLACK));
+
+        let mut app = build_app(GameActionsPlugin);
+        app.world_mut().trigger(GameAction::Draw(PieceColor::WHITE));
+        app.update();
+        assert_eq!(**app.world().resource::<DrawOffer>(), Some(PieceColor::WHITE));
+        assert_eq!(status(&app), GameStatus::Ongoing);
+
+        // Black declines by moving, and the offer can't be accepted anymore
+        make_move(&mut app, Square::E2, Square::E4);
+        assert_eq!(**app.world().resource::<DrawOffer>(), Some(PieceColor::WHITE));
+        make_move(&mut app, Square::E7, Square::E5);
+        assert_eq!(**app.world().resource::<DrawOffer>(), None);
+
+        app.world_mut().trigger(GameAction::Draw(PieceColor::WHITE));
+        app.world_mut().trigger(GameAction::Draw(PieceColor::BLACK));
+        app.update();
+        assert_eq!(status(&app), GameStatus::GameOverAgreement);
+    }
+
+    #[test]
+    fn claims_threefold_repetition() {
+        let mut app = build_app(GameActionsPlugin);
+        let shuffle = [
+            (Square::G1, Square::F3),
+            (Square::G8, Square::F6),
+            (Square::F3, Square::G1),
+            (Square::F6, Square::G8),
+        ];
+
+        // Too early to claim, which offers a draw instead
+        for (from, to) in shuffle {
+            make_move(&mut app, from, to);
+        }
+        app.world_mut().trigger(GameAction::Draw(PieceColor::WHITE));
+        app.update();
+        assert_eq!(status(&app), GameStatus::Ongoing);
+
+        for (from, to) in shuffle {
+            make_move(&mut app, from, to);
+        }
+        app.world_mut().trigger(GameAction::Draw(PieceColor::WHITE));
+        app.update();
+        assert_eq!(status(&app), GameStatus::GameOverRepetition);
+    }
+
+    #[test]
+    fn stockfish_answers_draw_offers_from_its_analysis() {
+        let mut app = build_app((GameActionsPlugin, |app: &mut App| {
+            app.insert_resource(StockfishOpponent { side: OpponentSide::Black, ..default() })
+                .init_resource::<EngineAnalysis>();
+        }));
+        let analyse = |app: &mut App, fen: String, cp: i32| {
+            let mut analysis = app.world_mut().resource_mut::<EngineAnalysis>();
+            analysis.reset(fen);
+            analysis.update(&EngineInfo {
+                score: Some(EngineScore { score: Score::Centipawns(cp), bound: ScoreBound::Exact }),
+                pv: vec![ChessMove::new(chess::Square::E2, chess::Square::E4, None)],
+                ..default()
+            });
+        };
+        let offer_draw = |app: &mut App| {
+            app.world_mut().trigger(GameAction::Draw(PieceColor::WHITE));
+            app.update();
+        };
+
+        // Nothing is known about the position yet
+        offer_draw(&mut app);
+        assert_eq!(status(&app), GameStatus::Ongoing);
+
+        // White is ahead, but the analysis is of another position
+        let fen = app.world().resource::<BoardState>().fen();
+        make_move(&mut app, Square::E2, Square::E4);
+        make_move(&mut app, Square::E7, Square::E5);
+        analyse(&mut app, fen, 50);
+        offer_draw(&mut app);
+        assert_eq!(status(&app), GameStatus::Ongoing);
+
+        // Black is ahead
+        let fen = app.world().resource::<BoardState>().fen();
+        analyse(&mut app, fen.clone(), -50);
+        offer_draw(&mut app);
+        assert_eq!(status(&app), GameStatus::Ongoing);
+
+        analyse(&mut app, fen, 50);
+        offer_draw(&mut app);
+        assert_eq!(status(&app), GameStatus::GameOverAgreement);
+    }
+}
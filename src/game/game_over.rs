@@ -1,8 +1,10 @@
 use bevy::prelude::*;
 
 use super::{
+    audio::PlayGameAudio,
     board::{BoardState, GameStatus, ShowCheckmateIcons, ShowDrawIcons},
     menu::MenuState,
+    stockfish::StockfishOpponent,
 };
 
 pub struct GameOver;
@@ -11,16 +13,26 @@ impl Command for GameOver {
     fn apply(self, world: &mut World) {
         trace!("Game over");
 
-        match world.resource::<BoardState>().status() {
-            GameStatus::GameOverCheckmate => ShowCheckmateIcons.apply(world),
-            GameStatus::GameOverStalemate
-            | GameStatus::GameOver50Moves
-            | GameStatus::GameOverRepetition => ShowDrawIcons.apply(world),
-            GameStatus::Ongoing => {
+        let board_state = world.resource::<BoardState>();
+        match (board_state.status(), board_state.loser()) {
+            (GameStatus::Ongoing, _) => {
                 warn!("Running game over sequence when the game is still ongoing")
             }
+            (_, Some(loser)) => ShowCheckmateIcons { loser }.apply(world),
+            (_, None) => ShowDrawIcons.apply(world),
         }
 
+        // A win or loss is from the player's point of view when playing Stockfish, and a game
+        // between two players always has a winner to celebrate
+        let board_state = world.resource::<BoardState>();
+        let engine_color = world.get_resource::<StockfishOpponent>().and_then(|o| o.side.color());
+        let audio = match (board_state.loser(), engine_color) {
+            (None, _) => PlayGameAudio::GameDraw,
+            (Some(loser), Some(engine_color)) if loser != engine_color => PlayGameAudio::GameLose,
+            (Some(_), _) => PlayGameAudio::GameWin,
+        };
+        audio.apply(world);
+
         world.resource_mut::<NextState<MenuState>>().set(MenuState::DoGameOver);
     }
 }
@@ -0,0 +1,192 @@
+use bevy::prelude::*;
+
+use crate::{
+    debug_name_f,
+    game::{
+        board::{
+            AnnotationColor, Arrow, ArrowLayer, BoardState, DrawArrowsSystem, Square, ViewedPly,
+        },
+        menu::MenuState,
+        move_entry::keyboard_not_captured,
+        stockfish::{EngineAnalysis, StockfishOpponent},
+    },
+    utils::{NoopExts, ctrl_pressed},
+};
+
+pub struct HintPlugin;
+
+impl Plugin for HintPlugin {
+    fn build(&self, app: &mut App) {
+        app.noop()
+            // Resources
+            .init_resource::<ShowHint>()
+            // Systems
+            .add_systems(
+                Update,
+                hint_shortcut.run_if(in_state(MenuState::Game).and(keyboard_not_captured)),
+            )
+            .add_systems(
+                Update,
+                update_hint_arrows.after(hint_shortcut).before(DrawArrowsSystem).run_if(
+                    resource_changed::<ShowHint>
+                        .or(resource_exists_and_changed::<EngineAnalysis>)
+                        .or(resource_changed::<ViewedPly>)
+                        .or(resource_exists_and_changed::<StockfishOpponent>),
+                ),
+            )
+            .noop();
+    }
+}
+
+/// The colors of the first move of the best, second best, and third best lines.
+const HINT_COLORS: [Color; 3] = [
+    AnnotationColor::Green.color(),
+    AnnotationColor::Blue.color(),
+    AnnotationColor::Yellow.color(),
+];
+
+/// How many moves of the best line's continuation are shown after its first move.
+const HINT_CONTINUATION_PLIES: usize = 3;
+
+/// Whether the engine's best moves are shown on the board.
+#[derive(Clone, Copy, Debug, Default, Deref, DerefMut, PartialEq, Eq, Resource)]
+pub struct ShowHint(pub bool);
+
+#[derive(Component)]
+struct HintArrow;
+
+/// Toggle the hint with `Ctrl+H`.
+fn hint_shortcut(keys: Res<ButtonInput<KeyCode>>, mut show_hint: ResMut<ShowHint>) {
+    if ctrl_pressed(&keys) && keys.just_pressed(KeyCode::KeyH) {
+        **show_hint = !**show_hint;
+    }
+}
+
+/// The arrows of the hint for the live position: the first move of each of the best lines, and
+/// the continuation of the best line, fading out.
+///
+/// The arrows are in reverse order of importance so that the best move is drawn on top.
+fn hint_arrows(analysis: &EngineAnalysis) -> Vec<Arrow> {
+    let arrow = |r#move: &chess::ChessMove, color| {
+        Arrow::new(Square::new(r#move.get_source()), Square::new(r#move.get_dest()), color)
+    };
+
+    let mut arrows = Vec::new();
+    if let Some(best) = analysis.lines.first() {
+        let continuation = best.pv.iter().skip(1).take(HINT_CONTINUATION_PLIES);
+        for (i, r#move) in continuation.enumerate().rev() {
+            let alpha = 0.6 - 0.15 * i as f32;
+            arrows.push(arrow(r#move, HINT_COLORS[0].with_alpha(alpha)));
+        }
+    }
+    for (line, color) in analysis.lines.iter().zip(HINT_COLORS).rev() {
+        if let Some(r#move) = line.pv.first() {
+            arrows.push(arrow(r#move, color));
+        }
+    }
+    arrows
+}
+
+fn update_hint_arrows(
+    mut commands: Commands,
+    show_hint: Res<ShowHint>,
+    analysis: Option<Res<EngineAnalysis>>,
+    board_state: Res<BoardState>,
+    viewed_ply: Res<ViewedPly>,
+    opponent: Option<Res<StockfishOpponent>>,
+    q_layer: Query<Entity, With<ArrowLayer>>,
+    q_hint_arrows: Query<Entity, With<HintArrow>>,
+) {
+    for entity in &q_hint_arrows {
+        commands.entity(entity).despawn();
+    }
+
+    let Some(analysis) = analysis else { return };
+
+    // The lines are only meaningful on the position they were searched from, and don't give away
+    // the move Stockfish is choosing
+    let is_live_analysis = analysis.fen.as_deref() == Some(board_state.fen().as_str());
+    let is_opponent_move =
+        opponent.is_some_and(|opponent| opponent.side.color() == Some(board_state.side_to_move()));
+    if !**show_hint || !viewed_ply.is_live() || !is_live_analysis || is_opponent_move {
+        return;
+    }
+
+    let Ok(layer) = q_layer.single() else { return };
+    for arrow in hint_arrows(&analysis) {
+        commands.spawn((
+            HintArrow,
+            debug_name_f!("Hint Arrow ({} => {})", arrow.from, arrow.to),
+            arrow,
+            ChildOf(layer),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chess::ChessMove;
+
+    use crate::game::{
+        core::build_app,
+        stockfish::{EngineInfo, EngineScore, OpponentSide, Score, ScoreBound},
+    };
+
+    use super::*;
+
+    fn line(rank: u32, pv: &[(Square, Square)]) -> EngineInfo {
+        EngineInfo {
+            multipv: Some(rank),
+            score: Some(EngineScore { score: Score::Centipawns(20), bound: ScoreBound::Exact }),
+            pv: pv.iter().map(|&(from, to)| ChessMove::new(from.0, to.0, None)).collect(),
+            ..default()
+        }
+    }
+
+    fn hint_arrows(app: &mut App) -> Vec<(Square, Square, Color)> {
+        let mut q_arrows = app.world_mut().query_filtered::<&Arrow, With<HintArrow>>();
+        q_arrows.iter(app.world()).map(|arrow| (arrow.from, arrow.to, arrow.color)).collect()
+    }
+
+    #[test]
+    fn shows_best_moves_on_live_position() {
+        let mut app = build_app(|app: &mut App| {
+            app.init_resource::<EngineAnalysis>().init_resource::<StockfishOpponent>();
+        });
+        let fen = app.world().resource::<BoardState>().fen();
+        let mut analysis = app.world_mut().resource_mut::<EngineAnalysis>();
+        analysis.reset(fen);
+        analysis.update(&line(1, &[(Square::E2, Square::E4), (Square::E7, Square::E5)]));
+        analysis.update(&line(2, &[(Square::D2, Square::D4)]));
+        app.update();
+        assert!(hint_arrows(&mut app).is_empty());
+
+        **app.world_mut().resource_mut::<ShowHint>() = true;
+        app.update();
+
+        let mut arrows = hint_arrows(&mut app);
+        arrows.sort_by_key(|&(from, ..)| from.0.to_index());
+        assert_eq!(
+            arrows,
+            [
+                (Square::D2, Square::D4, HINT_COLORS[1]),
+                (Square::E2, Square::E4, HINT_COLORS[0]),
+                (Square::E7, Square::E5, HINT_COLORS[0].with_alpha(0.6)),
+            ]
+        );
+
+        // Stockfish's search for its own move is hidden
+        app.world_mut().resource_mut::<StockfishOpponent>().side = OpponentSide::White;
+        app.update();
+        assert!(hint_arrows(&mut app).is_empty());
+        app.world_mut().resource_mut::<StockfishOpponent>().side = OpponentSide::Black;
+        app.update();
+        assert_eq!(hint_arrows(&mut app).len(), 3);
+
+        // Analysis of another position is stale
+        let mut analysis = app.world_mut().resource_mut::<EngineAnalysis>();
+        analysis.fen = Some("8/8/8/8/8/8/8/K6k w - - 0 1".to_string());
+        app.update();
+        assert!(hint_arrows(&mut app).is_empty());
+    }
+}
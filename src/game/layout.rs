@@ -0,0 +1,307 @@
+use bevy::{prelude::*, ui::UiSystem, window::PrimaryWindow};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{NoopExts, ctrl_pressed};
+
+use super::{
+    consts::{
+        BOARD_REFERENCE_SIZE, CAPTURES_PANEL_HEIGHT, EVAL_BAR_WIDTH, INIT_WIN_HEIGHT,
+        INIT_WIN_WIDTH, MOVE_LIST_WIDTH, UI_GAP, UI_GAP_VAL,
+    },
+    menu::MenuState,
+    move_entry::keyboard_not_captured,
+    ui::{BoardContainer, EvaluationBarContainer, MoveListContainer, Ui},
+};
+
+pub struct LayoutPlugin;
+
+impl Plugin for LayoutPlugin {
+    fn build(&self, app: &mut App) {
+        app.noop()
+            // Resources
+            .init_resource::<UiLayout>()
+            .init_resource::<ActiveLayout>()
+            // Systems
+            .add_systems(
+                Update,
+                layout_shortcuts.run_if(in_state(MenuState::Game).and(keyboard_not_captured)),
+            )
+            .add_systems(
+                PostUpdate,
+                (apply_layout, scale_fonts.run_if(resource_changed::<ActiveLayout>))
+                    .chain()
+                    .before(UiSystem::Layout),
+            )
+            .noop();
+    }
+}
+
+/// The narrowest the side column gets, which is the width of the move list.
+const SIDE_COLUMN_MIN_WIDTH: f32 = MOVE_LIST_WIDTH;
+
+/// The widest the side column gets in the landscape arrangement.
+const SIDE_COLUMN_MAX_WIDTH: f32 = 360.0;
+
+/// The shortest the side column gets in the portrait arrangement.
+const SIDE_COLUMN_MIN_HEIGHT: f32 = 160.0;
+
+/// The smallest the board gets, however small the window is.
+const BOARD_MIN_SIZE: f32 = 256.0;
+
+/// Fonts that scale with the board are never scaled below or above these factors.
+const FONT_SCALE_RANGE: (f32, f32) = (0.75, 1.5);
+
+/// How the board and the side column are arranged.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LayoutMode {
+    /// Landscape if the window is wide enough for the side column beside the board, else portrait.
+    #[default]
+    Auto,
+    /// The side column is to the right of the board.
+    Landscape,
+    /// The side column is below the board.
+    Portrait,
+}
+
+impl LayoutMode {
+    fn next(self) -> Self {
+        match self {
+            Self::Auto => Self::Landscape,
+            Self::Landscape => Self::Portrait,
+            Self::Portrait => Self::Auto,
+        }
+    }
+}
+
+/// The layout chosen by the player.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiLayout {
+    pub mode: LayoutMode,
+    /// Whether the side column, with the move list and engine lines, is shown.
+    pub side_column_docked: bool,
+    /// Whether the evaluation bar is shown beside the board.
+    pub eval_bar: bool,
+}
+
+impl Default for UiLayout {
+    fn default() -> Self {
+        Self { mode: LayoutMode::Auto, side_column_docked: true, eval_bar: true }
+    }
+}
+
+/// The arrangement that a [`LayoutMode`] resolves to for a window size.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Arrangement {
+    #[default]
+    Landscape,
+    Portrait,
+}
+
+/// The layout that is currently applied, in logical pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Resource)]
+pub struct ActiveLayout {
+    pub arrangement: Arrangement,
+    pub board_size: f32,
+    /// The size of the side column, or `None` if it is undocked.
+    pub side_column: Option<Vec2>,
+    pub eval_bar: bool,
+}
+
+impl Default for ActiveLayout {
+    fn default() -> Self {
+        Self::compute(Vec2::new(INIT_WIN_WIDTH, INIT_WIN_HEIGHT), default())
+    }
+}
+
+impl ActiveLayout {
+    /// Fit the board, with the panels above and below it and the evaluation bar beside it, and the
+    /// side column into a window.
+    pub fn compute(window: Vec2, layout: UiLayout) -> Self {
+        // The evaluation bar and its margin, and the window padding
+        let eval_bar_width = if layout.eval_bar { EVAL_BAR_WIDTH + UI_GAP } else { 0.0 };
+        let chrome_width = 2.0 * UI_GAP + eval_bar_width;
+        // The window padding and the panels with their gaps
+        let panels_height = 2.0 * (CAPTURES_PANEL_HEIGHT + UI_GAP);
+        let chrome_height = 2.0 * UI_GAP + panels_height;
+
+        let docked = layout.side_column_docked;
+        let eval_bar = layout.eval_bar;
+        let arrangement = match layout.mode {
+            LayoutMode::Landscape => Arrangement::Landscape,
+            LayoutMode::Portrait => Arrangement::Portrait,
+            LayoutMode::Auto => {
+                let board_by_height = window.y - chrome_height;
+                let column_width = if docked { UI_GAP + SIDE_COLUMN_MIN_WIDTH } else { 0.0 };
+                if window.x - chrome_width - column_width >= board_by_height {
+                    Arrangement::Landscape
+                } else {
+                    Arrangement::Portrait
+                }
+            }
+        };
+
+        match arrangement {
+            Arrangement::Landscape => {
+                let column_width = if docked { UI_GAP + SIDE_COLUMN_MIN_WIDTH } else { 0.0 };
+                let board_size = (window.y - chrome_height)
+                    .min(window.x - chrome_width - column_width)
+                    .max(BOARD_MIN_SIZE);
+                let side_column = docked.then(|| {
+                    let width = (window.x - chrome_width - board_size - UI_GAP)
+                        .clamp(SIDE_COLUMN_MIN_WIDTH, SIDE_COLUMN_MAX_WIDTH);
+                    Vec2::new(width, board_size + panels_height)
+                });
+                Self { arrangement, board_size, side_column, eval_bar }
+            }
+            Arrangement::Portrait => {
+                let column_height = if docked { UI_GAP + SIDE_COLUMN_MIN_HEIGHT } else { 0.0 };
+                let board_size = (window.x - chrome_width)
+                    .min(window.y - chrome_height - column_height)
+                    .max(BOARD_MIN_SIZE);
+                let side_column = docked.then(|| {
+                    let height = (window.y - chrome_height - board_size - UI_GAP)
+                        .max(SIDE_COLUMN_MIN_HEIGHT);
+                    Vec2::new(board_size + eval_bar_width, height)
+                });
+                Self { arrangement, board_size, side_column, eval_bar }
+            }
+        }
+    }
+
+    /// How much fonts that scale with the board are scaled.
+    pub fn font_scale(&self) -> f32 {
+        let (min, max) = FONT_SCALE_RANGE;
+        (self.board_size / BOARD_REFERENCE_SIZE).clamp(min, max)
+    }
+}
+
+/// A font size that is scaled with the board, from its size when the board is
+/// [`BOARD_REFERENCE_SIZE`] pixels wide.
+#[derive(Clone, Copy, Component, Debug, Deref)]
+pub struct ScaledFont(pub f32);
+
+/// Toggle the side column with `Ctrl+L`, and cycle through the layout modes with `Ctrl+Shift+L`.
+fn layout_shortcuts(keys: Res<ButtonInput<KeyCode>>, mut layout: ResMut<UiLayout>) {
+    if !ctrl_pressed(&keys) || !keys.just_pressed(KeyCode::KeyL) {
+        return;
+    }
+
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if shift {
+        layout.mode = layout.mode.next();
+    } else {
+        layout.side_column_docked = !layout.side_column_docked;
+    }
+    debug!(?layout, "Change layout");
+}
+
+fn apply_layout(
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    layout: Res<UiLayout>,
+    mut active: ResMut<ActiveLayout>,
+    mut q_ui: Query<&mut Node, With<Ui>>,
+    mut q_board: Query<&mut Node, (With<BoardContainer>, Without<Ui>)>,
+    mut q_column: Query<&mut Node, (With<MoveListContainer>, Without<Ui>, Without<BoardContainer>)>,
+    mut q_eval_bar: Query<
+        &mut Node,
+        (
+            With<EvaluationBarContainer>,
+            Without<Ui>,
+            Without<BoardContainer>,
+            Without<MoveListContainer>,
+        ),
+    >,
+) {
+    let Ok(win) = q_window.single() else { return };
+    let next = ActiveLayout::compute(Vec2::new(win.width(), win.height()), *layout);
+    active.set_if_neq(next);
+    if !active.is_changed() {
+        return;
+    }
+
+    let Ok(mut ui) = q_ui.single_mut() else { return };
+    let Ok(mut board) = q_board.single_mut() else { return };
+    let Ok(mut column) = q_column.single_mut() else { return };
+    let Ok(mut eval_bar) = q_eval_bar.single_mut() else { return };
+
+    ui.flex_direction = match next.arrangement {
+        Arrangement::Landscape => FlexDirection::Row,
+        Arrangement::Portrait => FlexDirection::Column,
+    };
+
+    board.width = Val::Px(next.board_size);
+    board.height = Val::Px(next.board_size);
+
+    match next.side_column {
+        Some(size) => {
+            column.display = Display::Flex;
+            column.width = Val::Px(size.x);
+            column.height = Val::Px(size.y);
+            column.margin = match next.arrangement {
+                Arrangement::Landscape => UiRect::left(UI_GAP_VAL),
+                Arrangement::Portrait => UiRect::top(UI_GAP_VAL),
+            };
+        }
+        None => column.display = Display::None,
+    }
+    eval_bar.display = if next.eval_bar { Display::Flex } else { Display::None };
+}
+
+fn scale_fonts(active: Res<ActiveLayout>, mut q_fonts: Query<(&ScaledFont, &mut TextFont)>) {
+    let scale = active.font_scale();
+    for (font, mut text_font) in &mut q_fonts {
+        let font_size = (**font * scale).round();
+        if text_font.font_size != font_size {
+            text_font.font_size = font_size;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arranges_side_column_by_window_shape() {
+        let wide = Vec2::new(1600.0, 900.0);
+        let tall = Vec2::new(700.0, 1000.0);
+
+        let layout = ActiveLayout::compute(wide, UiLayout::default());
+        assert_eq!(layout.arrangement, Arrangement::Landscape);
+        assert_eq!(
+            layout.board_size,
+            900.0 - 2.0 * UI_GAP - 2.0 * (CAPTURES_PANEL_HEIGHT + UI_GAP)
+        );
+        assert_eq!(layout.side_column.unwrap().x, SIDE_COLUMN_MAX_WIDTH);
+
+        let layout = ActiveLayout::compute(tall, UiLayout::default());
+        assert_eq!(layout.arrangement, Arrangement::Portrait);
+        assert_eq!(layout.board_size, 700.0 - 3.0 * UI_GAP - EVAL_BAR_WIDTH);
+        let column = layout.side_column.unwrap();
+        assert_eq!(column.x, layout.board_size + EVAL_BAR_WIDTH + UI_GAP);
+        assert!(column.y >= SIDE_COLUMN_MIN_HEIGHT);
+
+        // The board takes the room of an undocked column
+        let undocked = UiLayout { side_column_docked: false, ..default() };
+        let layout = ActiveLayout::compute(tall, undocked);
+        assert_eq!(layout.side_column, None);
+        assert_eq!(layout.board_size, 700.0 - 3.0 * UI_GAP - EVAL_BAR_WIDTH);
+
+        let forced = UiLayout { mode: LayoutMode::Landscape, ..default() };
+        let layout = ActiveLayout::compute(tall, forced);
+        assert_eq!(layout.arrangement, Arrangement::Landscape);
+        assert_eq!(layout.side_column.unwrap().x, SIDE_COLUMN_MIN_WIDTH);
+        assert_eq!(
+            layout.board_size,
+            700.0 - 3.0 * UI_GAP - EVAL_BAR_WIDTH - UI_GAP - SIDE_COLUMN_MIN_WIDTH
+        );
+
+        // The board takes the room of a hidden evaluation bar
+        let no_eval_bar = UiLayout { eval_bar: false, ..default() };
+        let layout = ActiveLayout::compute(tall, no_eval_bar);
+        assert_eq!(layout.board_size, 700.0 - 2.0 * UI_GAP);
+        assert_eq!(layout.side_column.unwrap().x, layout.board_size);
+    }
+}
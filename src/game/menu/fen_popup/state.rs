@@ -201,7 +201,6 @@ pub(super) struct PopupFonts {
     title: FontId,
     label: FontId,
     sublabel: FontId,
-    button: FontId,
     editable: FontId,
     drag_value: TextStyle,
 }
@@ -213,7 +212,6 @@ impl Default for PopupFonts {
             title: FontId::new(32.0, default()),
             label: FontId::new(18.0, default()),
             sublabel: FontId::new(16.0, default()),
-            button: FontId::new(20.0, default()),
             editable: FontId::new(14.5, default()),
             drag_value: TextStyle::Name(Arc::from("gambit-drag-value")),
         }
@@ -249,7 +247,6 @@ impl PopupFonts {
         title: FontId;
         label: FontId;
         sublabel: FontId;
-        button: FontId;
         editable: FontId;
         drag_value: TextStyle;
     }
@@ -1,7 +1,7 @@
 use bevy_egui::egui::{
-    Align, Align2, Button, Color32, ComboBox, Context, DragValue, FontId, Frame, Key, Layout,
-    Response, RichText, Sense, Stroke, StrokeKind, TextEdit, Ui, UiBuilder, Vec2, WidgetInfo,
-    WidgetType, Window, lerp, pos2,
+    Align, Align2, Color32, ComboBox, Context, DragValue, FontId, Frame, Key, Layout, Response,
+    RichText, Sense, Stroke, StrokeKind, TextEdit, Ui, UiBuilder, Vec2, WidgetInfo, WidgetType,
+    Window, lerp, pos2,
     text::{CCursor, CCursorRange},
     text_edit::TextEditOutput,
     vec2,
@@ -9,7 +9,7 @@ use bevy_egui::egui::{
 use chess::ALL_FILES;
 use egui_extras::{Size, StripBuilder};
 
-use crate::game::consts::DEFAULT_FEN;
+use crate::game::{consts::DEFAULT_FEN, menu::save_cancel_buttons};
 
 use super::{FenPopupInteraction, state::PopupState};
 
@@ -183,19 +183,13 @@ impl PopupState {
     }
 
     fn action_buttons_section(&self, ui: &mut Ui, interaction: &mut FenPopupInteraction) {
-        let size = ui.available_size_before_wrap();
-        let layout = Layout::right_to_left(Align::Center);
-        ui.allocate_ui_with_layout(size, layout, |ui| {
-            if load_button(ui, self.fonts.button()).clicked() {
-                *interaction = FenPopupInteraction::Submit;
-            }
-
-            ui.add_space(8.0);
-
-            if cancel_button(ui, self.fonts.button()).clicked() {
-                *interaction = FenPopupInteraction::Cancel;
-            }
-        });
+        let (load, cancel) = save_cancel_buttons(ui, "Load");
+        if load {
+            *interaction = FenPopupInteraction::Submit;
+        }
+        if cancel {
+            *interaction = FenPopupInteraction::Cancel;
+        }
     }
 }
 
@@ -232,13 +226,3 @@ fn players_turn_toggle(ui: &mut Ui, value: &mut bool) -> Response {
 
     response
 }
-
-fn cancel_button(ui: &mut Ui, font: FontId) -> Response {
-    let text = RichText::new("Cancel").font(font).color(Color32::WHITE);
-    ui.add(Button::new(text).fill(Color32::from_rgb(0xba, 0x29, 0x29))) // #ba2929
-}
-
-fn load_button(ui: &mut Ui, font: FontId) -> Response {
-    let text = RichText::new("Load").font(font).color(Color32::WHITE);
-    ui.add(Button::new(text).fill(Color32::from_rgb(0x7f, 0xa6, 0x50))) // #7fa650
-}
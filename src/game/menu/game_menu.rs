@@ -2,10 +2,13 @@ use bevy::{prelude::*, window::PrimaryWindow};
 
 use crate::{
     debug_name,
-    game::consts::{
-        FONT_PATH, INIT_MENU_BUTTON_TEXT_SIZE, INIT_MENU_HEIGHT, INIT_MENU_TITLE_SIZE,
-        INIT_MENU_WIDTH, INIT_WIN_HEIGHT, INIT_WIN_WIDTH, MENU_HEIGHT_RATIO, MENU_WIDTH_RATIO,
-        TITLE_FONT_PATH, Z_MENU,
+    game::{
+        board::FlipBoard,
+        consts::{
+            FONT_PATH, INIT_MENU_BUTTON_TEXT_SIZE, INIT_MENU_HEIGHT, INIT_MENU_TITLE_SIZE,
+            INIT_MENU_WIDTH, INIT_WIN_HEIGHT, INIT_WIN_WIDTH, MENU_HEIGHT_RATIO, MENU_WIDTH_RATIO,
+            TITLE_FONT_PATH, Z_MENU,
+        },
     },
     utils::{RoundToNearest, recolor_on, set_state_on},
 };
@@ -106,6 +109,10 @@ pub struct GameMenuButtonsText;
 pub(super) enum GameMenuButton {
     Start,
     LoadFen,
+    LoadPgn,
+    Opponent,
+    Settings,
+    FlipBoard,
 }
 
 /// `#7fa650`
@@ -200,13 +207,13 @@ pub fn spawn_menu_buttons(
             GameMenuButton::LoadFen,
             debug_name!("Load FEN Button"),
             Button,
-            button_node,
+            button_node.clone(),
             BackgroundColor(BUTTON_COLOR_DEFAULT),
             children![(
                 debug_name!("Load FEN Button Text"),
                 GameMenuButtonsText,
                 Text("Load FEN".to_string()),
-                text_font,
+                text_font.clone(),
             )],
         ))
         .observe(recolor_on::<Pointer<Over>>(BUTTON_COLOR_HOVER))
@@ -214,9 +221,92 @@ pub fn spawn_menu_buttons(
         .observe(set_state_on::<MenuState, Pointer<Click>>(MenuState::FenInput))
         .id();
 
-    commands
-        .entity(q_menu_buttons_container.single().unwrap())
-        .add_children(&[start_button_entity, fen_button_entity]);
+    let pgn_button_entity = commands
+        .spawn((
+            GameMenuButton::LoadPgn,
+            debug_name!("Load PGN Button"),
+            Button,
+            button_node.clone(),
+            BackgroundColor(BUTTON_COLOR_DEFAULT),
+            children![(
+                debug_name!("Load PGN Button Text"),
+                GameMenuButtonsText,
+                Text("Load PGN".to_string()),
+                text_font.clone(),
+            )],
+        ))
+        .observe(recolor_on::<Pointer<Over>>(BUTTON_COLOR_HOVER))
+        .observe(recolor_on::<Pointer<Out>>(BUTTON_COLOR_DEFAULT))
+        .observe(set_state_on::<MenuState, Pointer<Click>>(MenuState::PgnInput))
+        .id();
+
+    let opponent_button_entity = commands
+        .spawn((
+            GameMenuButton::Opponent,
+            debug_name!("Opponent Button"),
+            Button,
+            button_node.clone(),
+            BackgroundColor(BUTTON_COLOR_DEFAULT),
+            children![(
+                debug_name!("Opponent Button Text"),
+                GameMenuButtonsText,
+                Text("Opponent".to_string()),
+                text_font.clone(),
+            )],
+        ))
+        .observe(recolor_on::<Pointer<Over>>(BUTTON_COLOR_HOVER))
+        .observe(recolor_on::<Pointer<Out>>(BUTTON_COLOR_DEFAULT))
+        .observe(set_state_on::<MenuState, Pointer<Click>>(MenuState::OpponentInput))
+        .id();
+
+    let settings_button_entity = commands
+        .spawn((
+            GameMenuButton::Settings,
+            debug_name!("Settings Button"),
+            Button,
+            button_node.clone(),
+            BackgroundColor(BUTTON_COLOR_DEFAULT),
+            children![(
+                debug_name!("Settings Button Text"),
+                GameMenuButtonsText,
+                Text("Settings".to_string()),
+                text_font.clone(),
+            )],
+        ))
+        .observe(recolor_on::<Pointer<Over>>(BUTTON_COLOR_HOVER))
+        .observe(recolor_on::<Pointer<Out>>(BUTTON_COLOR_DEFAULT))
+        .observe(set_state_on::<MenuState, Pointer<Click>>(MenuState::SettingsInput))
+        .id();
+
+    let flip_button_entity = commands
+        .spawn((
+            GameMenuButton::FlipBoard,
+            debug_name!("Flip Board Button"),
+            Button,
+            button_node,
+            BackgroundColor(BUTTON_COLOR_DEFAULT),
+            children![(
+                debug_name!("Flip Board Button Text"),
+                GameMenuButtonsText,
+                Text("Flip Board".to_string()),
+                text_font,
+            )],
+        ))
+        .observe(recolor_on::<Pointer<Over>>(BUTTON_COLOR_HOVER))
+        .observe(recolor_on::<Pointer<Out>>(BUTTON_COLOR_DEFAULT))
+        .observe(|_trigger: Trigger<Pointer<Click>>, mut commands: Commands| {
+            commands.trigger(FlipBoard);
+        })
+        .id();
+
+    commands.entity(q_menu_buttons_container.single().unwrap()).add_children(&[
+        start_button_entity,
+        fen_button_entity,
+        pgn_button_entity,
+        opponent_button_entity,
+        settings_button_entity,
+        flip_button_entity,
+    ]);
 }
 
 pub(super) fn game_menu_elements_sizes(
@@ -1,14 +1,18 @@
 use bevy::{prelude::*, ui::UiSystem};
+use bevy_egui::egui::{Align, Button, Color32, FontId, Layout, RichText, Ui};
 use bevy_startup_tree::{AddStartupTree, startup_tree};
 
 use crate::utils::NoopExts;
 
-use self::fen_popup::*;
+use self::{fen_popup::*, opponent_popup::*, pgn_popup::*, settings_popup::*};
 
 pub use self::{game_menu::*, state::*};
 
 mod fen_popup;
 mod game_menu;
+mod opponent_popup;
+mod pgn_popup;
+mod settings_popup;
 mod state;
 
 pub struct GameMenuUiPlugin;
@@ -32,6 +36,9 @@ impl Plugin for GameMenuLogicPlugin {
         app.noop()
             // Resources
             .init_resource::<PopupState>()
+            .init_resource::<PgnPopupState>()
+            .init_resource::<OpponentPopupState>()
+            .init_resource::<SettingsPopupState>()
             .init_resource::<GameOverTimer>()
             // States
             .init_state::<MenuState>()
@@ -41,16 +48,41 @@ impl Plugin for GameMenuLogicPlugin {
             .add_systems(Startup, init_menu_state_from_cli)
             .add_systems(PostUpdate, menu_size.before(UiSystem::Layout))
             .add_systems(OnEnter(MenuState::FenInput), on_enter_menu_state_fen_input)
+            .add_systems(OnEnter(MenuState::PgnInput), on_enter_menu_state_pgn_input)
+            .add_systems(OnEnter(MenuState::OpponentInput), on_enter_menu_state_opponent_input)
+            .add_systems(OnEnter(MenuState::SettingsInput), on_enter_menu_state_settings_input)
             .add_systems(OnEnter(MenuState::Menu), on_enter_menu_state_menu)
             .add_systems(OnEnter(MenuState::Game), on_enter_menu_state_game)
             .add_systems(OnEnter(MenuState::DoGameOver), on_enter_menu_state_do_game_over)
             .add_systems(Update, fen_menu.run_if(in_state(MenuState::FenInput)))
+            .add_systems(Update, pgn_menu.run_if(in_state(MenuState::PgnInput)))
+            .add_systems(Update, opponent_menu.run_if(in_state(MenuState::OpponentInput)))
+            .add_systems(Update, settings_menu.run_if(in_state(MenuState::SettingsInput)))
             .add_systems(Update, game_menu_elements_sizes.run_if(in_state(MenuState::Menu)))
             .add_systems(Update, game_over.run_if(in_state(MenuState::DoGameOver)))
             .noop();
     }
 }
 
+/// The `confirm` (e.g. `Save`) and `Cancel` buttons at the bottom right of a popup, returning
+/// whether each was clicked.
+fn save_cancel_buttons(ui: &mut Ui, confirm: &str) -> (bool, bool) {
+    let size = ui.available_size_before_wrap();
+    let layout = Layout::right_to_left(Align::Center);
+    ui.allocate_ui_with_layout(size, layout, |ui| {
+        let font = FontId::proportional(20.0);
+        let confirm_text = RichText::new(confirm).font(font.clone()).color(Color32::WHITE);
+        // #7fa650
+        let save = ui.add(Button::new(confirm_text).fill(Color32::from_rgb(0x7f, 0xa6, 0x50)));
+        ui.add_space(8.0);
+        let cancel_text = RichText::new("Cancel").font(font).color(Color32::WHITE);
+        // #ba2929
+        let cancel = ui.add(Button::new(cancel_text).fill(Color32::from_rgb(0xba, 0x29, 0x29)));
+        (save.clicked(), cancel.clicked())
+    })
+    .inner
+}
+
 #[cfg(test)]
 pub mod test {
     use bevy::prelude::*;
@@ -0,0 +1,183 @@
+use bevy::prelude::*;
+use bevy_egui::{
+    EguiContexts,
+    egui::{
+        Align2, ComboBox, DragValue, FontId, Frame, Grid, Key, RichText, Slider, Ui, Vec2, Window,
+        vec2,
+    },
+};
+
+use crate::game::stockfish::{
+    ELO_RANGE, OpponentSide, SKILL_LEVELS, SearchLimit, StockfishOpponent,
+};
+
+use super::{MenuState, save_cancel_buttons};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SearchLimitKind {
+    MoveTime,
+    Depth,
+    Nodes,
+}
+
+impl SearchLimitKind {
+    fn label(self) -> &'static str {
+        match self {
+            Self::MoveTime => "Time per move (ms)",
+            Self::Depth => "Depth",
+            Self::Nodes => "Nodes",
+        }
+    }
+}
+
+/// The opponent settings being edited, which are only applied when saved.
+#[derive(Resource)]
+pub struct OpponentPopupState {
+    side: OpponentSide,
+    skill_level: u8,
+    limit_strength: bool,
+    elo: u16,
+    limit_kind: SearchLimitKind,
+    movetime: u32,
+    depth: u32,
+    nodes: u64,
+}
+
+impl Default for OpponentPopupState {
+    fn default() -> Self {
+        let mut state = Self {
+            side: default(),
+            skill_level: 0,
+            limit_strength: false,
+            elo: 0,
+            limit_kind: SearchLimitKind::MoveTime,
+            movetime: 2500,
+            depth: 12,
+            nodes: 1_000_000,
+        };
+        state.reset(&default());
+        state
+    }
+}
+
+impl OpponentPopupState {
+    pub fn reset(&mut self, opponent: &StockfishOpponent) {
+        self.side = opponent.side;
+        self.skill_level = opponent.skill_level;
+        self.limit_strength = opponent.elo.is_some();
+        self.elo = opponent.elo.unwrap_or(*ELO_RANGE.start());
+        match opponent.limit {
+            SearchLimit::MoveTime(ms) => {
+                self.limit_kind = SearchLimitKind::MoveTime;
+                self.movetime = ms;
+            }
+            SearchLimit::Depth(depth) => {
+                self.limit_kind = SearchLimitKind::Depth;
+                self.depth = depth;
+            }
+            SearchLimit::Nodes(nodes) => {
+                self.limit_kind = SearchLimitKind::Nodes;
+                self.nodes = nodes;
+            }
+        }
+    }
+
+    pub(super) fn opponent(&self) -> StockfishOpponent {
+        StockfishOpponent {
+            side: self.side,
+            skill_level: self.skill_level,
+            elo: self.limit_strength.then_some(self.elo),
+            limit: match self.limit_kind {
+                SearchLimitKind::MoveTime => SearchLimit::MoveTime(self.movetime),
+                SearchLimitKind::Depth => SearchLimit::Depth(self.depth),
+                SearchLimitKind::Nodes => SearchLimit::Nodes(self.nodes),
+            },
+        }
+    }
+
+    pub(super) fn controls(&mut self, ui: &mut Ui) {
+        let label = |text: &str| RichText::new(text).font(FontId::proportional(18.0));
+
+        Grid::new("Opponent Controls").num_columns(2).spacing(vec2(16.0, 16.0)).show(ui, |ui| {
+            ui.label(label("Stockfish plays:"));
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut self.side, OpponentSide::None, "Nobody");
+                ui.selectable_value(&mut self.side, OpponentSide::White, "White");
+                ui.selectable_value(&mut self.side, OpponentSide::Black, "Black");
+            });
+            ui.end_row();
+
+            ui.label(label("Skill level:"));
+            ui.add(Slider::new(&mut self.skill_level, SKILL_LEVELS));
+            ui.end_row();
+
+            ui.checkbox(&mut self.limit_strength, label("Limit Elo:"));
+            ui.add_enabled(self.limit_strength, Slider::new(&mut self.elo, ELO_RANGE));
+            ui.end_row();
+
+            ComboBox::from_id_salt("Search Limit").selected_text(self.limit_kind.label()).show_ui(
+                ui,
+                |ui| {
+                    for kind in
+                        [SearchLimitKind::MoveTime, SearchLimitKind::Depth, SearchLimitKind::Nodes]
+                    {
+                        ui.selectable_value(&mut self.limit_kind, kind, kind.label());
+                    }
+                },
+            );
+            match self.limit_kind {
+                SearchLimitKind::MoveTime => {
+                    ui.add(DragValue::new(&mut self.movetime).range(1..=60_000).speed(10.0))
+                }
+                SearchLimitKind::Depth => ui.add(DragValue::new(&mut self.depth).range(1..=99)),
+                SearchLimitKind::Nodes => {
+                    ui.add(DragValue::new(&mut self.nodes).range(1..=u64::MAX).speed(1000.0))
+                }
+            };
+            ui.end_row();
+        });
+    }
+}
+
+pub(super) fn opponent_menu(
+    mut egui_contexts: EguiContexts,
+    mut next_menu_state: ResMut<NextState<MenuState>>,
+    mut opponent: ResMut<StockfishOpponent>,
+    mut state: ResMut<OpponentPopupState>,
+) {
+    let ctx = egui_contexts.ctx_mut();
+
+    let mut cancel = false;
+    let mut save = false;
+
+    Window::new("Opponent Popup")
+        .resizable(false)
+        .title_bar(false)
+        .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
+        .show(ctx, |ui| {
+            if ui.input(|i| i.key_pressed(Key::Escape)) {
+                cancel = true;
+                return;
+            }
+
+            ui.vertical_centered_justified(|ui| {
+                ui.heading(RichText::new("Opponent").font(FontId::proportional(32.0)));
+                ui.separator();
+
+                ui.set_min_size(vec2(440.0, 0.0));
+                Frame::NONE.outer_margin(12.0).show(ui, |ui| {
+                    state.controls(ui);
+
+                    ui.add_space(24.0);
+                    (save, cancel) = save_cancel_buttons(ui, "Save");
+                });
+            });
+        });
+
+    if save {
+        opponent.set_if_neq(state.opponent());
+        next_menu_state.set(MenuState::Menu);
+    } else if cancel {
+        next_menu_state.set(MenuState::Menu);
+    }
+}
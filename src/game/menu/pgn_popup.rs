@@ -0,0 +1,93 @@
+use bevy::prelude::*;
+use bevy_egui::{
+    EguiContexts,
+    egui::{Align2, Color32, FontId, Frame, Key, RichText, TextEdit, Vec2, Window, vec2},
+};
+
+use crate::game::pgn::read_pgn_file;
+
+use super::{MenuState, save_cancel_buttons};
+
+#[derive(Default, Resource)]
+pub struct PgnPopupState {
+    focus_path: bool,
+    path: String,
+    error: Option<String>,
+}
+
+impl PgnPopupState {
+    pub fn reset(&mut self) {
+        *self = Self { focus_path: true, ..default() };
+    }
+}
+
+pub(super) fn pgn_menu(
+    mut commands: Commands,
+    mut egui_contexts: EguiContexts,
+    mut next_menu_state: ResMut<NextState<MenuState>>,
+    mut state: ResMut<PgnPopupState>,
+) {
+    let ctx = egui_contexts.ctx_mut();
+    let state = &mut *state;
+
+    let mut cancel = false;
+    let mut submit = false;
+
+    Window::new("PGN Popup")
+        .resizable(false)
+        .title_bar(false)
+        .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
+        .show(ctx, |ui| {
+            if ui.input(|i| i.key_pressed(Key::Escape)) {
+                cancel = true;
+                return;
+            }
+
+            ui.vertical_centered_justified(|ui| {
+                ui.heading(RichText::new("Load PGN").font(FontId::proportional(32.0)));
+                ui.separator();
+
+                ui.set_min_size(vec2(626.0, 0.0));
+                Frame::NONE.outer_margin(12.0).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(RichText::new("File:").font(FontId::proportional(18.0)));
+                        let response = TextEdit::singleline(&mut state.path)
+                            .hint_text("game.pgn")
+                            .font(FontId::proportional(14.5))
+                            .desired_width(f32::INFINITY)
+                            .show(ui)
+                            .response;
+                        if state.focus_path {
+                            state.focus_path = false;
+                            response.request_focus();
+                        }
+                        if response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+                            submit = true;
+                        }
+                    });
+
+                    if let Some(error) = &state.error {
+                        ui.add_space(8.0);
+                        ui.label(RichText::new(error).color(Color32::from_rgb(0xba, 0x29, 0x29)));
+                    }
+
+                    ui.add_space(24.0);
+                    let (load, cancel_clicked) = save_cancel_buttons(ui, "Load");
+                    submit |= load;
+                    cancel |= cancel_clicked;
+                });
+            });
+        });
+
+    if cancel {
+        next_menu_state.set(MenuState::Menu);
+    } else if submit {
+        match read_pgn_file(state.path.trim()) {
+            Ok(pgn) => commands.trigger(pgn.into_load_game(MenuState::Game)),
+            Err(err) => {
+                error!("{err}");
+                state.error = Some(err.to_string());
+            }
+        }
+    }
+}
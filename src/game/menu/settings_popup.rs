@@ -0,0 +1,187 @@
+use bevy::prelude::*;
+use bevy_egui::{
+    EguiContexts,
+    egui::{
+        Align2, Color32, ComboBox, FontId, Frame, Grid, Key, RichText, Slider, TextEdit, Vec2,
+        Window, vec2,
+    },
+};
+
+use crate::game::{
+    audio::SoundPacks,
+    board::{BoardOrientation, PieceColor, Themes},
+    clock::TimeControl,
+    layout::{LayoutMode, UiLayout},
+    settings::{ANIMATION_SPEEDS, EditSettings, Settings, SoundSettings},
+};
+
+use super::{MenuState, OpponentPopupState, save_cancel_buttons};
+
+/// The settings being edited, which are only applied when saved.
+#[derive(Resource, Default)]
+pub struct SettingsPopupState {
+    settings: Settings,
+    time_control: String,
+    time_control_error: Option<String>,
+    opponent: OpponentPopupState,
+}
+
+impl SettingsPopupState {
+    pub fn reset(&mut self, settings: &Settings) {
+        self.settings = settings.clone();
+        self.time_control = settings.time_control.map(|tc| tc.to_string()).unwrap_or_default();
+        self.time_control_error = None;
+        self.opponent.reset(&settings.engine);
+    }
+
+    /// The edited settings, or `None` if the time control is invalid.
+    fn settings(&mut self) -> Option<Settings> {
+        let time_control = self.time_control.trim();
+        let time_control = if time_control.is_empty() {
+            None
+        } else {
+            match time_control.parse::<TimeControl>() {
+                Ok(tc) => Some(tc),
+                Err(err) => {
+                    self.time_control_error = Some(err.to_string());
+                    return None;
+                }
+            }
+        };
+        Some(Settings { time_control, engine: self.opponent.opponent(), ..self.settings.clone() })
+    }
+}
+
+pub(super) fn settings_menu(
+    mut commands: Commands,
+    mut egui_contexts: EguiContexts,
+    mut next_menu_state: ResMut<NextState<MenuState>>,
+    mut state: ResMut<SettingsPopupState>,
+    themes: Res<Themes>,
+    sound_packs: Res<SoundPacks>,
+) {
+    let ctx = egui_contexts.ctx_mut();
+
+    let mut cancel = false;
+    let mut save = false;
+
+    Window::new("Settings Popup")
+        .resizable(false)
+        .title_bar(false)
+        .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
+        .show(ctx, |ui| {
+            if ui.input(|i| i.key_pressed(Key::Escape)) {
+                cancel = true;
+                return;
+            }
+
+            ui.vertical_centered_justified(|ui| {
+                ui.heading(RichText::new("Settings").font(FontId::proportional(32.0)));
+                ui.separator();
+
+                ui.set_min_size(vec2(480.0, 0.0));
+                Frame::NONE.outer_margin(12.0).show(ui, |ui| {
+                    let label = |text: &str| RichText::new(text).font(FontId::proportional(18.0));
+                    let state = &mut *state;
+                    let settings = &mut state.settings;
+
+                    Grid::new("Settings Controls").num_columns(2).spacing(vec2(16.0, 16.0)).show(
+                        ui,
+                        |ui| {
+                            ui.label(label("Theme:"));
+                            ComboBox::from_id_salt("Theme")
+                                .selected_text(settings.theme.as_str())
+                                .show_ui(ui, |ui| {
+                                    for theme in themes.iter() {
+                                        ui.selectable_value(
+                                            &mut settings.theme,
+                                            theme.clone(),
+                                            theme,
+                                        );
+                                    }
+                                });
+                            ui.end_row();
+
+                            let SoundSettings { volume, muted, pack } = &mut settings.sound;
+                            ui.label(label("Volume:"));
+                            ui.horizontal(|ui| {
+                                ui.add_enabled(!*muted, Slider::new(volume, 0.0..=1.0));
+                                ui.checkbox(muted, "Mute");
+                            });
+                            ui.end_row();
+
+                            ui.label(label("Sound pack:"));
+                            ComboBox::from_id_salt("Sound Pack")
+                                .selected_text(pack.as_str())
+                                .show_ui(ui, |ui| {
+                                    for name in sound_packs.iter() {
+                                        ui.selectable_value(pack, name.clone(), name);
+                                    }
+                                });
+                            ui.end_row();
+
+                            ui.label(label("Animation speed:"));
+                            ui.add(
+                                Slider::new(&mut settings.animation_speed, ANIMATION_SPEEDS)
+                                    .logarithmic(true)
+                                    .suffix("×"),
+                            );
+                            ui.end_row();
+
+                            ui.label(label("Board orientation:"));
+                            ui.horizontal(|ui| {
+                                let BoardOrientation(color) = &mut settings.board_orientation;
+                                ui.selectable_value(color, PieceColor::WHITE, "White");
+                                ui.selectable_value(color, PieceColor::BLACK, "Black");
+                            });
+                            ui.end_row();
+
+                            ui.label(label("Layout:"));
+                            ui.horizontal(|ui| {
+                                let UiLayout { mode, side_column_docked, eval_bar } =
+                                    &mut settings.layout;
+                                ui.selectable_value(mode, LayoutMode::Auto, "Auto");
+                                ui.selectable_value(mode, LayoutMode::Landscape, "Landscape");
+                                ui.selectable_value(mode, LayoutMode::Portrait, "Portrait");
+                                ui.checkbox(side_column_docked, "Side column");
+                                ui.checkbox(eval_bar, "Eval bar");
+                            });
+                            ui.end_row();
+
+                            ui.label(label("Time control:"));
+                            ui.vertical(|ui| {
+                                let response = ui.add(
+                                    TextEdit::singleline(&mut state.time_control)
+                                        .hint_text("None, e.g. 5+3"),
+                                );
+                                if response.changed() {
+                                    state.time_control_error = None;
+                                }
+                                if let Some(err) = &state.time_control_error {
+                                    ui.colored_label(Color32::from_rgb(0xba, 0x29, 0x29), err);
+                                }
+                            });
+                            ui.end_row();
+                        },
+                    );
+
+                    ui.add_space(16.0);
+                    ui.separator();
+                    ui.add_space(16.0);
+                    state.opponent.controls(ui);
+
+                    ui.add_space(24.0);
+                    (save, cancel) = save_cancel_buttons(ui, "Save");
+                });
+            });
+        });
+
+    if save {
+        if let Some(edited) = state.settings() {
+            commands.trigger(EditSettings(edited));
+            next_menu_state.set(MenuState::Menu);
+        }
+    } else if cancel {
+        next_menu_state.set(MenuState::Menu);
+    }
+}
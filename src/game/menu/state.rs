@@ -2,13 +2,19 @@ use std::{fmt, hash, mem};
 
 use bevy::{ecs::system::QueryLens, prelude::*};
 
-use crate::{cli::CliArgs, game::LoadGame};
+use crate::{
+    cli::CliArgs,
+    game::{LoadGame, settings::Settings, stockfish::StockfishOpponent},
+};
 
-use super::{GameMenuDimLayer, PopupState};
+use super::{GameMenuDimLayer, OpponentPopupState, PgnPopupState, PopupState, SettingsPopupState};
 
 #[derive(Clone, Copy, Debug, Default, Eq, States)]
 pub enum MenuState {
     FenInput,
+    PgnInput,
+    OpponentInput,
+    SettingsInput,
     #[default]
     Menu,
     Game,
@@ -19,8 +25,8 @@ pub(super) fn init_menu_state_from_cli(
     cli_args: Res<CliArgs>,
     mut next_menu_state: ResMut<NextState<MenuState>>,
 ) {
-    next_menu_state.set(match cli_args.fen {
-        Some(_) => MenuState::Game,
+    next_menu_state.set(match (&cli_args.fen, &cli_args.pgn) {
+        (Some(_), _) | (_, Some(_)) => MenuState::Game,
         _ => MenuState::Menu,
     });
 }
@@ -54,6 +60,24 @@ pub(super) fn on_enter_menu_state_fen_input(mut fen_popup_state: ResMut<PopupSta
     fen_popup_state.reset();
 }
 
+pub(super) fn on_enter_menu_state_pgn_input(mut pgn_popup_state: ResMut<PgnPopupState>) {
+    pgn_popup_state.reset();
+}
+
+pub(super) fn on_enter_menu_state_opponent_input(
+    opponent: Res<StockfishOpponent>,
+    mut opponent_popup_state: ResMut<OpponentPopupState>,
+) {
+    opponent_popup_state.reset(&opponent);
+}
+
+pub(super) fn on_enter_menu_state_settings_input(
+    settings: Res<Settings>,
+    mut settings_popup_state: ResMut<SettingsPopupState>,
+) {
+    settings_popup_state.reset(&settings);
+}
+
 pub(super) fn on_enter_menu_state_menu(mut q_menu: Query<&mut Node, With<GameMenuDimLayer>>) {
     set_menu_display(q_menu.transmute_lens(), Display::Flex);
 }
@@ -3,26 +3,46 @@ use bevy::prelude::*;
 use crate::{cli::CliArgs, utils::NoopExts};
 
 use self::{
-    board::{MovePlugin, PieceAnimationPlugin, SelectionPlugin},
+    audio::GameAudioPlugin,
+    board::{
+        AnnotationPlugin, HistoryPlugin, MovePlugin, PieceAnimationPlugin, PremovePlugin,
+        SelectionPlugin,
+    },
     camera::setup_camera,
+    clock::ClockPlugin,
+    game_actions::GameActionsPlugin,
     menu::GameMenuLogicPlugin,
     menu::MenuState,
     mouse::MouseLogicPlugin,
+    move_entry::MoveEntryPlugin,
+    pgn::PgnPlugin,
+    settings::SettingsPlugin,
     stockfish::StockfishPlugin,
+    toast::ToastPlugin,
     ui::GameUiPlugin,
 };
 
+pub mod analysis;
 pub mod audio;
 pub mod board;
 pub mod camera;
+pub mod clock;
 pub mod consts;
 pub mod core;
 pub mod eval_bar;
+pub mod game_actions;
 pub mod game_over;
+pub mod hint;
+pub mod layout;
 pub mod menu;
 pub mod mouse;
+pub mod move_entry;
+pub mod move_list;
 pub mod panels;
+pub mod pgn;
+pub mod settings;
 pub mod stockfish;
+pub mod toast;
 pub mod ui;
 
 pub struct GameLogicPlugin;
@@ -31,13 +51,23 @@ impl Plugin for GameLogicPlugin {
     fn build(&self, app: &mut App) {
         app.noop()
             // Plugins
+            .add_plugins(MovePlugin)
+            .add_plugins(HistoryPlugin)
             .add_plugins(GameUiPlugin)
             .add_plugins(MouseLogicPlugin)
             .add_plugins(GameMenuLogicPlugin)
             .add_plugins(SelectionPlugin)
-            .add_plugins(MovePlugin)
+            .add_plugins(PremovePlugin)
+            .add_plugins(AnnotationPlugin)
+            .add_plugins(ClockPlugin)
+            .add_plugins(GameActionsPlugin)
             .add_plugins(PieceAnimationPlugin)
+            .add_plugins(PgnPlugin)
+            .add_plugins(MoveEntryPlugin)
             .add_plugins(StockfishPlugin)
+            .add_plugins(GameAudioPlugin)
+            .add_plugins(ToastPlugin)
+            .add_plugins(SettingsPlugin)
             // Events
             .add_event::<LoadGame>()
             // Startup
@@ -50,6 +80,15 @@ impl Plugin for GameLogicPlugin {
 
 fn load_game_on_startup(world: &mut World) {
     let cli_args = world.resource::<CliArgs>();
+
+    if let Some(path) = &cli_args.pgn {
+        match pgn::read_pgn_file(path) {
+            Ok(pgn) => world.trigger(pgn.into_load_game(MenuState::Game)),
+            Err(err) => error!("{err}"),
+        }
+        return;
+    }
+
     let (data, menu_state) = match cli_args.fen.as_deref().map(board::parse_fen) {
         None => ((default(), 0, 1), MenuState::Menu),
         Some(Err(err)) => {
@@ -68,6 +107,8 @@ pub struct LoadGame {
     pub menu_state: MenuState,
     pub half_move_clock: u8,
     pub full_move_count: u16,
+    /// Moves to replay from `board`, so that the game starts with them in its history.
+    pub moves: Vec<chess::ChessMove>,
 }
 
 impl LoadGame {
@@ -77,7 +118,17 @@ impl LoadGame {
         full_move_count: u16,
         menu_state: MenuState,
     ) -> Self {
-        Self { board, half_move_clock, full_move_count, menu_state }
+        Self { board, half_move_clock, full_move_count, menu_state, moves: Vec::new() }
+    }
+
+    pub fn with_moves(mut self, moves: Vec<chess::ChessMove>) -> Self {
+        self.moves = moves;
+        self
+    }
+
+    /// The position after all of the moves have been replayed.
+    pub fn final_board(&self) -> chess::Board {
+        pgn::play_moves(self.board, &self.moves)
     }
 
     pub fn in_game(board: chess::Board, half_move_clock: u8, full_move_count: u16) -> Self {
@@ -6,7 +6,10 @@ use bevy::{
 use crate::{
     debug_name,
     game::{
-        board::{BoardState, MouseSelectionEvent, Square, Tile},
+        board::{
+            AnnotationColor, BoardState, MouseAnnotationEvent, MouseSelectionEvent, Premove,
+            Square, Tile,
+        },
         consts::Z_PIECE_SELECTED,
     },
     utils::{NoopExts, hook},
@@ -32,6 +35,44 @@ pub(super) fn mouse_handler(
     }
 }
 
+/// Mark squares and draw arrows with the right mouse button, and clear them with the left.
+///
+/// The right mouse button cancels a premove instead if there is one.
+pub(super) fn annotation_mouse_handler(
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_sq: Res<MouseBoardSquare>,
+    premove: Option<ResMut<Premove>>,
+    mut drag_start: Local<Option<Square>>,
+    mut event_writer: EventWriter<MouseAnnotationEvent>,
+) {
+    if mouse_buttons.just_pressed(MouseButton::Left) && mouse_sq.is_some() {
+        event_writer.write(MouseAnnotationEvent::Clear);
+    }
+
+    if mouse_buttons.just_pressed(MouseButton::Right) {
+        *drag_start = **mouse_sq;
+        if mouse_sq.is_some()
+            && let Some(mut premove) = premove
+            && premove.is_some()
+        {
+            debug!(premove = ?**premove, "Cancel premove");
+            **premove = None;
+            *drag_start = None;
+        }
+    }
+
+    if mouse_buttons.just_released(MouseButton::Right)
+        && let Some(from) = drag_start.take()
+        && let Some(to) = **mouse_sq
+    {
+        let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        let alt = keys.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]);
+        let color = AnnotationColor::from_modifiers(shift, alt);
+        event_writer.write(MouseAnnotationEvent::Draw { from, to, color });
+    }
+}
+
 #[derive(Component)]
 pub struct DragContainer;
 
@@ -92,5 +133,5 @@ fn on_remove_dragging_piece(
     board_state: Res<BoardState>,
 ) {
     let original_tile = board_state.tile(dragging.original_square);
-    commands.entity(piece).insert(ChildOf(original_tile));
+    commands.entity(piece).try_insert(ChildOf(original_tile));
 }
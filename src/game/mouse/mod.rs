@@ -36,7 +36,7 @@ impl Plugin for MouseLogicPlugin {
             )
             .add_systems(
                 PreUpdate,
-                (mouse_handler, update_drag_container)
+                (mouse_handler, annotation_mouse_handler, update_drag_container)
                     .run_if(in_state(MenuState::Game))
                     .run_if(mouse_is_in_world),
             )
@@ -2,7 +2,7 @@ use bevy::{prelude::*, window::PrimaryWindow};
 use chess::{File, Rank};
 
 use crate::game::{
-    board::{Square, UiBoard},
+    board::{BoardOrientation, PieceColor, Square, UiBoard},
     camera::MainCamera,
 };
 
@@ -36,6 +36,7 @@ pub(super) struct MouseBoardSquare(pub Option<Square>);
 pub(super) fn mouse_world_position_to_square(
     q_board: Query<(&GlobalTransform, &ComputedNode), With<UiBoard>>,
     mouse_world_pos: Res<MouseWorldPosition>,
+    orientation: Res<BoardOrientation>,
     mut mouse_sq: ResMut<MouseBoardSquare>,
 ) {
     let mouse_pos = **mouse_world_pos;
@@ -56,7 +57,11 @@ pub(super) fn mouse_world_position_to_square(
 
     **mouse_sq = if mouse_in_board {
         let mouse_board_pos_a1 = Vec2::new(mouse_board_pos.x, board_size.y - mouse_board_pos.y);
-        let mouse_square_index = mouse_board_pos_a1 / tile_size;
+        let mouse_square_index = match **orientation {
+            PieceColor::WHITE => mouse_board_pos_a1 / tile_size,
+            // a1 is at the top right
+            PieceColor::BLACK => Vec2::splat(8.0) - mouse_board_pos_a1 / tile_size,
+        };
         let file = File::from_index(mouse_square_index.x as usize);
         let rank = Rank::from_index(mouse_square_index.y as usize);
         Some(Square::from_coords(rank, file))
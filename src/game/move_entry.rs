@@ -0,0 +1,272 @@
+use std::{fmt, str::FromStr};
+
+use bevy::prelude::*;
+use bevy_egui::{
+    EguiContexts, EguiPreUpdateSet,
+    egui::{Align2, Color32, FontId, Key, RichText, TextEdit, Window, vec2},
+};
+use chess::ChessMove;
+
+use crate::utils::NoopExts;
+
+use super::{
+    audio::PlayGameAudio,
+    board::{BoardState, MovePiece, PieceType, PromotingPiece, Square, ViewedPly},
+    menu::MenuState,
+    mouse::Dragging,
+    pgn::parse_san,
+    stockfish::StockfishOpponent,
+};
+
+pub struct MoveEntryPlugin;
+
+impl Plugin for MoveEntryPlugin {
+    fn build(&self, app: &mut App) {
+        app.noop()
+            // Resources
+            .init_resource::<MoveEntryState>()
+            .init_resource::<KeyboardCaptured>()
+            // Systems
+            .add_systems(PreUpdate, capture_keyboard.after(EguiPreUpdateSet::BeginPass))
+            .add_systems(
+                Update,
+                (open_move_entry, move_entry).chain().run_if(in_state(MenuState::Game)),
+            )
+            .add_systems(OnExit(MenuState::Game), close_move_entry)
+            .noop();
+    }
+}
+
+/// The text box in which moves are typed.
+#[derive(Default, Resource)]
+pub struct MoveEntryState {
+    open: bool,
+    focus: bool,
+    text: String,
+    error: Option<String>,
+}
+
+/// Whether keys are typed into the move entry or another egui text box, rather than being the
+/// board's keyboard shortcuts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Resource)]
+pub struct KeyboardCaptured(pub bool);
+
+/// Run condition for the board's keyboard shortcuts, which are ignored while typing.
+pub fn keyboard_not_captured(captured: Option<Res<KeyboardCaptured>>) -> bool {
+    captured.is_none_or(|captured| !captured.0)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseMoveEntryError(String);
+
+impl fmt::Display for ParseMoveEntryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "'{}' is not a legal move, expected e.g. Nf3, exd5, O-O, e7e8=Q or g1f3", self.0)
+    }
+}
+
+impl std::error::Error for ParseMoveEntryError {}
+
+/// Find the legal move on `board` described by `text`, in SAN (e.g. `Nf3`, `exd5`, `O-O`, `e8=Q`)
+/// or UCI (e.g. `g1f3`, `e7e8q`) notation.
+pub fn parse_move_entry(
+    board: &chess::Board,
+    text: &str,
+) -> Result<ChessMove, ParseMoveEntryError> {
+    let text = text.trim();
+    let err = || ParseMoveEntryError(text.to_string());
+
+    if let Some(r#move) = parse_san(board, text) {
+        return Ok(r#move);
+    }
+
+    if !matches!(text.len(), 4 | 5) {
+        return Err(err());
+    }
+    ChessMove::from_str(&text.to_ascii_lowercase())
+        .ok()
+        .filter(|&r#move| board.legal(r#move))
+        .ok_or_else(err)
+}
+
+/// Open the move entry with `Enter`.
+fn open_move_entry(keys: Res<ButtonInput<KeyCode>>, mut state: ResMut<MoveEntryState>) {
+    if !state.open && keys.just_pressed(KeyCode::Enter) {
+        *state = MoveEntryState { open: true, focus: true, ..default() };
+    }
+}
+
+fn capture_keyboard(
+    mut egui_contexts: EguiContexts,
+    state: Res<MoveEntryState>,
+    mut captured: ResMut<KeyboardCaptured>,
+) {
+    let wants_keyboard_input =
+        egui_contexts.try_ctx_mut().is_some_and(|ctx| ctx.wants_keyboard_input());
+    captured.set_if_neq(KeyboardCaptured(state.open || wants_keyboard_input));
+}
+
+fn close_move_entry(mut state: ResMut<MoveEntryState>) {
+    *state = default();
+}
+
+/// Why a move can't be entered right now, if it can't.
+fn move_entry_blocker(
+    board_state: &BoardState,
+    viewed_ply: &ViewedPly,
+    opponent: Option<&StockfishOpponent>,
+    is_busy: bool,
+) -> Option<&'static str> {
+    if board_state.is_game_over() {
+        Some("The game is over")
+    } else if !viewed_ply.is_live() {
+        Some("Go back to the current position to move")
+    } else if opponent.and_then(|opponent| opponent.side.color())
+        == Some(board_state.side_to_move())
+    {
+        Some("It is Stockfish's turn")
+    } else if is_busy {
+        Some("Finish the move on the board first")
+    } else {
+        None
+    }
+}
+
+fn move_entry(
+    mut commands: Commands,
+    mut egui_contexts: EguiContexts,
+    mut state: ResMut<MoveEntryState>,
+    board_state: Res<BoardState>,
+    viewed_ply: Res<ViewedPly>,
+    opponent: Option<Res<StockfishOpponent>>,
+    q_busy: Query<(), Or<(With<Dragging>, With<PromotingPiece>)>>,
+) {
+    if !state.open {
+        return;
+    }
+
+    let ctx = egui_contexts.ctx_mut();
+    let state = &mut *state;
+
+    let mut close = false;
+    let mut submit = false;
+
+    Window::new("Move Entry")
+        .resizable(false)
+        .title_bar(false)
+        .anchor(Align2::CENTER_BOTTOM, vec2(0.0, -16.0))
+        .show(ctx, |ui| {
+            if ui.input(|i| i.key_pressed(Key::Escape)) {
+                close = true;
+                return;
+            }
+
+            ui.horizontal(|ui| {
+                ui.label(RichText::new("Move:").font(FontId::proportional(18.0)));
+                let response = TextEdit::singleline(&mut state.text)
+                    .hint_text("Nf3")
+                    .font(FontId::proportional(16.0))
+                    .desired_width(160.0)
+                    .show(ui)
+                    .response;
+                if state.focus {
+                    state.focus = false;
+                    response.request_focus();
+                }
+                if response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+                    submit = true;
+                }
+            });
+
+            if let Some(error) = &state.error {
+                ui.label(RichText::new(error).color(Color32::from_rgb(0xba, 0x29, 0x29)));
+            }
+        });
+
+    if close {
+        state.open = false;
+        return;
+    }
+    if !submit {
+        return;
+    }
+
+    // Keep the text box focused for the next move
+    state.focus = true;
+    if state.text.trim().is_empty() {
+        state.open = false;
+        return;
+    }
+
+    let blocker =
+        move_entry_blocker(&board_state, &viewed_ply, opponent.as_deref(), !q_busy.is_empty());
+    if let Some(blocker) = blocker {
+        state.error = Some(blocker.to_string());
+        return;
+    }
+
+    match parse_move_entry(board_state.board(), &state.text) {
+        Ok(r#move) => {
+            let (from_sq, to_sq) =
+                (Square::new(r#move.get_source()), Square::new(r#move.get_dest()));
+            let promotion = r#move.get_promotion().map(PieceType);
+            debug!(%from_sq, %to_sq, ?promotion, text = state.text, "Enter move");
+            let piece = board_state.piece(from_sq);
+            commands.trigger_targets(MovePiece::new(from_sq, to_sq, promotion, true), piece);
+            state.text.clear();
+            state.error = None;
+        }
+        Err(err) => {
+            state.error = Some(err.to_string());
+            commands.queue(PlayGameAudio::Illegal);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chess::Board;
+
+    use super::*;
+
+    fn board(fen: &str) -> Board {
+        Board::from_str(fen).unwrap()
+    }
+
+    fn entry(board: &Board, text: &str) -> Option<(chess::Square, chess::Square)> {
+        parse_move_entry(board, text).ok().map(|m| (m.get_source(), m.get_dest()))
+    }
+
+    #[test]
+    fn parses_san_and_uci_moves() {
+        use chess::Square as Sq;
+
+        let start = Board::default();
+        assert_eq!(entry(&start, "Nf3"), Some((Sq::G1, Sq::F3)));
+        assert_eq!(entry(&start, " g1f3 "), Some((Sq::G1, Sq::F3)));
+        assert_eq!(entry(&start, "E2E4"), Some((Sq::E2, Sq::E4)));
+        assert_eq!(entry(&start, "Nf4"), None);
+        assert_eq!(entry(&start, "e2e5"), None);
+        assert_eq!(
+            parse_move_entry(&start, "Ke2").unwrap_err(),
+            ParseMoveEntryError("Ke2".to_string())
+        );
+
+        // Both knights can go to d2
+        let knights = board("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1");
+        assert_eq!(entry(&knights, "Nd2"), None);
+        assert_eq!(entry(&knights, "Nbd2"), Some((Sq::B1, Sq::D2)));
+        assert_eq!(entry(&knights, "f1d2"), Some((Sq::F1, Sq::D2)));
+
+        let promotion = board("8/4P3/8/8/8/8/k7/4K3 w - - 0 1");
+        for text in ["e8=Q", "e8Q", "e7e8q"] {
+            let r#move = parse_move_entry(&promotion, text).unwrap();
+            assert_eq!(r#move.get_promotion(), Some(chess::Piece::Queen), "{text}");
+        }
+        assert_eq!(entry(&promotion, "e7e8"), None);
+
+        let castling = board("4k3/8/8/8/8/8/8/4K2R w K - 0 1");
+        assert_eq!(entry(&castling, "O-O"), Some((Sq::E1, Sq::G1)));
+        assert_eq!(entry(&castling, "e1g1"), Some((Sq::E1, Sq::G1)));
+    }
+}
@@ -0,0 +1,396 @@
+use bevy::{
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    prelude::*,
+    ui::RelativeCursorPosition,
+};
+
+use crate::{
+    debug_name, debug_name_f,
+    game::{
+        LoadGame,
+        board::{
+            BoardState, HistoryPlugin, MovePieceCompleted, RedoMove, UndoMove, ViewPly, ViewedPly,
+        },
+        consts::{
+            CAPTURES_PANEL_HEIGHT, COLOR_PANEL, COLOR_PANEL_HIGHLIGHT, COLOR_PANEL_ROW_ODD,
+            COLOR_PANEL_TEXT, FONT_PATH,
+        },
+        pgn::to_san,
+        ui::MoveListContainer,
+    },
+    utils::{NoopExts, ReparentInTag, SortIndex},
+};
+
+use super::ui::spawn_ui;
+
+pub struct MoveListPlugin;
+
+impl Plugin for MoveListPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<HistoryPlugin>() {
+            panic!("Attempted to add plugin without required dependency: {HistoryPlugin:?}");
+        }
+
+        app.noop()
+            // Observers
+            .add_observer(sync_move_list_on::<MovePieceCompleted>)
+            .add_observer(sync_move_list_on::<UndoMove>)
+            .add_observer(sync_move_list_on::<RedoMove>)
+            .add_observer(rebuild_move_list_on_load_game)
+            .add_observer(highlight_move_list_on_view_ply)
+            // Systems
+            .add_systems(Startup, spawn_move_list.after(spawn_ui))
+            .add_systems(Update, scroll_move_list)
+            .noop();
+    }
+}
+
+const MOVE_FONT_SIZE: f32 = 14.0;
+
+const MOVE_ROW_HEIGHT: f32 = 24.0;
+
+const MOVE_NUMBER_WIDTH: f32 = 36.0;
+
+/// How far one line of mouse wheel scrolling moves the list.
+const SCROLL_LINE_HEIGHT: f32 = MOVE_ROW_HEIGHT;
+
+#[derive(Component)]
+pub struct MoveList;
+
+/// A move in the move list, holding the ply of the position after the move.
+#[derive(Clone, Copy, Component, Debug)]
+pub struct MoveListEntry(pub usize);
+
+pub fn spawn_move_list(mut commands: Commands) {
+    const SPACER_H: Val = Val::Px(CAPTURES_PANEL_HEIGHT);
+    let spacer_bundle = || Node { height: SPACER_H, flex_shrink: 0.0, ..default() };
+
+    let spacer_top = commands.spawn((debug_name!("Move List Spacer (Top)"), spacer_bundle())).id();
+
+    let list = commands
+        .spawn((
+            MoveList,
+            debug_name!("Move List"),
+            SortIndex(1),
+            Node {
+                width: Val::Percent(100.0),
+                flex_grow: 1.0,
+                // Let the list shrink below its content height so that it scrolls instead
+                flex_basis: Val::Px(0.0),
+                flex_direction: FlexDirection::Column,
+                overflow: Overflow::scroll_y(),
+                ..default()
+            },
+            ScrollPosition::DEFAULT,
+            RelativeCursorPosition::default(),
+            BackgroundColor(COLOR_PANEL),
+        ))
+        .id();
+
+    let spacer_bot = commands
+        .spawn((debug_name!("Move List Spacer (Bottom)"), SortIndex(3), spacer_bundle()))
+        .id();
+
+    commands.reparent_in_tag::<MoveListContainer>([spacer_top, list, spacer_bot]);
+}
+
+fn sync_move_list_on<E: Event>(_trigger: Trigger<E>, mut commands: Commands) {
+    commands.queue(SyncMoveList::default());
+}
+
+fn rebuild_move_list_on_load_game(_trigger: Trigger<LoadGame>, mut commands: Commands) {
+    commands.queue(SyncMoveList { rebuild: true });
+}
+
+fn highlight_move_list_on_view_ply(_trigger: Trigger<ViewPly>, mut commands: Commands) {
+    commands.queue(HighlightViewedPly);
+}
+
+/// A row of the move list, holding the ply of the position after its first move.
+#[derive(Clone, Copy, Component, Debug)]
+struct MoveListRow(usize);
+
+/// Bring the move list up to date with the move history, then highlight the move of the viewed
+/// ply.
+///
+/// Only the rows from the one holding the latest move onwards are respawned, unless `rebuild` is
+/// set, e.g. when a new game is loaded.
+#[derive(Default)]
+pub struct SyncMoveList {
+    pub rebuild: bool,
+}
+
+impl Command for SyncMoveList {
+    fn apply(self, world: &mut World) {
+        let Ok(list_entity) = world.query_filtered::<Entity, With<MoveList>>().single(world) else {
+            return;
+        };
+
+        let n_plies = world.resource::<BoardState>().history().entries().len();
+
+        // Respawn the row holding the latest move, as it may be missing black's move, and any
+        // undone rows after it.
+        let mut rows: Vec<(Entity, usize)> = world
+            .query::<(Entity, &MoveListRow)>()
+            .iter(world)
+            .map(|(entity, &MoveListRow(first_ply))| (entity, first_ply))
+            .collect();
+        rows.sort_by_key(|&(_, first_ply)| first_ply);
+        let n_kept = if self.rebuild {
+            0
+        } else {
+            rows.iter()
+                .take_while(|&&(_, first_ply)| first_ply <= n_plies)
+                .count()
+                .saturating_sub(1)
+        };
+        let first_ply = rows.get(n_kept).map_or(1, |&(_, first_ply)| first_ply);
+        for &(row, _) in &rows[n_kept..] {
+            world.entity_mut(row).despawn();
+        }
+
+        // Pair the new moves up into rows of (first ply, move number, white move, black move),
+        // leaving the white move empty when the row starts with black to move.
+        let board_state = world.resource::<BoardState>();
+        let entries = board_state.history().entries();
+        let mut new_rows: Vec<(usize, u16, [Option<(usize, String)>; 2])> = Vec::new();
+        for (i, entry) in entries.iter().enumerate().skip(first_ply - 1) {
+            let ply = i + 1;
+            let san = to_san(&entry.board, entry.r#move);
+            let side = entry.board.side_to_move().to_index();
+            match new_rows.last_mut() {
+                Some((_, _, moves)) if side == 1 && moves[1].is_none() => {
+                    moves[1] = Some((ply, san));
+                }
+                _ => {
+                    let mut moves = [None, None];
+                    moves[side] = Some((ply, san));
+                    new_rows.push((ply, entry.full_move_count.max(1), moves));
+                }
+            }
+        }
+
+        let font = world.resource::<AssetServer>().load(FONT_PATH);
+        let text_font = TextFont { font, font_size: MOVE_FONT_SIZE, ..default() };
+
+        for (row_i, (first_ply, number, moves)) in new_rows.into_iter().enumerate() {
+            let row_color =
+                if (n_kept + row_i) % 2 == 1 { COLOR_PANEL_ROW_ODD } else { COLOR_PANEL };
+            let row = world
+                .spawn((
+                    MoveListRow(first_ply),
+                    debug_name_f!("Move List Row ({number})"),
+                    Node {
+                        height: Val::Px(MOVE_ROW_HEIGHT),
+                        flex_shrink: 0.0,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    BackgroundColor(row_color),
+                    ChildOf(list_entity),
+                    children![(
+                        Node { width: Val::Px(MOVE_NUMBER_WIDTH), ..default() },
+                        Text(format!("{number}.")),
+                        text_font.clone(),
+                        TextColor(COLOR_PANEL_TEXT.with_alpha(0.6)),
+                        TextLayout::new_with_justify(JustifyText::Center),
+                    )],
+                ))
+                .id();
+
+            for r#move in moves {
+                let mut cell = world.spawn((
+                    Node {
+                        flex_grow: 1.0,
+                        flex_basis: Val::Px(0.0),
+                        height: Val::Percent(100.0),
+                        padding: UiRect::horizontal(Val::Px(4.0)),
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ChildOf(row),
+                ));
+
+                let Some((ply, san)) = r#move else { continue };
+
+                cell.insert((
+                    MoveListEntry(ply),
+                    Button,
+                    BackgroundColor(Color::NONE),
+                    children![(
+                        Text(san),
+                        text_font.clone(),
+                        TextColor(COLOR_PANEL_TEXT),
+                        Pickable::IGNORE,
+                    )],
+                ))
+                .observe(view_ply_on_click);
+            }
+        }
+
+        HighlightViewedPly.apply(world);
+    }
+}
+
+/// Highlight the move of the viewed ply in the move list.
+pub struct HighlightViewedPly;
+
+impl Command for HighlightViewedPly {
+    fn apply(self, world: &mut World) {
+        let Ok(list_entity) = world.query_filtered::<Entity, With<MoveList>>().single(world) else {
+            return;
+        };
+
+        let n_plies = world.resource::<BoardState>().history().entries().len();
+        let viewed_ply = world.get_resource::<ViewedPly>().and_then(|ply| ply.0);
+        let current_ply = viewed_ply.unwrap_or(n_plies);
+
+        let mut q_entries = world.query::<(&MoveListEntry, &mut BackgroundColor, &Children)>();
+        let mut texts = Vec::new();
+        for (&MoveListEntry(ply), mut bg, children) in q_entries.iter_mut(world) {
+            let is_current = ply == current_ply;
+            bg.set_if_neq(BackgroundColor(if is_current {
+                COLOR_PANEL_HIGHLIGHT
+            } else {
+                Color::NONE
+            }));
+            texts.push((children[0], is_current));
+        }
+        for (text, is_current) in texts {
+            if let Some(mut color) = world.get_mut::<TextColor>(text) {
+                color.set_if_neq(TextColor(if is_current {
+                    Color::WHITE
+                } else {
+                    COLOR_PANEL_TEXT
+                }));
+            }
+        }
+
+        // Keep the latest move in view while following the live game
+        if viewed_ply.is_none()
+            && let Some(mut scroll) = world.get_mut::<ScrollPosition>(list_entity)
+        {
+            scroll.offset_y = f32::MAX;
+        }
+    }
+}
+
+fn view_ply_on_click(
+    trigger: Trigger<Pointer<Click>>,
+    mut commands: Commands,
+    q_entry: Query<&MoveListEntry>,
+) {
+    if let Ok(&MoveListEntry(ply)) = q_entry.get(trigger.target()) {
+        commands.trigger(ViewPly(ply));
+    }
+}
+
+fn scroll_move_list(
+    mut wheel_events: EventReader<MouseWheel>,
+    mut q_list: Query<(&mut ScrollPosition, &RelativeCursorPosition), With<MoveList>>,
+) {
+    let Ok((mut scroll, cursor)) = q_list.single_mut() else {
+        wheel_events.clear();
+        return;
+    };
+    if !cursor.mouse_over() {
+        wheel_events.clear();
+        return;
+    }
+
+    for event in wheel_events.read() {
+        let dy = match event.unit {
+            MouseScrollUnit::Line => event.y * SCROLL_LINE_HEIGHT,
+            MouseScrollUnit::Pixel => event.y,
+        };
+        scroll.offset_y = (scroll.offset_y - dy).max(0.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{
+        board::Square,
+        core::{build_app, make_move},
+    };
+
+    use super::*;
+
+    /// The SAN of each move in the list, and whether it is highlighted as the current move.
+    fn move_list(app: &mut App) -> Vec<(String, bool)> {
+        let mut q_entries =
+            app.world_mut().query::<(&MoveListEntry, &BackgroundColor, &Children)>();
+        let mut entries: Vec<_> = q_entries
+            .iter(app.world())
+            .map(|(&MoveListEntry(ply), bg, children)| (ply, bg.0, children[0]))
+            .collect();
+        entries.sort_by_key(|&(ply, ..)| ply);
+        entries
+            .into_iter()
+            .map(|(_, bg, text)| {
+                let text = app.world().get::<Text>(text).unwrap().0.clone();
+                (text, bg == COLOR_PANEL_HIGHLIGHT)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lists_moves_with_current_highlighted() {
+        let mut app = build_app(());
+        make_move(&mut app, Square::F2, Square::F3);
+        make_move(&mut app, Square::E7, Square::E5);
+        make_move(&mut app, Square::G2, Square::G4);
+        make_move(&mut app, Square::D8, Square::H4);
+
+        let expected = [("f3", false), ("e5", false), ("g4", false), ("Qh4#", true)];
+        let expected = expected.map(|(san, current)| (san.to_string(), current));
+        assert_eq!(move_list(&mut app), expected);
+    }
+
+    #[test]
+    fn highlights_viewed_ply() {
+        let mut app = build_app(());
+        make_move(&mut app, Square::E2, Square::E4);
+        make_move(&mut app, Square::E7, Square::E5);
+
+        app.world_mut().trigger(ViewPly(1));
+        app.update();
+
+        let expected = [("e4".to_string(), true), ("e5".to_string(), false)];
+        assert_eq!(move_list(&mut app), expected);
+    }
+
+    #[test]
+    fn updates_rows_on_undo_and_redo() {
+        let mut app = build_app(());
+        make_move(&mut app, Square::E2, Square::E4);
+        make_move(&mut app, Square::E7, Square::E5);
+        make_move(&mut app, Square::G1, Square::F3);
+
+        /// The SAN of each move in the list, the last one highlighted, and the number of rows.
+        fn expected(sans: &[&str], rows: usize) -> (Vec<(String, bool)>, usize) {
+            let last = sans.len() - 1;
+            (sans.iter().enumerate().map(|(i, san)| (san.to_string(), i == last)).collect(), rows)
+        }
+        fn actual(app: &mut App) -> (Vec<(String, bool)>, usize) {
+            let rows = app.world_mut().query::<&MoveListRow>().iter(app.world()).count();
+            (move_list(app), rows)
+        }
+
+        app.world_mut().trigger(UndoMove);
+        app.update();
+        assert_eq!(actual(&mut app), expected(&["e4", "e5"], 1));
+
+        app.world_mut().trigger(UndoMove);
+        app.update();
+        assert_eq!(actual(&mut app), expected(&["e4"], 1));
+
+        app.world_mut().trigger(RedoMove);
+        app.update();
+        assert_eq!(actual(&mut app), expected(&["e4", "e5"], 1));
+
+        app.world_mut().trigger(RedoMove);
+        app.update();
+        assert_eq!(actual(&mut app), expected(&["e4", "e5", "Nf3"], 2));
+    }
+}
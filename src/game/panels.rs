@@ -6,8 +6,10 @@ use crate::{
 };
 
 use super::{
-    board::{CapturePlugin, CaptureState, PieceColor},
+    board::{BoardOrientation, CapturePlugin, CaptureState, PieceColor},
     consts::{CAPTURES_PANEL_HEIGHT, FONT_PATH, UI_GAP_VAL},
+    game_actions::{GameAction, GameActionButton, trigger_game_action_on_click},
+    layout::ScaledFont,
     ui::{BoardAndPanelsContainer, spawn_ui},
 };
 
@@ -21,6 +23,7 @@ impl Plugin for UiPanelsPlugin {
 
         app.noop()
             .add_systems(Startup, spawn_panels.after(spawn_ui))
+            .add_systems(Update, orient_panels.run_if(resource_changed::<BoardOrientation>))
             .add_systems(PostUpdate, captures_images_sizes.before(UiSystem::Layout))
             .noop();
     }
@@ -31,23 +34,21 @@ pub struct UiPanel;
 
 fn spawn_panels(mut commands: Commands) {
     let black_panel = PanelBuilder {
-        name: "Top Panel",
+        name: "Black Panel",
         index: 0,
         color: PieceColor::BLACK,
         profile_image_path: "images/profiles/black.png",
         profile_label: "Black",
-        margin: UiRect::bottom(UI_GAP_VAL),
     };
     let black_panel_entity = commands.spawn(black_panel.as_bundle()).id();
     commands.queue(black_panel.build(black_panel_entity));
 
     let white_panel = PanelBuilder {
-        name: "Bottom Panel",
+        name: "White Panel",
         index: 2,
         color: PieceColor::WHITE,
         profile_image_path: "images/profiles/white.png",
         profile_label: "White",
-        margin: UiRect::top(UI_GAP_VAL),
     };
     let white_panel_entity = commands.spawn(white_panel.as_bundle()).id();
     commands.queue(white_panel.build(white_panel_entity));
@@ -55,13 +56,26 @@ fn spawn_panels(mut commands: Commands) {
     commands.reparent_in_tag::<BoardAndPanelsContainer>([black_panel_entity, white_panel_entity]);
 }
 
+/// Put the panel of the side at the bottom of the board below it, and the other above it.
+///
+/// The panels are spawned with black's above the board.
+fn orient_panels(
+    orientation: Res<BoardOrientation>,
+    mut q_container: Query<&mut Node, With<BoardAndPanelsContainer>>,
+) {
+    let Ok(mut node) = q_container.single_mut() else { return };
+    node.flex_direction = match **orientation {
+        PieceColor::WHITE => FlexDirection::Column,
+        PieceColor::BLACK => FlexDirection::ColumnReverse,
+    };
+}
+
 pub struct PanelBuilder {
     pub name: &'static str,
     pub index: usize,
     pub color: PieceColor,
     pub profile_image_path: &'static str,
     pub profile_label: &'static str,
-    pub margin: UiRect,
 }
 
 impl PanelBuilder {
@@ -75,7 +89,6 @@ impl PanelBuilder {
                 width: Val::Percent(100.0),
                 height: Val::Px(CAPTURES_PANEL_HEIGHT),
                 min_height: Val::Px(CAPTURES_PANEL_HEIGHT),
-                margin: self.margin,
                 ..default()
             },
         )
@@ -97,12 +110,29 @@ pub struct ProfileImage;
 #[derive(Component)]
 pub struct PanelInnerContainer;
 
+/// The strip of the pieces of a type that a side captured.
 #[derive(Component)]
-pub struct CapturesImage;
+pub struct CapturesStrip;
+
+/// The image of a captured piece in a [`CapturesStrip`].
+#[derive(Component)]
+pub struct CapturedPieceImage;
+
+/// How far apart the captured pieces in a strip are, as a fraction of the strip's height.
+const CAPTURED_PIECES_STEP: f32 = 0.4;
 
 const PROFILE_IMAGE_SIZE: f32 = CAPTURES_PANEL_HEIGHT;
 const PROFILE_IMAGE_SIZE_VAL: Val = Val::Px(PROFILE_IMAGE_SIZE);
 
+const CLOCK_MIN_WIDTH: f32 = 96.0;
+
+const CLOCK_FONT_SIZE: f32 = 20.0;
+
+const ACTION_BUTTON_FONT_SIZE: f32 = 12.0;
+
+/// `#3c3a37`
+const ACTION_BUTTON_COLOR: Color = Color::srgb_u8(0x3c, 0x3a, 0x37);
+
 impl Command for PanelBuilderCmd {
     fn apply(self, world: &mut World) {
         let color = self.data.color;
@@ -110,6 +140,9 @@ impl Command for PanelBuilderCmd {
         let asset_server = world.resource::<AssetServer>();
         let profile_image_handle = asset_server.load(self.data.profile_image_path);
         let font = asset_server.load(FONT_PATH);
+        let clock_font = font.clone();
+        let action_font =
+            TextFont { font: font.clone(), font_size: ACTION_BUTTON_FONT_SIZE, ..default() };
 
         let mut state = SystemState::<(Commands, ResMut<CaptureState>)>::new(world);
         let (mut commands, mut capture_state) = state.get_mut(world);
@@ -139,6 +172,7 @@ impl Command for PanelBuilderCmd {
                     debug_name!("Profile Label"),
                     Text(self.data.profile_label.to_string()),
                     TextFont { font: font.clone(), font_size: 12.0, ..default() },
+                    ScaledFont(12.0),
                     TextColor(Color::WHITE),
                 ));
 
@@ -150,11 +184,9 @@ impl Command for PanelBuilderCmd {
                 })
                 .with_children(|cmds| {
                     for cap_state in capture_state[color].iter_mut() {
-                        let handle = cap_state.image_handles[0].clone();
-                        cap_state.image_entity = cmds
+                        cap_state.strip_entity = cmds
                             .spawn((
-                                CapturesImage,
-                                ImageNode::new(handle),
+                                CapturesStrip,
                                 Node {
                                     display: Display::None,
                                     margin: UiRect::right(UI_GAP_VAL),
@@ -168,11 +200,68 @@ impl Command for PanelBuilderCmd {
                         MaterialAdvantageLabel(color),
                         Text("+6".to_string()),
                         TextFont { font, font_size: 12.0, ..default() },
+                        ScaledFont(12.0),
                         TextColor(Color::srgba(1.0, 1.0, 1.0, 0.5)),
                         Visibility::Hidden,
                     ));
                 });
             });
+
+            cmds.spawn((
+                debug_name!("Game Actions"),
+                Node {
+                    height: Val::Percent(100.0),
+                    margin: UiRect::right(UI_GAP_VAL),
+                    column_gap: UI_GAP_VAL,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+            ))
+            .with_children(|cmds| {
+                for action in [GameAction::Draw(color), GameAction::Resign(color)] {
+                    cmds.spawn((
+                        GameActionButton(action),
+                        Button,
+                        Node {
+                            padding: UiRect::axes(Val::Px(6.0), Val::Px(4.0)),
+                            display: Display::None,
+                            ..default()
+                        },
+                        BorderRadius::all(Val::Px(3.0)),
+                        BackgroundColor(ACTION_BUTTON_COLOR),
+                        children![(
+                            Text::default(),
+                            action_font.clone(),
+                            ScaledFont(ACTION_BUTTON_FONT_SIZE),
+                            TextColor::WHITE,
+                            Pickable::IGNORE,
+                        )],
+                    ))
+                    .observe(trigger_game_action_on_click);
+                }
+            });
+
+            cmds.spawn((
+                ClockLabel(color),
+                debug_name!("Clock"),
+                Node {
+                    height: Val::Percent(100.0),
+                    min_width: Val::Px(CLOCK_MIN_WIDTH),
+                    padding: UiRect::horizontal(UI_GAP_VAL),
+                    justify_content: JustifyContent::FlexEnd,
+                    align_items: AlignItems::Center,
+                    display: Display::None,
+                    ..default()
+                },
+                BorderRadius::all(Val::Px(3.0)),
+                BackgroundColor::DEFAULT,
+                children![(
+                    Text::default(),
+                    TextFont { font: clock_font, font_size: CLOCK_FONT_SIZE, ..default() },
+                    ScaledFont(CLOCK_FONT_SIZE),
+                    TextColor::WHITE,
+                )],
+            ));
         });
 
         state.apply(world);
@@ -182,16 +271,28 @@ impl Command for PanelBuilderCmd {
 #[derive(Deref, Component)]
 pub struct MaterialAdvantageLabel(PieceColor);
 
+/// The clock of a side, which is hidden when the game is played without clocks.
+#[derive(Deref, Component)]
+pub struct ClockLabel(PieceColor);
+
+/// Make the captured pieces square, and overlap each with the previous one.
 fn captures_images_sizes(
-    image_assets: Res<Assets<Image>>,
-    mut q_captures_images: Query<(&ImageNode, &mut Node, &ComputedNode), With<CapturesImage>>,
+    q_strips: Query<(&ComputedNode, &Children), With<CapturesStrip>>,
+    mut q_images: Query<&mut Node, With<CapturedPieceImage>>,
 ) {
-    for (ImageNode { image, .. }, mut node, computed_node) in &mut q_captures_images {
-        if let Some(img) = image_assets.get(image) {
-            let image_size = img.size();
-            let size = computed_node.size() * computed_node.inverse_scale_factor();
-            let scale = size.y / image_size.y as f32;
-            node.width = Val::Px(image_size.x as f32 * scale);
+    for (computed_node, children) in &q_strips {
+        let size = computed_node.size().y * computed_node.inverse_scale_factor();
+        for (i, child) in children.iter().enumerate() {
+            let Ok(mut node) = q_images.get_mut(child) else { continue };
+            let width = Val::Px(size);
+            let margin_left = match i {
+                0 => Val::ZERO,
+                _ => Val::Px(-size * (1.0 - CAPTURED_PIECES_STEP)),
+            };
+            if node.width != width || node.margin.left != margin_left {
+                node.width = width;
+                node.margin.left = margin_left;
+            }
         }
     }
 }
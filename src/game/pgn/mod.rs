@@ -0,0 +1,161 @@
+use std::{
+    collections::BTreeMap,
+    error, fmt, fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use chess::{Board, ChessMove};
+
+use crate::{
+    cli::CliArgs,
+    utils::{NoopExts, ctrl_pressed},
+};
+
+use super::{
+    LoadGame,
+    board::{BoardState, PlyAnnotations},
+    menu::MenuState,
+    move_entry::keyboard_not_captured,
+};
+
+pub use self::{reader::*, san::*};
+
+mod reader;
+mod san;
+mod writer;
+
+#[derive(Debug)]
+pub struct PgnPlugin;
+
+impl Plugin for PgnPlugin {
+    fn build(&self, app: &mut App) {
+        app.noop()
+            // Observers
+            .add_observer(save_pgn)
+            // Systems
+            .add_systems(
+                Update,
+                save_pgn_shortcut.run_if(in_state(MenuState::Game).and(keyboard_not_captured)),
+            )
+            .add_systems(OnEnter(MenuState::DoGameOver), save_pgn_on_game_over)
+            .noop();
+    }
+}
+
+/// A game loaded from PGN: its tags, the position it starts from, and its mainline moves.
+#[derive(Clone, Debug)]
+pub struct Pgn {
+    pub tags: Vec<(String, String)>,
+    pub board: Board,
+    pub half_move_clock: u8,
+    pub full_move_count: u16,
+    pub moves: Vec<ChessMove>,
+    /// Comments by the ply of the position they are about, including their braces. A comment at
+    /// ply 0 comes before the first move.
+    pub comments: BTreeMap<usize, String>,
+}
+
+impl Pgn {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(n, _)| n == name).map(|(_, value)| value.as_str())
+    }
+
+    pub fn into_load_game(self, menu_state: MenuState) -> LoadGame {
+        debug!(white = ?self.tag("White"), black = ?self.tag("Black"), "Load PGN");
+        LoadGame::new(self.board, self.half_move_clock, self.full_move_count, menu_state)
+            .with_moves(self.moves)
+    }
+}
+
+pub fn write_pgn_file(path: impl AsRef<Path>, pgn: &Pgn) -> Result<(), PgnError> {
+    let path = path.as_ref();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(PgnError::Io)?;
+    }
+    fs::write(path, pgn.to_string()).map_err(PgnError::Io)
+}
+
+pub fn read_pgn_file(path: impl AsRef<Path>) -> Result<Pgn, PgnError> {
+    let text = fs::read_to_string(path).map_err(PgnError::Io)?;
+    parse_pgn(&text)
+}
+
+/// Replay `moves` from `board`, returning the final position.
+pub fn play_moves(board: Board, moves: &[ChessMove]) -> Board {
+    moves.iter().fold(board, |board, &r#move| board.make_move_new(r#move))
+}
+
+#[derive(Debug)]
+pub enum PgnError {
+    Io(io::Error),
+    InvalidTag(String),
+    InvalidFen(chess::Error),
+    InvalidMove { ply: usize, san: String },
+    Unterminated(&'static str),
+    Unexpected(char),
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "Failed to read or write PGN: {err}"),
+            Self::InvalidTag(tag) => write!(f, "Invalid PGN tag pair: {tag}"),
+            Self::InvalidFen(err) => write!(f, "Invalid PGN FEN tag: {err}"),
+            Self::InvalidMove { ply, san } => write!(f, "Invalid PGN move at ply {ply}: {san}"),
+            Self::Unterminated(what) => write!(f, "Unterminated PGN {what}"),
+            Self::Unexpected(c) => write!(f, "Unexpected '{c}' in PGN"),
+        }
+    }
+}
+
+impl error::Error for PgnError {}
+
+/// Write the current game as PGN to the `--save-pgn` path, or to a new file in the local data
+/// directory if it was not given.
+#[derive(Event, Debug)]
+pub struct SavePgn;
+
+fn save_pgn(
+    _trigger: Trigger<SavePgn>,
+    board_state: Res<BoardState>,
+    annotations: Option<Res<PlyAnnotations>>,
+    cli_args: Option<Res<CliArgs>>,
+) {
+    let path = match cli_args.and_then(|cli| cli.save_pgn.clone()) {
+        Some(path) => path,
+        None => {
+            let Some(data_d) = dirs::data_local_dir() else {
+                error!("Failed to get local data dir to save PGN in");
+                return;
+            };
+            let secs =
+                SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+            [data_d, PathBuf::from("gambit"), PathBuf::from("games"), format!("{secs}.pgn").into()]
+                .iter()
+                .collect()
+        }
+    };
+
+    let mut pgn = Pgn::from_board_state(&board_state);
+    if let Some(annotations) = annotations {
+        pgn.comments = annotations.pgn_comments();
+    }
+    match write_pgn_file(&path, &pgn) {
+        Ok(()) => info!(path = %path.display(), "Saved PGN"),
+        Err(err) => error!(path = %path.display(), "{err}"),
+    }
+}
+
+fn save_pgn_shortcut(mut commands: Commands, keys: Res<ButtonInput<KeyCode>>) {
+    if ctrl_pressed(&keys) && keys.just_pressed(KeyCode::KeyS) {
+        commands.trigger(SavePgn);
+    }
+}
+
+fn save_pgn_on_game_over(mut commands: Commands, cli_args: Option<Res<CliArgs>>) {
+    if cli_args.is_some_and(|cli| cli.save_pgn.is_some()) {
+        commands.trigger(SavePgn);
+    }
+}
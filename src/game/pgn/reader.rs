@@ -0,0 +1,298 @@
+use bevy::prelude::default;
+use chess::Board;
+
+use crate::game::board::parse_fen;
+
+use super::{Pgn, PgnError, san::parse_san};
+
+/// Parse the first game in the PGN `text`.
+///
+/// Comments, NAGs, and recursive annotation variations are skipped; only the mainline is kept.
+pub fn parse_pgn(text: &str) -> Result<Pgn, PgnError> {
+    let mut lexer = Lexer::new(text);
+
+    let mut tags = Vec::new();
+    while lexer.skip_whitespace_and_escapes() == Some('[') {
+        tags.push(lexer.tag_pair()?);
+    }
+
+    let (mut board, half_move_clock, full_move_count) =
+        match tags.iter().find(|(name, _)| name == "FEN") {
+            Some((_, fen)) => parse_fen(fen).map_err(PgnError::InvalidFen)?,
+            None => (Board::default(), 0, 1),
+        };
+    let initial_board = board;
+
+    let mut moves = Vec::new();
+    while let Some(token) = lexer.movetext_token()? {
+        match token {
+            Token::Result => break,
+            Token::San(san) => {
+                let r#move = parse_san(&board, san).ok_or_else(|| PgnError::InvalidMove {
+                    ply: moves.len() + 1,
+                    san: san.to_string(),
+                })?;
+                board = board.make_move_new(r#move);
+                moves.push(r#move);
+            }
+        }
+    }
+
+    Ok(Pgn {
+        tags,
+        board: initial_board,
+        half_move_clock,
+        full_move_count,
+        moves,
+        comments: default(),
+    })
+}
+
+enum Token<'a> {
+    San(&'a str),
+    Result,
+}
+
+struct Lexer<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(text: &'a str) -> Self {
+        Self { text, pos: 0 }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_line(&mut self) {
+        match self.rest().find('\n') {
+            Some(i) => self.pos += i + 1,
+            None => self.pos = self.text.len(),
+        }
+    }
+
+    /// Skip whitespace and `%` escape lines, then return the next character without consuming it.
+    fn skip_whitespace_and_escapes(&mut self) -> Option<char> {
+        loop {
+            let at_line_start = self.pos == 0 || self.text[..self.pos].ends_with('\n');
+            match self.peek()? {
+                c if c.is_whitespace() => {
+                    self.bump();
+                }
+                '%' if at_line_start => self.skip_line(),
+                c => return Some(c),
+            }
+        }
+    }
+
+    /// Consume a `[Name "value"]` tag pair.
+    fn tag_pair(&mut self) -> Result<(String, String), PgnError> {
+        let start = self.pos;
+        let invalid = |lexer: &Self| {
+            let line = lexer.text[start..].lines().next().unwrap_or_default();
+            PgnError::InvalidTag(line.to_string())
+        };
+
+        self.bump(); // '['
+        self.skip_whitespace_and_escapes();
+        let name_len =
+            self.rest().find(|c: char| !c.is_alphanumeric() && c != '_').unwrap_or_default();
+        if name_len == 0 {
+            return Err(invalid(self));
+        }
+        let name = self.rest()[..name_len].to_string();
+        self.pos += name_len;
+
+        if self.skip_whitespace_and_escapes() != Some('"') {
+            return Err(invalid(self));
+        }
+        self.bump();
+
+        let mut value = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => break,
+                Some('\\') => match self.bump() {
+                    Some(c @ ('"' | '\\')) => value.push(c),
+                    _ => return Err(invalid(self)),
+                },
+                Some('\n') | None => return Err(invalid(self)),
+                Some(c) => value.push(c),
+            }
+        }
+
+        if self.skip_whitespace_and_escapes() != Some(']') {
+            return Err(invalid(self));
+        }
+        self.bump();
+
+        Ok((name, value))
+    }
+
+    /// Return the next mainline move or game termination marker, skipping over move numbers,
+    /// comments, NAGs, en passant markers, and variations.
+    fn movetext_token(&mut self) -> Result<Option<Token<'a>>, PgnError> {
+        loop {
+            let Some(c) = self.skip_whitespace_and_escapes() else { return Ok(None) };
+            match c {
+                '{' => match self.rest().find('}') {
+                    Some(i) => self.pos += i + 1,
+                    None => return Err(PgnError::Unterminated("comment")),
+                },
+                ';' => self.skip_line(),
+                '(' => self.skip_variation()?,
+                ')' | '}' => return Err(PgnError::Unexpected(c)),
+                // The next game's tags
+                '[' => return Ok(None),
+                '*' => {
+                    self.bump();
+                    return Ok(Some(Token::Result));
+                }
+                _ => {
+                    let word = self.word();
+                    if matches!(word, "1-0" | "0-1" | "1/2-1/2") {
+                        return Ok(Some(Token::Result));
+                    }
+                    // Strip a leading move number, e.g. `12.` or `12...`
+                    let san = match word.find(|c: char| !c.is_ascii_digit()) {
+                        Some(i) if word[i..].starts_with('.') => word[i..].trim_start_matches('.'),
+                        Some(_) => word,
+                        None => "",
+                    };
+                    let is_annotation = san.starts_with('$')
+                        || san.chars().all(|c| "!?".contains(c))
+                        || san == "e.p.";
+                    if !is_annotation {
+                        return Ok(Some(Token::San(san)));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Consume characters up to the next whitespace or movetext delimiter.
+    fn word(&mut self) -> &'a str {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| c.is_whitespace() || matches!(c, '{' | '}' | '(' | ')' | ';' | '['))
+            .unwrap_or(rest.len())
+            .max(1);
+        self.pos += len;
+        &rest[..len]
+    }
+
+    /// Consume a (possibly nested) recursive annotation variation.
+    fn skip_variation(&mut self) -> Result<(), PgnError> {
+        let mut depth = 0usize;
+        loop {
+            match self.bump() {
+                Some('(') => depth += 1,
+                Some(')') => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                }
+                Some('{') => match self.rest().find('}') {
+                    Some(i) => self.pos += i + 1,
+                    None => return Err(PgnError::Unterminated("comment")),
+                },
+                Some(';') => self.skip_line(),
+                Some(_) => {}
+                None => return Err(PgnError::Unterminated("variation")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chess::{ChessMove, Piece, Square};
+
+    use super::{super::play_moves, *};
+
+    const GAME: &str = r#"
+[Event "Casual Game"]
+[Site "Berlin GER"]
+[Date "1852.??.??"]
+[Round "?"]
+[White "Adolf Anderssen"]
+[Black "Jean Dufresne"]
+[Result "1-0"]
+
+1.e4 e5 2.Nf3 Nc6 3.Bc4 Bc5 4.b4 Bxb4 5.c3 Ba5 6.d4 exd4 7.O-O
+d3 8.Qb3 Qf6 9.e5 Qg6 10.Re1 Nge7 11.Ba3 b5 12.Qxb5 Rb8 13.Qa4
+Bb6 14.Nbd2 Bb7 15.Ne4 Qf5 16.Bxd3 Qh5 17.Nf6+ gxf6 18.exf6
+Rg8 19.Rad1 Qxf3 20.Rxe7+ Nxe7 21.Qxd7+ Kxd7 22.Bf5+ Ke8
+23.Bd7+ Kf8 24.Bxe7# 1-0
+"#;
+
+    #[test]
+    fn parses_tags_and_mainline() {
+        let pgn = parse_pgn(GAME).unwrap();
+        assert_eq!(pgn.tag("White"), Some("Adolf Anderssen"));
+        assert_eq!(pgn.tag("Result"), Some("1-0"));
+        assert_eq!(pgn.board, Board::default());
+        assert_eq!(pgn.moves.len(), 47);
+        assert_eq!(pgn.moves[0], ChessMove::new(Square::E2, Square::E4, None));
+        let final_board = play_moves(pgn.board, &pgn.moves);
+        assert_eq!(final_board.status(), chess::BoardStatus::Checkmate);
+    }
+
+    #[test]
+    fn skips_comments_nags_and_variations() {
+        let text = "1. e4 {best by test} e5 $1 (1... c5 2. Nf3 (2. c3) d6) 2. Nf3 ; comment\n\
+                    2... Nc6 3. Bb5!? *";
+        let pgn = parse_pgn(text).unwrap();
+        assert!(pgn.tags.is_empty());
+        assert_eq!(pgn.moves.len(), 5);
+        assert_eq!(pgn.moves[4], ChessMove::new(Square::F1, Square::B5, None));
+    }
+
+    #[test]
+    fn skips_en_passant_markers() {
+        let pgn = parse_pgn("1. e4 Nf6 2. e5 d5 3. exd6 e.p. e6 *").unwrap();
+        assert_eq!(pgn.moves.len(), 6);
+        assert_eq!(pgn.moves[4], ChessMove::new(Square::E5, Square::D6, None));
+        assert_eq!(pgn.moves[5], ChessMove::new(Square::E7, Square::E6, None));
+    }
+
+    #[test]
+    fn starts_from_fen_tag() {
+        let text = r#"[SetUp "1"]
+[FEN "4k3/1P6/8/8/8/8/8/4K3 w - - 0 40"]
+
+40. b8=Q+ Kd7 *"#;
+        let pgn = parse_pgn(text).unwrap();
+        assert_eq!(pgn.full_move_count, 40);
+        assert_eq!(pgn.moves[0], ChessMove::new(Square::B7, Square::B8, Some(Piece::Queen)));
+    }
+
+    #[test]
+    fn reports_illegal_moves() {
+        let err = parse_pgn("1. e4 e5 2. Ke3 *").unwrap_err();
+        assert!(matches!(err, PgnError::InvalidMove { ply: 3, ref san } if san == "Ke3"));
+    }
+
+    #[test]
+    fn reports_malformed_tags() {
+        assert!(matches!(parse_pgn("[Event Casual]"), Err(PgnError::InvalidTag(_))));
+        assert!(matches!(parse_pgn("1. e4 {oops"), Err(PgnError::Unterminated("comment"))));
+        assert!(matches!(parse_pgn("1. e4 (1. d4"), Err(PgnError::Unterminated("variation"))));
+        assert!(matches!(parse_pgn("1. e4 ) e5"), Err(PgnError::Unexpected(')'))));
+        assert!(matches!(parse_pgn("1. e4 } e5"), Err(PgnError::Unexpected('}'))));
+    }
+}
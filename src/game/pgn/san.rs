@@ -0,0 +1,244 @@
+use std::str::FromStr;
+
+use chess::{Board, BoardStatus, ChessMove, EMPTY, File, MoveGen, Piece, Rank};
+
+fn piece_to_char(piece: Piece) -> char {
+    piece.to_string(chess::Color::White).chars().next().expect("piece string is not empty")
+}
+
+fn piece_from_char(c: char) -> Option<Piece> {
+    match c {
+        'N' => Some(Piece::Knight),
+        'B' => Some(Piece::Bishop),
+        'R' => Some(Piece::Rook),
+        'Q' => Some(Piece::Queen),
+        'K' => Some(Piece::King),
+        _ => None,
+    }
+}
+
+/// Find the legal move on `board` described by the SAN `san`.
+///
+/// Check and mate markers, suffix annotations (e.g. `!?`), and en passant markers are ignored.
+/// Castling may be written with either letter O's or zeros. Return `None` if the SAN is malformed
+/// or does not describe exactly one legal move.
+pub fn parse_san(board: &Board, san: &str) -> Option<ChessMove> {
+    let san = san.trim_end_matches(" e.p.").trim_end_matches(['+', '#', '!', '?']);
+
+    let castle_file = match san {
+        "O-O" | "0-0" => Some(File::G),
+        "O-O-O" | "0-0-0" => Some(File::C),
+        _ => None,
+    };
+    if let Some(dest_file) = castle_file {
+        let rank = board.side_to_move().to_my_backrank();
+        let source = chess::Square::make_square(rank, File::E);
+        let dest = chess::Square::make_square(rank, dest_file);
+        return MoveGen::new_legal(board).find(|m| {
+            m.get_source() == source
+                && m.get_dest() == dest
+                && board.piece_on(source) == Some(Piece::King)
+        });
+    }
+
+    let mut chars = san.chars().peekable();
+
+    let piece = match chars.peek().copied().and_then(piece_from_char) {
+        Some(piece) => {
+            chars.next();
+            piece
+        }
+        None => Piece::Pawn,
+    };
+
+    // Everything after the piece letter, with the capture marker dropped: the optional source
+    // disambiguation, the destination, then the optional promotion.
+    let mut rest: Vec<char> = chars.filter(|&c| c != 'x' && c != ':').collect();
+
+    let promotion = match rest.last().copied().and_then(piece_from_char) {
+        Some(promo) if piece == Piece::Pawn => {
+            rest.pop();
+            if rest.last() == Some(&'=') {
+                rest.pop();
+            }
+            Some(promo)
+        }
+        Some(_) => return None,
+        None => None,
+    };
+
+    if rest.len() < 2 {
+        return None;
+    }
+    let dest_str: String = rest.split_off(rest.len() - 2).into_iter().collect();
+    let dest = chess::Square::from_str(&dest_str).ok()?;
+
+    let (mut source_file, mut source_rank) = (None, None);
+    for c in rest {
+        match c {
+            'a'..='h' if source_file.is_none() => {
+                source_file = Some(File::from_index(c as usize - 'a' as usize));
+            }
+            '1'..='8' if source_rank.is_none() => {
+                source_rank = Some(Rank::from_index(c as usize - '1' as usize));
+            }
+            _ => return None,
+        }
+    }
+
+    let mut candidates = MoveGen::new_legal(board).filter(|m| {
+        m.get_dest() == dest
+            && m.get_promotion() == promotion
+            && board.piece_on(m.get_source()) == Some(piece)
+            && source_file.is_none_or(|f| m.get_source().get_file() == f)
+            && source_rank.is_none_or(|r| m.get_source().get_rank() == r)
+    });
+
+    match (candidates.next(), candidates.next()) {
+        (Some(m), None) => Some(m),
+        _ => None,
+    }
+}
+
+/// Write the legal move `move` on `board` in SAN, including the check or mate marker.
+pub fn to_san(board: &Board, r#move: ChessMove) -> String {
+    let (source, dest) = (r#move.get_source(), r#move.get_dest());
+    let piece = board.piece_on(source).unwrap_or_else(|| panic!("no piece at {source}"));
+    let is_capture = board.piece_on(dest).is_some()
+        || (piece == Piece::Pawn && source.get_file() != dest.get_file());
+
+    let mut san = String::with_capacity(8);
+
+    if piece == Piece::King && source.get_file() == File::E && dest.get_file() == File::G {
+        san.push_str("O-O");
+    } else if piece == Piece::King && source.get_file() == File::E && dest.get_file() == File::C {
+        san.push_str("O-O-O");
+    } else if piece == Piece::Pawn {
+        if is_capture {
+            san.push(file_char(source.get_file()));
+            san.push('x');
+        }
+        san.push_str(&dest.to_string());
+        if let Some(promo) = r#move.get_promotion() {
+            san.push('=');
+            san.push(piece_to_char(promo));
+        }
+    } else {
+        san.push(piece_to_char(piece));
+
+        // Other pieces of the same type that could also move to the destination
+        let mut move_gen = MoveGen::new_legal(board);
+        move_gen.set_iterator_mask(chess::BitBoard::from_square(dest));
+        let rivals: Vec<chess::Square> = move_gen
+            .map(|m| m.get_source())
+            .filter(|&sq| sq != source && board.piece_on(sq) == Some(piece))
+            .collect();
+        if !rivals.is_empty() {
+            let shares_file = rivals.iter().any(|sq| sq.get_file() == source.get_file());
+            let shares_rank = rivals.iter().any(|sq| sq.get_rank() == source.get_rank());
+            if !shares_file {
+                san.push(file_char(source.get_file()));
+            } else if !shares_rank {
+                san.push(rank_char(source.get_rank()));
+            } else {
+                san.push_str(&source.to_string());
+            }
+        }
+
+        if is_capture {
+            san.push('x');
+        }
+        san.push_str(&dest.to_string());
+    }
+
+    let after = board.make_move_new(r#move);
+    if after.status() == BoardStatus::Checkmate {
+        san.push('#');
+    } else if *after.checkers() != EMPTY {
+        san.push('+');
+    }
+
+    san
+}
+
+fn file_char(file: File) -> char {
+    (b'a' + file.to_index() as u8) as char
+}
+
+fn rank_char(rank: Rank) -> char {
+    (b'1' + rank.to_index() as u8) as char
+}
+
+#[cfg(test)]
+mod tests {
+    use chess::Square;
+
+    use super::*;
+
+    fn board(fen: &str) -> Board {
+        Board::from_str(fen).unwrap()
+    }
+
+    #[test]
+    fn parses_pawn_and_piece_moves() {
+        let board = Board::default();
+        assert_eq!(parse_san(&board, "e4"), Some(ChessMove::new(Square::E2, Square::E4, None)));
+        assert_eq!(parse_san(&board, "Nf3"), Some(ChessMove::new(Square::G1, Square::F3, None)));
+        assert_eq!(parse_san(&board, "Nf3!?"), Some(ChessMove::new(Square::G1, Square::F3, None)));
+        assert_eq!(parse_san(&board, "e5"), None);
+        assert_eq!(parse_san(&board, "Qh5"), None);
+    }
+
+    #[test]
+    fn parses_disambiguated_moves() {
+        // Knights on b1 and f3 can both reach d2.
+        let board = board("4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1");
+        assert_eq!(parse_san(&board, "Nd2"), None);
+        assert_eq!(parse_san(&board, "Nbd2"), Some(ChessMove::new(Square::B1, Square::D2, None)));
+        assert_eq!(parse_san(&board, "Nfd2"), Some(ChessMove::new(Square::F3, Square::D2, None)));
+        assert_eq!(parse_san(&board, "Nf3d2"), Some(ChessMove::new(Square::F3, Square::D2, None)));
+    }
+
+    #[test]
+    fn parses_castling_and_promotion() {
+        let board = board("r3k3/1P6/8/8/8/8/8/R3K2R w KQq - 0 1");
+        assert_eq!(parse_san(&board, "O-O"), Some(ChessMove::new(Square::E1, Square::G1, None)));
+        assert_eq!(parse_san(&board, "0-0-0+"), Some(ChessMove::new(Square::E1, Square::C1, None)));
+        assert_eq!(
+            parse_san(&board, "bxa8=Q+"),
+            Some(ChessMove::new(Square::B7, Square::A8, Some(Piece::Queen)))
+        );
+        assert_eq!(
+            parse_san(&board, "b8N"),
+            Some(ChessMove::new(Square::B7, Square::B8, Some(Piece::Knight)))
+        );
+        assert_eq!(parse_san(&board, "b8"), None);
+
+        let board = self::board("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1");
+        let en_passant = Some(ChessMove::new(Square::E5, Square::D6, None));
+        assert_eq!(parse_san(&board, "exd6"), en_passant);
+        assert_eq!(parse_san(&board, "exd6 e.p."), en_passant);
+    }
+
+    #[test]
+    fn writes_san() {
+        let board = board("r3k3/1P6/8/8/8/5N2/8/1N2K2R w Kq - 0 1");
+        let san = |from, to, promo| to_san(&board, ChessMove::new(from, to, promo));
+        assert_eq!(san(Square::E1, Square::G1, None), "O-O");
+        assert_eq!(san(Square::B1, Square::D2, None), "Nbd2");
+        assert_eq!(san(Square::F3, Square::G5, None), "Ng5");
+        assert_eq!(san(Square::B7, Square::A8, Some(Piece::Queen)), "bxa8=Q+");
+        assert_eq!(san(Square::B7, Square::B8, Some(Piece::Rook)), "b8=R+");
+        assert_eq!(san(Square::H1, Square::H8, None), "Rh8+");
+    }
+
+    #[test]
+    fn san_round_trips() {
+        let mut board = Board::default();
+        for san in ["f3", "e5", "g4", "Qh4#"] {
+            let r#move = parse_san(&board, san).unwrap();
+            assert_eq!(to_san(&board, r#move), san);
+            board = board.make_move_new(r#move);
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use std::{
+    fmt,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::default;
+use chess::Board;
+
+use crate::game::board::{BoardState, GameStatus, PieceColor, to_fen};
+
+use super::{Pgn, san::to_san};
+
+/// The longest a line of movetext may be, as recommended by the PGN specification.
+const MAX_LINE_LEN: usize = 80;
+
+impl Pgn {
+    /// Build a PGN of the moves played in the current game.
+    ///
+    /// The game starts from the position before the first move in the history, and gets `[SetUp]`
+    /// and `[FEN]` tags if that is not the standard starting position.
+    pub fn from_board_state(board_state: &BoardState) -> Self {
+        let entries = board_state.history().entries();
+        let (board, half_move_clock, full_move_count) = match entries.first() {
+            Some(first) => (first.board, first.half_move_clock, first.full_move_count),
+            None => {
+                (*board_state.board(), board_state.half_move_clock(), board_state.full_move_count())
+            }
+        };
+
+        let result = match (board_state.status(), board_state.loser()) {
+            (GameStatus::Ongoing, _) => "*",
+            (_, Some(PieceColor::WHITE)) => "0-1",
+            (_, Some(PieceColor::BLACK)) => "1-0",
+            (_, None) => "1/2-1/2",
+        };
+
+        let mut tags: Vec<(String, String)> = [
+            ("Event", "Casual Game"),
+            ("Site", "Gambit"),
+            ("Date", &today()),
+            ("Round", "-"),
+            ("White", "?"),
+            ("Black", "?"),
+            ("Result", result),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+
+        let is_standard_start =
+            board == Board::default() && half_move_clock == 0 && full_move_count == 1;
+        if !is_standard_start {
+            tags.push(("SetUp".to_string(), "1".to_string()));
+            tags.push(("FEN".to_string(), to_fen(&board, half_move_clock, full_move_count)));
+        }
+
+        let moves = entries.iter().map(|entry| entry.r#move).collect();
+
+        Self { tags, board, half_move_clock, full_move_count, moves, comments: default() }
+    }
+}
+
+impl fmt::Display for Pgn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, value) in &self.tags {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            writeln!(f, "[{name} \"{value}\"]")?;
+        }
+        if !self.tags.is_empty() {
+            writeln!(f)?;
+        }
+
+        let mut tokens = Vec::with_capacity(self.moves.len() * 3 / 2 + self.comments.len() + 2);
+        tokens.extend(self.comments.get(&0).cloned());
+        let mut board = self.board;
+        let mut full_move_count = self.full_move_count.max(1);
+        for (i, &r#move) in self.moves.iter().enumerate() {
+            // Black's moves are numbered when they don't follow white's move directly
+            let follows_comment = self.comments.contains_key(&i);
+            match board.side_to_move() {
+                chess::Color::White => tokens.push(format!("{full_move_count}.")),
+                chess::Color::Black if i == 0 || follows_comment => {
+                    tokens.push(format!("{full_move_count}..."))
+                }
+                chess::Color::Black => {}
+            }
+            tokens.push(to_san(&board, r#move));
+            tokens.extend(self.comments.get(&(i + 1)).cloned());
+            if board.side_to_move() == chess::Color::Black {
+                full_move_count += 1;
+            }
+            board = board.make_move_new(r#move);
+        }
+        tokens.push(self.tag("Result").unwrap_or("*").to_string());
+
+        let mut line_len = 0;
+        for token in tokens {
+            if line_len > 0 && line_len + 1 + token.len() > MAX_LINE_LEN {
+                writeln!(f)?;
+                line_len = 0;
+            } else if line_len > 0 {
+                f.write_str(" ")?;
+                line_len += 1;
+            }
+            f.write_str(&token)?;
+            line_len += token.len();
+        }
+        writeln!(f)
+    }
+}
+
+/// Today's date (UTC) in the PGN `YYYY.MM.DD` format.
+fn today() -> String {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    format!("{year:04}.{month:02}.{day:02}")
+}
+
+/// Convert a number of days since the Unix epoch to a (year, month, day) date.
+///
+/// See [Howard Hinnant's date algorithms][1].
+///
+/// [1]: https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use chess::{ChessMove, Square};
+
+    use super::{super::parse_pgn, *};
+
+    #[test]
+    fn writes_tags_and_movetext() {
+        let pgn = parse_pgn("1. f3 e5 2. g4 Qh4# 0-1").unwrap();
+        let pgn = Pgn { tags: vec![("Result".into(), "0-1".into())], ..pgn };
+        assert_eq!(pgn.to_string(), "[Result \"0-1\"]\n\n1. f3 e5 2. g4 Qh4# 0-1\n");
+    }
+
+    #[test]
+    fn writes_black_first_move_number() {
+        let board = Board::from_str("4k3/8/8/8/8/8/4P3/4K3 b - - 3 12").unwrap();
+        let moves = vec![
+            ChessMove::new(Square::E8, Square::D8, None),
+            ChessMove::new(Square::E2, Square::E4, None),
+        ];
+        let pgn = Pgn {
+            tags: Vec::new(),
+            board,
+            half_move_clock: 3,
+            full_move_count: 12,
+            moves,
+            comments: default(),
+        };
+        assert_eq!(pgn.to_string(), "12... Kd8 13. e4 *\n");
+    }
+
+    #[test]
+    fn writes_comments() {
+        let pgn = parse_pgn("1. e4 e5 2. Nf3").unwrap();
+        let comments = [(0, "{[%csl Gd4]}"), (1, "{[%cal Ge7e5]}"), (3, "{[%cal Rb8c6]}")];
+        let comments = comments.into_iter().map(|(ply, c)| (ply, c.to_string())).collect();
+        let pgn = Pgn { tags: Vec::new(), comments, ..pgn };
+        assert_eq!(
+            pgn.to_string(),
+            "{[%csl Gd4]} 1. e4 {[%cal Ge7e5]} 1... e5 2. Nf3 {[%cal Rb8c6]} *\n"
+        );
+        assert_eq!(parse_pgn(&pgn.to_string()).unwrap().moves, pgn.moves);
+    }
+
+    #[test]
+    fn wraps_long_movetext() {
+        let moves = "1. Nf3 Nf6 2. Ng1 Ng8 ".repeat(8);
+        let pgn = parse_pgn(&moves).unwrap();
+        let text = pgn.to_string();
+        assert!(text.lines().all(|line| line.len() <= MAX_LINE_LEN));
+        assert_eq!(parse_pgn(&text).unwrap().moves, pgn.moves);
+    }
+
+    #[test]
+    fn builds_from_board_state() {
+        let mut world = bevy::prelude::World::new();
+        world.init_resource::<BoardState>();
+        let mut board_state = world.resource_mut::<BoardState>();
+        let fen = "7k/8/6K1/8/8/8/8/R7 w - - 10 30";
+        board_state.set_board(&Board::from_str(fen).unwrap(), 10, 30);
+        board_state.make_board_move(Square::A1.into(), Square::A8.into(), None);
+        board_state.sync_status();
+
+        let pgn = Pgn::from_board_state(&board_state);
+        assert_eq!(pgn.tag("Result"), Some("1-0"));
+        assert_eq!(pgn.tag("SetUp"), Some("1"));
+        assert_eq!(pgn.tag("FEN"), Some(fen));
+        assert!(pgn.to_string().ends_with("\n30. Ra8# 1-0\n"));
+    }
+
+    #[test]
+    fn converts_days_to_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(20_744), (2026, 10, 18));
+    }
+}
@@ -0,0 +1,405 @@
+use std::{
+    fs, io,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use bevy::{
+    audio::Volume,
+    ecs::system::SystemParam,
+    prelude::*,
+    window::{PrimaryWindow, WindowResized},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::NoopExts;
+
+use super::{
+    audio::{DEFAULT_SOUND_PACK, SoundPack},
+    board::{BoardOrientation, Theme},
+    clock::{DefaultTimeControl, TimeControl},
+    consts::{INIT_WIN_HEIGHT, INIT_WIN_WIDTH},
+    layout::UiLayout,
+    stockfish::StockfishOpponent,
+};
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.noop()
+            // Resources
+            .init_resource::<SettingsPath>()
+            // Observers
+            .add_observer(apply_edited_settings)
+            // Systems
+            .add_systems(PreStartup, (load_settings, apply_settings).chain())
+            .add_systems(Update, (collect_settings, save_settings).chain())
+            .noop();
+    }
+}
+
+/// The name of the file in the config directory that the settings are saved to.
+const SETTINGS_FILE_NAME: &str = "settings.toml";
+
+/// The settings are saved once they haven't changed for this long, e.g. while the window is
+/// being resized.
+const SETTINGS_SAVE_DELAY: Duration = Duration::from_millis(500);
+
+/// The theme used when none is set.
+pub const DEFAULT_THEME: &str = "default";
+
+/// The range of [`Settings::animation_speed`].
+pub const ANIMATION_SPEEDS: RangeInclusive<f32> = 0.25..=4.0;
+
+/// The player's preferences, which are loaded from `settings.toml` in the config directory at
+/// startup and saved to it when they change.
+///
+/// Settings missing from the file are set to their defaults.
+#[derive(Clone, Debug, PartialEq, Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// The name of the board and piece theme, see [`Theme`].
+    pub theme: String,
+    pub sound: SoundSettings,
+    /// How fast pieces move, as a factor of the default speed, see [`ANIMATION_SPEEDS`].
+    pub animation_speed: f32,
+    pub board_orientation: BoardOrientation,
+    pub engine: StockfishOpponent,
+    /// The time control of new games, or `None` to play without clocks.
+    pub time_control: Option<TimeControl>,
+    pub window: WindowSize,
+    pub layout: UiLayout,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            theme: DEFAULT_THEME.to_string(),
+            sound: default(),
+            animation_speed: 1.0,
+            board_orientation: default(),
+            engine: default(),
+            time_control: None,
+            window: default(),
+            layout: default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SoundSettings {
+    /// The volume from 0 (silent) to 1 (full).
+    pub volume: f32,
+    pub muted: bool,
+    /// The name of the sound pack, see [`SoundPack`].
+    pub pack: String,
+}
+
+impl Default for SoundSettings {
+    fn default() -> Self {
+        Self { volume: 1.0, muted: false, pack: DEFAULT_SOUND_PACK.to_string() }
+    }
+}
+
+impl SoundSettings {
+    pub fn effective_volume(&self) -> Volume {
+        if self.muted { Volume::SILENT } else { Volume::Linear(self.volume) }
+    }
+}
+
+/// The size of the window, in logical pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowSize {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for WindowSize {
+    fn default() -> Self {
+        Self { width: INIT_WIN_WIDTH, height: INIT_WIN_HEIGHT }
+    }
+}
+
+/// Save the settings edited on the settings screen, and set what they control.
+#[derive(Clone, Debug, Event)]
+pub struct EditSettings(pub Settings);
+
+/// The path of the file the settings are loaded from and saved to, if any.
+#[derive(Clone, Debug, Resource)]
+pub struct SettingsPath(pub Option<PathBuf>);
+
+impl Default for SettingsPath {
+    fn default() -> Self {
+        Self(Settings::path())
+    }
+}
+
+impl Settings {
+    /// The path of the settings file, if there is a config directory.
+    pub fn path() -> Option<PathBuf> {
+        let mut path = dirs::config_local_dir()?;
+        path.push("gambit");
+        path.push(SETTINGS_FILE_NAME);
+        Some(path)
+    }
+
+    /// Read the settings from `path`, or the defaults if there is no such file or it is invalid.
+    pub fn load(path: &Path) -> Self {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                debug!(path = %path.display(), "No settings file, using the defaults");
+                return default();
+            }
+            Err(err) => {
+                warn!(path = %path.display(), %err, "Failed to read settings, using the defaults");
+                return default();
+            }
+        };
+        toml::from_str(&contents).unwrap_or_else(|err| {
+            warn!(path = %path.display(), %err, "Invalid settings, using the defaults");
+            default()
+        })
+    }
+
+    /// Write the settings to `path`, creating its directory if needed.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let contents = toml::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, contents)
+    }
+}
+
+fn load_settings(mut commands: Commands, path: Res<SettingsPath>) {
+    let settings = path.0.as_deref().map(Settings::load).unwrap_or_default();
+    debug!(?settings, "Load settings");
+    commands.insert_resource(settings);
+}
+
+/// What the settings control.
+#[derive(SystemParam)]
+struct SettingsTargets<'w, 's> {
+    opponent: ResMut<'w, StockfishOpponent>,
+    orientation: ResMut<'w, BoardOrientation>,
+    layout: ResMut<'w, UiLayout>,
+    theme: ResMut<'w, Theme>,
+    sound_pack: ResMut<'w, SoundPack>,
+    default_time_control: ResMut<'w, DefaultTimeControl>,
+    global_volume: ResMut<'w, GlobalVolume>,
+    q_window: Query<'w, 's, &'static mut Window, With<PrimaryWindow>>,
+}
+
+impl SettingsTargets<'_, '_> {
+    /// Set what `settings` control, or only what they changed from `previous` if given, so that
+    /// the rest keeps what was set since, e.g. by the command line arguments.
+    fn apply(&mut self, settings: &Settings, previous: Option<&Settings>) {
+        if previous.is_none_or(|prev| prev.engine != settings.engine) {
+            self.opponent.set_if_neq(settings.engine);
+        }
+        if previous.is_none_or(|prev| prev.board_orientation != settings.board_orientation) {
+            self.orientation.set_if_neq(settings.board_orientation);
+        }
+        if previous.is_none_or(|prev| prev.layout != settings.layout) {
+            self.layout.set_if_neq(settings.layout);
+        }
+        if self.theme.name != settings.theme {
+            *self.theme = Theme::load_or_default(&settings.theme);
+        }
+        if self.sound_pack.name != settings.sound.pack {
+            *self.sound_pack = SoundPack::load(&settings.sound.pack);
+        }
+        if previous.is_none_or(|prev| prev.time_control != settings.time_control) {
+            self.default_time_control.set_if_neq(DefaultTimeControl(settings.time_control));
+        }
+        self.global_volume.volume = settings.sound.effective_volume();
+
+        if previous.is_none_or(|prev| prev.window != settings.window)
+            && let Ok(mut window) = self.q_window.single_mut()
+        {
+            let WindowSize { width, height } = settings.window;
+            if window.width() != width || window.height() != height {
+                window.resolution.set(width, height);
+            }
+        }
+    }
+}
+
+/// Set what the settings control at startup, before the command line arguments are applied.
+fn apply_settings(settings: Res<Settings>, mut targets: SettingsTargets) {
+    targets.apply(&settings, None);
+}
+
+/// Save the settings from the settings screen, and set only what was changed there.
+fn apply_edited_settings(
+    trigger: Trigger<EditSettings>,
+    mut settings: ResMut<Settings>,
+    mut targets: SettingsTargets,
+) {
+    let EditSettings(edited) = trigger.event();
+    targets.apply(edited, Some(&settings));
+    settings.set_if_neq(edited.clone());
+}
+
+/// Keep the settings up to date with what is changed outside the settings screen, e.g. by
+/// flipping the board or resizing the window.
+///
+/// Changes made at startup, i.e. by command line arguments, aren't kept.
+fn collect_settings(
+    mut settings: ResMut<Settings>,
+    opponent: Res<StockfishOpponent>,
+    orientation: Res<BoardOrientation>,
+    layout: Res<UiLayout>,
+    mut resized_reader: EventReader<WindowResized>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+) {
+    if changed_after_startup(&opponent) && settings.engine != *opponent {
+        settings.engine = *opponent;
+    }
+    if changed_after_startup(&orientation) && settings.board_orientation != *orientation {
+        settings.board_orientation = *orientation;
+    }
+    if changed_after_startup(&layout) && settings.layout != *layout {
+        settings.layout = *layout;
+    }
+    if resized_reader.read().last().is_some()
+        && let Ok(window) = q_window.single()
+    {
+        let size = WindowSize { width: window.width(), height: window.height() };
+        if settings.window != size {
+            settings.window = size;
+        }
+    }
+}
+
+fn changed_after_startup<T: Resource>(resource: &Res<T>) -> bool {
+    resource.is_changed() && !resource.is_added()
+}
+
+/// Save the settings once they stop changing.
+fn save_settings(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    path: Res<SettingsPath>,
+    mut save_timer: Local<Option<Timer>>,
+) {
+    if changed_after_startup(&settings) {
+        *save_timer = Some(Timer::new(SETTINGS_SAVE_DELAY, TimerMode::Once));
+    }
+
+    let Some(timer) = save_timer.as_mut() else { return };
+    if !timer.tick(time.delta()).finished() {
+        return;
+    }
+    *save_timer = None;
+
+    let Some(path) = &path.0 else {
+        warn!("No config directory to save the settings to");
+        return;
+    };
+    match settings.save(path) {
+        Ok(()) => debug!(path = %path.display(), "Save settings"),
+        Err(err) => error!(path = %path.display(), %err, "Failed to save settings"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{
+        audio::GameAudioPlugin,
+        board::{FlipBoard, PieceColor},
+        clock::ClockPlugin,
+        core::build_app,
+        layout::LayoutMode,
+        stockfish::{OpponentSide, SearchLimit},
+    };
+
+    use super::*;
+
+    #[test]
+    fn reads_and_writes_settings_files() {
+        // Missing settings are set to their defaults
+        let settings: Settings = toml::from_str(
+            r#"
+                animation_speed = 2.0
+                board_orientation = "black"
+                time_control = "5+3"
+
+                [engine]
+                side = "white"
+                limit = { depth = 12 }
+
+                [layout]
+                mode = "portrait"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            settings,
+            Settings {
+                animation_speed: 2.0,
+                board_orientation: BoardOrientation(PieceColor::BLACK),
+                time_control: Some("5+3".parse().unwrap()),
+                engine: StockfishOpponent {
+                    side: OpponentSide::White,
+                    limit: SearchLimit::Depth(12),
+                    ..default()
+                },
+                layout: UiLayout { mode: LayoutMode::Portrait, ..default() },
+                ..default()
+            }
+        );
+
+        let contents = toml::to_string_pretty(&settings).unwrap();
+        assert_eq!(toml::from_str::<Settings>(&contents).unwrap(), settings);
+        assert!(toml::from_str::<Settings>("board_orientation = \"red\"").is_err());
+    }
+
+    #[test]
+    fn keeps_command_line_overrides_when_settings_change() {
+        let mut app = build_app((
+            GameAudioPlugin,
+            ClockPlugin,
+            |app: &mut App| {
+                app.insert_resource(SettingsPath(None))
+                    .init_resource::<StockfishOpponent>()
+                    .add_event::<WindowResized>()
+                    // As `--opponent white` does
+                    .add_systems(Startup, |mut opponent: ResMut<StockfishOpponent>| {
+                        opponent.side = OpponentSide::White;
+                    });
+            },
+            SettingsPlugin,
+        ));
+        let opponent = |app: &App| app.world().resource::<StockfishOpponent>().side;
+        assert_eq!(opponent(&app), OpponentSide::White);
+
+        app.world_mut().trigger(FlipBoard);
+        app.update();
+        app.update();
+        let settings = app.world().resource::<Settings>();
+        assert_eq!(settings.board_orientation, BoardOrientation(PieceColor::BLACK));
+        assert_eq!(settings.engine.side, OpponentSide::None);
+        assert_eq!(opponent(&app), OpponentSide::White);
+
+        // Only what is edited on the settings screen is set
+        let edited = Settings { animation_speed: 2.0, ..settings.clone() };
+        app.world_mut().trigger(EditSettings(edited));
+        app.update();
+        assert_eq!(app.world().resource::<Settings>().animation_speed, 2.0);
+        assert_eq!(opponent(&app), OpponentSide::White);
+
+        let edited = Settings {
+            engine: StockfishOpponent { side: OpponentSide::Black, ..default() },
+            ..app.world().resource::<Settings>().clone()
+        };
+        app.world_mut().trigger(EditSettings(edited));
+        app.update();
+        assert_eq!(opponent(&app), OpponentSide::Black);
+    }
+}
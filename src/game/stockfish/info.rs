@@ -0,0 +1,236 @@
+use std::str::FromStr;
+
+use bevy::prelude::*;
+use chess::ChessMove;
+
+use crate::game::board::PieceColor;
+
+/// A search score, from the point of view of the side to move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Score {
+    /// An evaluation in centipawns.
+    Centipawns(i32),
+    /// Mate in this many moves (not plies), negative if the side to move is getting mated.
+    Mate(i32),
+}
+
+impl Score {
+    /// Convert a score from the point of view of `side_to_move` to white's point of view.
+    pub fn for_white(self, side_to_move: PieceColor) -> Self {
+        if side_to_move == PieceColor::WHITE {
+            return self;
+        }
+        match self {
+            Self::Centipawns(cp) => Self::Centipawns(-cp),
+            Self::Mate(moves) => Self::Mate(-moves),
+        }
+    }
+}
+
+/// Whether a score is exact or only a bound on the real score.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ScoreBound {
+    #[default]
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EngineScore {
+    pub score: Score,
+    pub bound: ScoreBound,
+}
+
+/// The search progress reported by a UCI `info` line. Fields that are absent from the line are
+/// `None` (or empty).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Event)]
+pub struct EngineInfo {
+    pub depth: Option<u32>,
+    pub seldepth: Option<u32>,
+    /// The rank of the line this info is about, starting at 1, when searching multiple lines.
+    pub multipv: Option<u32>,
+    pub score: Option<EngineScore>,
+    pub nodes: Option<u64>,
+    pub nps: Option<u64>,
+    /// How full the hash table is, in permill.
+    pub hashfull: Option<u32>,
+    pub tbhits: Option<u64>,
+    /// Milliseconds searched.
+    pub time: Option<u64>,
+    /// The principal variation, starting with the move to play.
+    pub pv: Vec<ChessMove>,
+    pub currmove: Option<ChessMove>,
+    pub currmovenumber: Option<u32>,
+}
+
+impl EngineInfo {
+    /// Parse a UCI `info` line.
+    ///
+    /// Unknown fields are skipped. Return `None` if the line is not an `info` line or a known field
+    /// has a malformed value.
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut tokens = line.split_whitespace().peekable();
+        if tokens.next() != Some("info") {
+            return None;
+        }
+
+        fn value<T: FromStr>(token: Option<&str>) -> Option<T> {
+            token?.parse().ok()
+        }
+
+        let mut info = Self::default();
+        while let Some(token) = tokens.next() {
+            match token {
+                "depth" => info.depth = Some(value(tokens.next())?),
+                "seldepth" => info.seldepth = Some(value(tokens.next())?),
+                "multipv" => info.multipv = Some(value(tokens.next())?),
+                "nodes" => info.nodes = Some(value(tokens.next())?),
+                "nps" => info.nps = Some(value(tokens.next())?),
+                "hashfull" => info.hashfull = Some(value(tokens.next())?),
+                "tbhits" => info.tbhits = Some(value(tokens.next())?),
+                "time" => info.time = Some(value(tokens.next())?),
+                "currmove" => info.currmove = Some(value(tokens.next())?),
+                "currmovenumber" => info.currmovenumber = Some(value(tokens.next())?),
+                "score" => {
+                    let score = match tokens.next()? {
+                        "cp" => Score::Centipawns(value(tokens.next())?),
+                        "mate" => Score::Mate(value(tokens.next())?),
+                        _ => return None,
+                    };
+                    let bound = match tokens.peek() {
+                        Some(&"lowerbound") => ScoreBound::Lower,
+                        Some(&"upperbound") => ScoreBound::Upper,
+                        _ => ScoreBound::Exact,
+                    };
+                    if bound != ScoreBound::Exact {
+                        tokens.next();
+                    }
+                    info.score = Some(EngineScore { score, bound });
+                }
+                "pv" => {
+                    while let Some(r#move) = tokens.peek().and_then(|t| ChessMove::from_str(t).ok())
+                    {
+                        info.pv.push(r#move);
+                        tokens.next();
+                    }
+                }
+                // The rest of the line is free-form text
+                "string" => break,
+                _ => {}
+            }
+        }
+
+        Some(info)
+    }
+}
+
+/// The latest completed line of each principal variation of the current search.
+#[derive(Debug, Default, Resource)]
+pub struct EngineAnalysis {
+    /// The position being searched.
+    pub fen: Option<String>,
+    /// The lines ordered by rank, best first.
+    pub lines: Vec<EngineInfo>,
+}
+
+impl EngineAnalysis {
+    /// The side to move in the position being searched.
+    pub fn side_to_move(&self) -> Option<PieceColor> {
+        match self.fen.as_deref()?.split_whitespace().nth(1)? {
+            "w" => Some(PieceColor::WHITE),
+            "b" => Some(PieceColor::BLACK),
+            _ => None,
+        }
+    }
+
+    pub fn reset(&mut self, fen: String) {
+        self.fen = Some(fen);
+        self.lines.clear();
+    }
+
+    /// Record `info` if it completes a line, i.e. it has a score and a principal variation.
+    pub fn update(&mut self, info: &EngineInfo) {
+        if info.score.is_none() || info.pv.is_empty() {
+            return;
+        }
+
+        let index = info.multipv.unwrap_or(1).max(1) as usize - 1;
+        if index >= self.lines.len() {
+            self.lines.resize_with(index + 1, default);
+        }
+        self.lines[index] = info.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chess::{Piece, Square};
+
+    use super::*;
+
+    #[test]
+    fn parses_search_info() {
+        let line = "info depth 18 seldepth 24 multipv 2 score cp -35 upperbound nodes 812345 \
+            nps 1203456 hashfull 312 tbhits 0 time 675 pv e7e5 g1f3 b8c6";
+        let info = EngineInfo::parse(line).unwrap();
+        assert_eq!(
+            info,
+            EngineInfo {
+                depth: Some(18),
+                seldepth: Some(24),
+                multipv: Some(2),
+                score: Some(EngineScore {
+                    score: Score::Centipawns(-35),
+                    bound: ScoreBound::Upper
+                }),
+                nodes: Some(812_345),
+                nps: Some(1_203_456),
+                hashfull: Some(312),
+                tbhits: Some(0),
+                time: Some(675),
+                pv: vec![
+                    ChessMove::new(Square::E7, Square::E5, None),
+                    ChessMove::new(Square::G1, Square::F3, None),
+                    ChessMove::new(Square::B8, Square::C6, None),
+                ],
+                ..default()
+            }
+        );
+    }
+
+    #[test]
+    fn parses_mate_and_current_move() {
+        let info = EngineInfo::parse("info depth 5 score mate -3 wdl 0 0 1000 pv a7a8q").unwrap();
+        let expected = EngineScore { score: Score::Mate(-3), bound: ScoreBound::Exact };
+        assert_eq!(info.score, Some(expected));
+        assert_eq!(info.pv, [ChessMove::new(Square::A7, Square::A8, Some(Piece::Queen))]);
+
+        let info = EngineInfo::parse("info depth 12 currmove d2d4 currmovenumber 3").unwrap();
+        assert_eq!(info.currmove, Some(ChessMove::new(Square::D2, Square::D4, None)));
+        assert_eq!(info.currmovenumber, Some(3));
+    }
+
+    #[test]
+    fn rejects_malformed_info() {
+        assert_eq!(EngineInfo::parse("bestmove e2e4"), None);
+        assert_eq!(EngineInfo::parse("info depth deep"), None);
+        assert_eq!(EngineInfo::parse("info score 35"), None);
+    }
+
+    #[test]
+    fn keeps_latest_line_per_rank() {
+        let mut analysis = EngineAnalysis::default();
+        for line in [
+            "info depth 10 multipv 1 score cp 20 pv e2e4",
+            "info depth 10 multipv 2 score cp 15 pv d2d4",
+            "info depth 11 currmove e2e4 currmovenumber 1",
+            "info depth 11 multipv 1 score cp 25 pv g1f3",
+        ] {
+            analysis.update(&EngineInfo::parse(line).unwrap());
+        }
+        assert_eq!(analysis.lines.len(), 2);
+        assert_eq!(analysis.lines[0].pv, [ChessMove::new(Square::G1, Square::F3, None)]);
+        assert_eq!(analysis.lines[1].depth, Some(10));
+    }
+}
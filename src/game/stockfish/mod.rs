@@ -0,0 +1,1021 @@
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    fmt,
+    fs::{self, File},
+    io::{BufRead, BufReader, Write},
+    ops::RangeInclusive,
+    os::unix::fs::PermissionsExt,
+    path::PathBuf,
+    process::{self, Stdio},
+    str::FromStr,
+};
+
+use bevy::{prelude::*, tasks::IoTaskPool};
+use chess::ChessMove;
+use clap::ValueEnum;
+use crossbeam_channel::{Receiver, TryRecvError, unbounded};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cli::CliArgs,
+    game::{
+        analysis::{AnalysisMode, AnalysisPlugin},
+        board::{PieceColor, PieceType, Square},
+        clock::ChessClock,
+        eval_bar::EvaluationUpdate,
+        hint::ShowHint,
+        layout::UiLayout,
+        settings::Settings,
+    },
+    utils::NoopExts,
+};
+
+use super::{
+    board::{BoardOrientation, BoardPlugin, BoardState, MovePiece, MovePlugin, ViewPly, ViewedPly},
+    menu::MenuState,
+};
+
+pub use self::info::*;
+
+mod info;
+
+const STOCKFISH_EXECUTABLE: &[u8] =
+    include_bytes!("../../../target/stockfish/Stockfish-sf_15/src/stockfish");
+
+pub struct StockfishPlugin;
+
+impl Plugin for StockfishPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<MovePlugin>() {
+            panic!("Attempted to add plugin without required dependency: {MovePlugin:?}");
+        }
+        if !app.is_plugin_added::<AnalysisPlugin>() {
+            panic!("Attempted to add plugin without required dependency: {AnalysisPlugin:?}");
+        }
+        if !app.is_plugin_added::<BoardPlugin>() {
+            panic!("Attempted to add plugin without required dependency: {BoardPlugin:?}");
+        }
+
+        app.noop()
+            // Events
+            .add_event::<SfCommand>()
+            .add_event::<EngineInfo>()
+            // Resources
+            .init_resource::<SfCommunications>()
+            .init_resource::<EngineAnalysis>()
+            .init_resource::<StockfishOpponent>()
+            .init_resource::<SearchedPosition>()
+            // Systems
+            .add_systems(Startup, init_stockfish_opponent_from_cli)
+            .add_systems(PostStartup, initialize_stockfish)
+            .add_systems(
+                Update,
+                (configure_stockfish_opponent, orient_board_to_player)
+                    .run_if(resource_changed::<StockfishOpponent>),
+            )
+            .add_systems(
+                Update,
+                configure_multipv.run_if(resource_changed::<AnalysisMode>).before(search_position),
+            )
+            .add_systems(Update, search_position.run_if(in_state(MenuState::Game)))
+            .add_systems(OnExit(MenuState::Game), stop_analysis_on_exit_game)
+            .add_systems(PostUpdate, stockfish_update)
+            .noop();
+    }
+}
+
+#[derive(Clone, Debug, Event)]
+#[allow(dead_code)]
+pub enum SfCommand {
+    Uci,
+    IsReady,
+    UciNewGame,
+    SetOption(&'static str, String), // name, value
+    Position(String),                // FEN
+    Go(GoParams),
+    Stop,
+    #[cfg(feature = "debug-inspector")]
+    Custom(String),
+}
+
+impl SfCommand {
+    pub fn to_str(&self) -> Cow<'_, str> {
+        match self {
+            Self::Uci => Cow::Borrowed("uci\n"),
+            Self::IsReady => Cow::Borrowed("isready\n"),
+            Self::UciNewGame => Cow::Borrowed("ucinewgame\n"),
+            Self::SetOption(name, value) => {
+                Cow::Owned(format!("setoption name {name} value {value}\n"))
+            }
+            Self::Position(fen) => Cow::Owned(format!("position fen {fen}\n")),
+            Self::Go(params) => Cow::Owned(format!("{params}\n")),
+            Self::Stop => Cow::Borrowed("stop\n"),
+            #[cfg(feature = "debug-inspector")]
+            Self::Custom(s) => Cow::Borrowed(s.as_str()),
+        }
+    }
+
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        match self.to_str() {
+            Cow::Borrowed(s) => Cow::Borrowed(s.as_bytes()),
+            Cow::Owned(s) => Cow::Owned(s.into_bytes()),
+        }
+    }
+}
+
+/// The parameters of the UCI `go` command.
+///
+/// Times are in milliseconds. A search with no limits, or with `infinite` set, runs until it is
+/// stopped with [`SfCommand::Stop`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GoParams {
+    pub movetime: Option<u32>,
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub wtime: Option<u32>,
+    pub btime: Option<u32>,
+    pub winc: Option<u32>,
+    pub binc: Option<u32>,
+    pub movestogo: Option<u32>,
+    /// Only search these moves.
+    pub searchmoves: Vec<ChessMove>,
+    pub ponder: bool,
+    pub infinite: bool,
+}
+
+impl GoParams {
+    /// Whether the search ends on its own, as opposed to running until it is stopped.
+    pub fn is_bounded(&self) -> bool {
+        !self.infinite
+            && !self.ponder
+            && (self.movetime.is_some()
+                || self.depth.is_some()
+                || self.nodes.is_some()
+                || self.wtime.is_some()
+                || self.btime.is_some())
+    }
+}
+
+impl From<SearchLimit> for GoParams {
+    fn from(limit: SearchLimit) -> Self {
+        match limit {
+            SearchLimit::MoveTime(ms) => Self { movetime: Some(ms), ..default() },
+            SearchLimit::Depth(depth) => Self { depth: Some(depth), ..default() },
+            SearchLimit::Nodes(nodes) => Self { nodes: Some(nodes), ..default() },
+        }
+    }
+}
+
+impl fmt::Display for GoParams {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("go")?;
+        if !self.searchmoves.is_empty() {
+            f.write_str(" searchmoves")?;
+            for r#move in &self.searchmoves {
+                write!(f, " {move}")?;
+            }
+        }
+        if self.ponder {
+            f.write_str(" ponder")?;
+        }
+        let limits = [
+            ("wtime", self.wtime.map(u64::from)),
+            ("btime", self.btime.map(u64::from)),
+            ("winc", self.winc.map(u64::from)),
+            ("binc", self.binc.map(u64::from)),
+            ("movestogo", self.movestogo.map(u64::from)),
+            ("depth", self.depth.map(u64::from)),
+            ("nodes", self.nodes),
+            ("movetime", self.movetime.map(u64::from)),
+        ];
+        for (name, value) in limits {
+            if let Some(value) = value {
+                write!(f, " {name} {value}")?;
+            }
+        }
+        if self.infinite {
+            f.write_str(" infinite")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Resource)]
+pub struct Stockfish {
+    stdin: Box<dyn Write + Send + Sync>,
+    response: Receiver<String>,
+    command_queue: VecDeque<SfCommand>,
+}
+
+impl Stockfish {
+    fn new(stdin: impl Write + Send + Sync + 'static, response: Receiver<String>) -> Self {
+        Self { stdin: Box::new(stdin), response, command_queue: VecDeque::new() }
+    }
+
+    // #[cfg(feature = "debug-inspector")]
+    pub fn push_cmd(&mut self, command: SfCommand) {
+        self.command_queue.push_back(command);
+    }
+
+    pub fn extend_cmds(&mut self, commands: impl IntoIterator<Item = SfCommand>) {
+        self.command_queue.extend(commands);
+    }
+
+    fn pop_cmd(&mut self) -> Option<SfCommand> {
+        self.command_queue.pop_front()
+    }
+
+    fn unpop_cmd(&mut self, command: SfCommand) {
+        self.command_queue.push_front(command);
+    }
+
+    /// Whether a search of a position other than `search_fen` is queued.
+    fn has_new_search_cmd(&self, search_fen: Option<&str>) -> bool {
+        self.command_queue.iter().any(|command| match command {
+            SfCommand::Position(fen) => Some(fen.as_str()) != search_fen,
+            _ => false,
+        })
+    }
+
+    fn write_cmd(&mut self, command: SfCommand) {
+        let cmd_bytes = command.to_bytes();
+        self.stdin.write_all(&cmd_bytes).expect("write command to stockfish stdin");
+    }
+
+    fn iter_responses(&self) -> impl Iterator<Item = String> + '_ {
+        SfResponseIter(&self.response)
+    }
+}
+
+struct SfResponseIter<'r>(&'r Receiver<String>);
+
+impl Iterator for SfResponseIter<'_> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.0.try_recv() {
+            Ok(line) => Some(line),
+            Err(TryRecvError::Empty) => None,
+            Err(err) => {
+                error!("{err}");
+                panic!();
+            }
+        }
+    }
+}
+
+fn initialize_stockfish(mut commands: Commands) {
+    let stockfish_p = ensure_stockfish_executable();
+
+    #[allow(clippy::zombie_processes)]
+    let mut proc = process::Command::new(stockfish_p)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("start stockfish");
+
+    let stdin = proc.stdin.take().expect("get handle to stockfish stdin");
+    let stdout = proc.stdout.take().expect("get handle to stockfish stdout");
+
+    let task_pool = IoTaskPool::get();
+    let (response_tx, response_rx) = unbounded::<String>();
+
+    // Read responses from proc stdout
+    let task = async move {
+        let mut reader = BufReader::new(stdout);
+        let mut line = String::with_capacity(1000);
+        while let Ok(count) = reader.read_line(&mut line) {
+            if count == 0 {
+                break;
+            }
+            let Ok(()) = response_tx.send(line.trim().to_string()) else { return };
+            line.clear();
+        }
+    };
+    task_pool.spawn(task).detach();
+
+    let mut sf = Stockfish::new(stdin, response_rx);
+
+    sf.extend_cmds([SfCommand::Uci, SfCommand::UciNewGame, SfCommand::IsReady]);
+
+    commands.insert_resource(sf);
+}
+
+fn ensure_stockfish_executable() -> PathBuf {
+    let mut config_d = dirs::config_local_dir().expect("get local config dir");
+    config_d.push("gambit");
+
+    if !config_d.exists() {
+        trace!(path = %config_d.display(), "Create config dir");
+        fs::create_dir_all(&config_d).expect("create config dir");
+    } else if !config_d.is_dir() {
+        error!(path = %config_d.display(), "Path for config directory must be a directory");
+        panic!();
+    }
+
+    let mut stockfish_p = config_d;
+    stockfish_p.push("stockfish");
+
+    if stockfish_p.exists() {
+        if !stockfish_p.is_file() {
+            error!(path = %stockfish_p.display(), "Path for stockfish executable must be a file");
+        }
+    } else {
+        trace!(path = %stockfish_p.display(), "Create stockfish executable");
+
+        let mut f = File::options()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&stockfish_p)
+            .expect("create file for stockfish executable");
+
+        f.write_all(STOCKFISH_EXECUTABLE).expect("write to temp file");
+        let mut perms = f.metadata().expect("get temp file metadata").permissions();
+        perms.set_mode(0o700);
+        f.set_permissions(perms).expect("set temp file permissions");
+    }
+
+    stockfish_p
+}
+
+#[derive(Deref, DerefMut, Resource)]
+pub struct SfCommunications {
+    #[deref]
+    messages: VecDeque<SfMessage>,
+    /// The number of responses dropped from the front, which cursors count from.
+    dropped: usize,
+}
+
+impl Default for SfCommunications {
+    fn default() -> Self {
+        Self { messages: VecDeque::with_capacity(4000), dropped: 0 }
+    }
+}
+
+impl SfCommunications {
+    fn iter_responses_from<'a>(&'a self, cursor: &'a mut usize) -> impl Iterator<Item = &'a str> {
+        let iter =
+            self.messages.iter().filter_map(SfMessage::as_response).skip(*cursor - self.dropped);
+        CursorIterator::new(iter, cursor)
+    }
+
+    /// Drop the responses before `cursor`, which have been read, and the commands before them.
+    fn drop_read(&mut self, cursor: usize) {
+        while self.dropped < cursor
+            && let Some(message) = self.messages.pop_front()
+        {
+            if message.as_response().is_some() {
+                self.dropped += 1;
+            }
+        }
+    }
+
+    /// The info lines from `cursor` on, parsed, or the line itself if it couldn't be parsed.
+    fn iter_infos_from<'a>(
+        &'a self,
+        cursor: &'a mut usize,
+    ) -> impl Iterator<Item = Result<EngineInfo, &'a str>> {
+        self.iter_responses_from(cursor)
+            .filter(|line| line.starts_with("info ") && !line.starts_with("info string"))
+            .map(|line| EngineInfo::parse(line).ok_or(line))
+    }
+}
+
+pub enum SfMessage {
+    #[cfg(feature = "debug-inspector")]
+    Command(SfCommand),
+    Response(String),
+}
+
+impl SfMessage {
+    fn as_response(&self) -> Option<&str> {
+        match self {
+            Self::Response(response) => Some(response),
+            #[cfg(feature = "debug-inspector")]
+            Self::Command(_) => None,
+        }
+    }
+}
+
+struct CursorIterator<'a, I> {
+    inner: I,
+    cursor: &'a mut usize,
+}
+
+impl<'a, I> CursorIterator<'a, I> {
+    fn new(inner: I, cursor: &'a mut usize) -> Self {
+        Self { inner, cursor }
+    }
+}
+
+impl<'a, I: Iterator> Iterator for CursorIterator<'a, I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.inner.next()?;
+        *self.cursor += 1;
+        Some(item)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+enum SfState {
+    #[default]
+    Idle,
+    WaitingUci,
+    WaitingReady,
+    WaitingFinishSearch,
+}
+
+fn stockfish_update(
+    mut commands: Commands,
+    board_state: Res<BoardState>,
+    mut sf_comms: ResMut<SfCommunications>,
+    mut response_cursor: Local<usize>,
+    mut info_cursor: Local<usize>,
+    mut analysis: ResMut<EngineAnalysis>,
+    mut info_writer: EventWriter<EngineInfo>,
+    mut search_fen: Local<Option<String>>,
+    mut is_searching: Local<bool>,
+    mut stockfish: ResMut<Stockfish>,
+    mut sf_state: Local<SfState>,
+    mut eval_bar_writer: EventWriter<EvaluationUpdate>,
+    opponent: Res<StockfishOpponent>,
+    viewed_ply: Option<Res<ViewedPly>>,
+) {
+    sf_comms.extend(stockfish.iter_responses().map(SfMessage::Response));
+
+    for info in sf_comms.iter_infos_from(&mut info_cursor) {
+        match info {
+            Ok(info) => {
+                analysis.update(&info);
+                let is_best_line = info.multipv.is_none_or(|rank| rank == 1);
+                if let Some(EngineScore { score, bound: ScoreBound::Exact }) = info.score
+                    && is_best_line
+                    && let Some(side_to_move) = analysis.side_to_move()
+                {
+                    eval_bar_writer.write(EvaluationUpdate(score.for_white(side_to_move)));
+                }
+                info_writer.write(info);
+            }
+            Err(line) => warn!(response = line, "Failed to parse info from Stockfish"),
+        }
+    }
+    sf_comms.drop_read((*response_cursor).min(*info_cursor));
+
+    'is_waiting: {
+        if let SfState::WaitingUci = *sf_state {
+            for line in sf_comms.iter_responses_from(&mut response_cursor) {
+                if line == "uciok" {
+                    trace!(response = line, "Stockfish");
+                    *sf_state = SfState::Idle;
+                    break 'is_waiting;
+                }
+            }
+            return;
+        }
+        if let SfState::WaitingReady = *sf_state {
+            for line in sf_comms.iter_responses_from(&mut response_cursor) {
+                if line == "readyok" {
+                    trace!(response = line, "Stockfish");
+                    *sf_state = SfState::Idle;
+                    break 'is_waiting;
+                }
+            }
+            return;
+        }
+        if let SfState::WaitingFinishSearch = *sf_state {
+            // Stop a search that is still running once another position is waiting to be searched,
+            // as its result is stale. Other commands wait for the best move, which may still be
+            // played.
+            if *is_searching && stockfish.has_new_search_cmd(search_fen.as_deref()) {
+                *is_searching = false;
+                #[cfg(feature = "debug-inspector")]
+                sf_comms.push_back(SfMessage::Command(SfCommand::Stop));
+                stockfish.write_cmd(SfCommand::Stop);
+            }
+
+            // The game may have been reset, changed, or lost on time while the engine was
+            // searching, and searches on the player's turn are only for analysis
+            let is_opponent_move = !board_state.is_game_over()
+                && opponent.side.color() == Some(board_state.side_to_move())
+                && search_fen.as_ref().is_some_and(|fen| *fen == board_state.fen());
+
+            // The best move is played on the live board, so once it arrives stop browsing past
+            // positions first and handle the response when the live pieces are back
+            if is_opponent_move && viewed_ply.is_some_and(|ply| !ply.is_live()) {
+                let mut peek_cursor = *response_cursor;
+                if sf_comms
+                    .iter_responses_from(&mut peek_cursor)
+                    .any(|line| line.starts_with("bestmove"))
+                {
+                    commands.trigger(ViewPly(usize::MAX));
+                }
+                return;
+            }
+            for line in sf_comms.iter_responses_from(&mut response_cursor) {
+                if line.starts_with("bestmove") {
+                    trace!(response = line, "Stockfish");
+                    *sf_state = SfState::Idle;
+                    *is_searching = false;
+
+                    let mut chunks = line.splitn(3, ' ');
+                    chunks.next();
+                    let bestmove = chunks.next().expect("invalid bestmove response from stockfish");
+
+                    *search_fen = None;
+                    if !is_opponent_move {
+                        debug!(bestmove, "Ignore best move");
+                        break 'is_waiting;
+                    }
+
+                    if !matches!(bestmove.len(), 4 | 5) {
+                        panic!("Unexpected move from stockfish: {bestmove}");
+                    }
+
+                    let from_sq = Square::from_str(&bestmove[0..2]).unwrap_or_else(|_| {
+                        panic!("Invalid source square in move from stockfish: {bestmove}")
+                    });
+                    let to_sq = Square::from_str(&bestmove[2..4]).unwrap_or_else(|_| {
+                        panic!("Invalid destination square in move from stockfish: {bestmove}")
+                    });
+                    let promotion = match bestmove.get(4..) {
+                        Some("q") => Some(PieceType::QUEEN),
+                        Some("r") => Some(PieceType::ROOK),
+                        Some("b") => Some(PieceType::BISHOP),
+                        Some("n") => Some(PieceType::KNIGHT),
+                        _ => None,
+                    };
+
+                    let piece = board_state.piece(from_sq);
+                    commands
+                        .trigger_targets(MovePiece::new(from_sq, to_sq, promotion, true), piece);
+
+                    break 'is_waiting;
+                }
+            }
+            return;
+        }
+    }
+
+    // Stockfish state is `Idle`
+
+    // Nothing is waited for while idle, so skip the responses, e.g. the info of an analysis, to let
+    // them be dropped
+    sf_comms.iter_responses_from(&mut response_cursor).for_each(drop);
+
+    #[allow(unused_mut)]
+    let mut write_cmd = |stockfish: &mut Stockfish, command: SfCommand| {
+        #[cfg(feature = "debug-inspector")]
+        sf_comms.push_back(SfMessage::Command(command.clone()));
+        stockfish.write_cmd(command);
+    };
+
+    while let Some(command) = stockfish.pop_cmd() {
+        trace!(?command, "Stockfish");
+        match command {
+            // Stop the running search before starting another one or changing options
+            SfCommand::SetOption(..) | SfCommand::Position(_) | SfCommand::Go(_)
+                if *is_searching =>
+            {
+                stockfish.unpop_cmd(command);
+                *is_searching = false;
+                *sf_state = SfState::WaitingFinishSearch;
+                write_cmd(&mut stockfish, SfCommand::Stop);
+                return;
+            }
+            SfCommand::Position(ref fen) => {
+                *search_fen = Some(fen.clone());
+                analysis.reset(fen.clone());
+                write_cmd(&mut stockfish, command);
+            }
+            SfCommand::Uci => {
+                *sf_state = SfState::WaitingUci;
+                write_cmd(&mut stockfish, command);
+                return;
+            }
+            SfCommand::IsReady => {
+                *sf_state = SfState::WaitingReady;
+                write_cmd(&mut stockfish, command);
+                return;
+            }
+            SfCommand::Go(ref params) if params.is_bounded() => {
+                *is_searching = true;
+                *sf_state = SfState::WaitingFinishSearch;
+                write_cmd(&mut stockfish, command);
+                return;
+            }
+            SfCommand::Go(_) => {
+                *is_searching = true;
+                write_cmd(&mut stockfish, command);
+            }
+            SfCommand::Stop if *is_searching => {
+                *is_searching = false;
+                *sf_state = SfState::WaitingFinishSearch;
+                write_cmd(&mut stockfish, command);
+                return;
+            }
+            // There is no search to stop, and so no best move to wait for
+            SfCommand::Stop => {}
+            _ => write_cmd(&mut stockfish, command),
+        }
+    }
+}
+
+/// The side Stockfish plays, if any.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OpponentSide {
+    #[default]
+    None,
+    White,
+    Black,
+}
+
+impl OpponentSide {
+    pub fn color(self) -> Option<PieceColor> {
+        match self {
+            Self::None => None,
+            Self::White => Some(PieceColor::WHITE),
+            Self::Black => Some(PieceColor::BLACK),
+        }
+    }
+}
+
+/// When Stockfish stops searching and plays its move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchLimit {
+    /// Search for this many milliseconds.
+    MoveTime(u32),
+    /// Search to this many plies.
+    Depth(u32),
+    /// Search this many nodes.
+    Nodes(u64),
+}
+
+/// The range of the UCI `Skill Level` option.
+pub const SKILL_LEVELS: RangeInclusive<u8> = 0..=20;
+
+/// The range of the UCI `UCI_Elo` option.
+pub const ELO_RANGE: RangeInclusive<u16> = 1350..=2850;
+
+/// How Stockfish plays as the opponent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StockfishOpponent {
+    pub side: OpponentSide,
+    /// The UCI `Skill Level` option, see [`SKILL_LEVELS`].
+    pub skill_level: u8,
+    /// The UCI `UCI_Elo` option, see [`ELO_RANGE`]. The strength is only limited to an Elo
+    /// (i.e. `UCI_LimitStrength` is set) when this is given.
+    pub elo: Option<u16>,
+    pub limit: SearchLimit,
+}
+
+impl Default for StockfishOpponent {
+    fn default() -> Self {
+        Self {
+            side: OpponentSide::None,
+            skill_level: *SKILL_LEVELS.end(),
+            elo: None,
+            limit: SearchLimit::MoveTime(2500),
+        }
+    }
+}
+
+impl StockfishOpponent {
+    /// The UCI options that set the strength of the engine.
+    fn option_commands(&self) -> [SfCommand; 3] {
+        [
+            SfCommand::SetOption("Skill Level", self.skill_level.to_string()),
+            SfCommand::SetOption("UCI_LimitStrength", self.elo.is_some().to_string()),
+            SfCommand::SetOption("UCI_Elo", self.elo.unwrap_or(*ELO_RANGE.start()).to_string()),
+        ]
+    }
+}
+
+fn init_stockfish_opponent_from_cli(
+    cli_args: Option<Res<CliArgs>>,
+    mut opponent: ResMut<StockfishOpponent>,
+) {
+    let Some(cli_args) = cli_args else { return };
+
+    // The arguments override the settings
+    let default = *opponent;
+    let limit = match (cli_args.movetime, cli_args.depth, cli_args.nodes) {
+        (Some(ms), _, _) => SearchLimit::MoveTime(ms),
+        (_, Some(depth), _) => SearchLimit::Depth(depth),
+        (_, _, Some(nodes)) => SearchLimit::Nodes(nodes),
+        _ => default.limit,
+    };
+    *opponent = StockfishOpponent {
+        side: cli_args.opponent.unwrap_or(default.side),
+        skill_level: cli_args.skill.unwrap_or(default.skill_level),
+        elo: cli_args.elo.or(default.elo),
+        limit,
+    };
+}
+
+fn configure_stockfish_opponent(
+    opponent: Res<StockfishOpponent>,
+    mut stockfish: ResMut<Stockfish>,
+) {
+    debug!(?opponent, "Configure Stockfish opponent");
+    stockfish.extend_cmds(opponent.option_commands());
+}
+
+/// Put the side the player plays against Stockfish at the bottom of the board when Stockfish
+/// switches sides. At startup, the side from the settings keeps the orientation from the settings,
+/// while another side, e.g. from the command line arguments, is a switch.
+fn orient_board_to_player(
+    opponent: Res<StockfishOpponent>,
+    settings: Option<Res<Settings>>,
+    mut orientation: ResMut<BoardOrientation>,
+    mut prev_side: Local<Option<OpponentSide>>,
+) {
+    let saved_side = settings.map_or(opponent.side, |settings| settings.engine.side);
+    let prev_side = prev_side.replace(opponent.side).unwrap_or(saved_side);
+    if prev_side == opponent.side {
+        return;
+    }
+    if let Some(color) = opponent.side.color() {
+        orientation.set_if_neq(BoardOrientation(!color));
+    }
+}
+
+fn configure_multipv(
+    mode: Res<AnalysisMode>,
+    board_state: Res<BoardState>,
+    opponent: Res<StockfishOpponent>,
+    mut searched: ResMut<SearchedPosition>,
+    mut stockfish: ResMut<Stockfish>,
+) {
+    debug!(?mode, "Configure analysis");
+    stockfish.push_cmd(SfCommand::SetOption("MultiPV", mode.multipv().to_string()));
+
+    // Search the position again to report the new number of lines, unless Stockfish is choosing
+    // its move
+    if opponent.side.color() != Some(board_state.side_to_move()) {
+        searched.0 = None;
+    }
+}
+
+/// The hash of the last position that was searched, and whether it was analysed.
+#[derive(Default, Resource)]
+struct SearchedPosition(Option<(u64, bool)>);
+
+/// Search the live position whenever it changes: to pick a move when Stockfish plays the side to
+/// move, within the time left on the clocks in timed games, otherwise to analyse it until the next
+/// move if the evaluation bar, the analysis panel or the hint shows the result.
+fn search_position(
+    board_state: Res<BoardState>,
+    opponent: Res<StockfishOpponent>,
+    clock: Option<Res<ChessClock>>,
+    layout: Res<UiLayout>,
+    mode: Res<AnalysisMode>,
+    show_hint: Res<ShowHint>,
+    mut searched: ResMut<SearchedPosition>,
+    mut stockfish: ResMut<Stockfish>,
+) {
+    let is_opponent_move = opponent.side.color() == Some(board_state.side_to_move());
+    let is_analysed = !is_opponent_move && (layout.eval_bar || mode.enabled || **show_hint);
+    let position = (board_state.board().get_hash(), is_analysed);
+    if searched.0 == Some(position) {
+        return;
+    }
+    searched.0 = Some(position);
+
+    if board_state.is_game_over() || !(is_opponent_move || is_analysed) {
+        stockfish.push_cmd(SfCommand::Stop);
+        return;
+    }
+
+    let params = if is_opponent_move {
+        clock
+            .and_then(|clock| clock.go_params(board_state.side_to_move()))
+            .unwrap_or_else(|| opponent.limit.into())
+    } else {
+        GoParams { infinite: true, ..default() }
+    };
+    stockfish.extend_cmds([SfCommand::Position(board_state.fen()), SfCommand::Go(params)]);
+}
+
+/// Stop analysing when leaving the game, while a search for Stockfish's own move keeps running.
+fn stop_analysis_on_exit_game(
+    mut searched: ResMut<SearchedPosition>,
+    mut stockfish: ResMut<Stockfish>,
+) {
+    if searched.0.is_some_and(|(_, is_analysed)| is_analysed) {
+        searched.0 = None;
+        stockfish.push_cmd(SfCommand::Stop);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::game::{consts::DEFAULT_FEN, core::build_app};
+
+    use super::*;
+
+    /// The commands written to Stockfish, shared with the test.
+    #[derive(Clone, Default)]
+    struct CommandLog(Arc<Mutex<Vec<u8>>>);
+
+    impl CommandLog {
+        fn take(&self) -> String {
+            String::from_utf8(std::mem::take(&mut *self.0.lock().unwrap())).unwrap()
+        }
+    }
+
+    impl Write for CommandLog {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn orients_board_when_opponent_switches_sides() {
+        let mut app = App::new();
+        app.insert_resource(BoardOrientation(PieceColor::BLACK))
+            .insert_resource(StockfishOpponent { side: OpponentSide::Black, ..default() })
+            .add_systems(
+                Update,
+                orient_board_to_player.run_if(resource_changed::<StockfishOpponent>),
+            );
+        let orientation = |app: &App| *app.world().resource::<BoardOrientation>();
+
+        // The orientation from the settings is kept at startup
+        app.update();
+        assert_eq!(orientation(&app), BoardOrientation(PieceColor::BLACK));
+
+        app.world_mut().resource_mut::<StockfishOpponent>().side = OpponentSide::White;
+        app.update();
+        assert_eq!(orientation(&app), BoardOrientation(PieceColor::BLACK));
+
+        app.world_mut().resource_mut::<StockfishOpponent>().side = OpponentSide::Black;
+        app.update();
+        assert_eq!(orientation(&app), BoardOrientation(PieceColor::WHITE));
+
+        // Changing the strength keeps the orientation
+        app.insert_resource(BoardOrientation(PieceColor::BLACK));
+        app.world_mut().resource_mut::<StockfishOpponent>().set_changed();
+        app.update();
+        assert_eq!(orientation(&app), BoardOrientation(PieceColor::BLACK));
+
+        // A side other than the saved one at startup, e.g. from `--opponent white`, is a switch
+        let mut app = App::new();
+        app.insert_resource(BoardOrientation(PieceColor::WHITE))
+            .insert_resource(Settings::default())
+            .insert_resource(StockfishOpponent { side: OpponentSide::White, ..default() })
+            .add_systems(
+                Update,
+                orient_board_to_player.run_if(resource_changed::<StockfishOpponent>),
+            );
+        app.update();
+        assert_eq!(orientation(&app), BoardOrientation(PieceColor::BLACK));
+    }
+
+    #[test]
+    fn resumes_responses_after_empty_polls() {
+        let mut sf_comms = SfCommunications::default();
+        let mut cursor = 0;
+        sf_comms.push_back(SfMessage::Response("readyok".to_string()));
+        assert_eq!(sf_comms.iter_responses_from(&mut cursor).collect::<Vec<_>>(), ["readyok"]);
+
+        for _ in 0..3 {
+            assert_eq!(sf_comms.iter_responses_from(&mut cursor).next(), None);
+        }
+        sf_comms.push_back(SfMessage::Response("bestmove e2e4 ponder e7e5".to_string()));
+        assert_eq!(
+            sf_comms.iter_responses_from(&mut cursor).collect::<Vec<_>>(),
+            ["bestmove e2e4 ponder e7e5"]
+        );
+        assert_eq!(cursor, 2);
+    }
+
+    #[test]
+    fn parses_every_info_line_across_frames() {
+        let mut sf_comms = SfCommunications::default();
+        let mut cursor = 0;
+        let mut depths = Vec::new();
+        for frame in 0..4 {
+            for depth in [frame * 2 + 1, frame * 2 + 2] {
+                let line = format!("info depth {depth} score cp 20 pv e2e4");
+                sf_comms.push_back(SfMessage::Response(line));
+            }
+            sf_comms.push_back(SfMessage::Response("info string NNUE enabled".to_string()));
+            for info in sf_comms.iter_infos_from(&mut cursor) {
+                depths.push(info.unwrap().depth.unwrap());
+            }
+            assert_eq!(sf_comms.iter_infos_from(&mut cursor).count(), 0);
+        }
+        assert_eq!(depths, (1..=8).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn writes_go_params() {
+        let infinite = GoParams { infinite: true, ..default() };
+        assert_eq!(infinite.to_string(), "go infinite");
+        assert_eq!(GoParams::from(SearchLimit::Depth(12)).to_string(), "go depth 12");
+        assert!(!infinite.is_bounded());
+        assert!(GoParams::from(SearchLimit::Nodes(1000)).is_bounded());
+
+        let params = GoParams {
+            wtime: Some(60_000),
+            btime: Some(59_000),
+            winc: Some(2000),
+            binc: Some(2000),
+            movestogo: Some(20),
+            searchmoves: vec![
+                ChessMove::new(chess::Square::E2, chess::Square::E4, None),
+                ChessMove::new(chess::Square::D7, chess::Square::D8, Some(chess::Piece::Queen)),
+            ],
+            ..default()
+        };
+        assert_eq!(
+            params.to_string(),
+            "go searchmoves e2e4 d7d8q wtime 60000 btime 59000 winc 2000 binc 2000 movestogo 20"
+        );
+    }
+
+    #[test]
+    fn waits_for_bounded_search_before_setting_options() {
+        let log = CommandLog::default();
+        let (response_tx, response_rx) = unbounded::<String>();
+        let mut app = build_app(|app: &mut App| {
+            app.add_event::<EngineInfo>()
+                .init_resource::<SfCommunications>()
+                .init_resource::<EngineAnalysis>()
+                .insert_resource(StockfishOpponent { side: OpponentSide::White, ..default() });
+        });
+        app.insert_resource(Stockfish::new(log.clone(), response_rx))
+            .add_systems(PostUpdate, stockfish_update);
+        let push_cmds = |app: &mut App, commands: Vec<SfCommand>| {
+            app.world_mut().resource_mut::<Stockfish>().extend_cmds(commands);
+            app.update();
+        };
+
+        let fen = app.world().resource::<BoardState>().fen();
+        let go = GoParams::from(SearchLimit::Depth(12));
+        push_cmds(&mut app, vec![SfCommand::Position(fen.clone()), SfCommand::Go(go)]);
+        assert_eq!(log.take(), format!("position fen {fen}\ngo depth 12\n"));
+
+        // Toggling the analysis panel during the opponent's turn doesn't cut its search short
+        push_cmds(&mut app, vec![SfCommand::SetOption("MultiPV", "3".to_string())]);
+        push_cmds(&mut app, vec![SfCommand::Stop]);
+        assert_eq!(log.take(), "");
+
+        response_tx.send("bestmove e2e4 ponder e7e5".to_string()).unwrap();
+        app.update();
+        app.update();
+        assert_eq!(app.world().resource::<BoardState>().side_to_move(), PieceColor::BLACK);
+        assert_eq!(log.take(), "setoption name MultiPV value 3\n");
+
+        // A search of another position does stop it
+        let fen = app.world().resource::<BoardState>().fen();
+        let go = GoParams::from(SearchLimit::Depth(12));
+        push_cmds(&mut app, vec![SfCommand::Position(fen), SfCommand::Go(go)]);
+        log.take();
+        push_cmds(&mut app, vec![SfCommand::Position(DEFAULT_FEN.to_string())]);
+        assert_eq!(log.take(), "stop\n");
+    }
+
+    #[test]
+    fn drops_responses_once_read() {
+        let mut sf_comms = SfCommunications::default();
+        let (mut response_cursor, mut info_cursor) = (0, 0);
+        for depth in 1..=3 {
+            let line = format!("info depth {depth} score cp 20 pv e2e4");
+            sf_comms.push_back(SfMessage::Response(line));
+        }
+        assert_eq!(sf_comms.iter_infos_from(&mut info_cursor).count(), 3);
+        sf_comms.drop_read(response_cursor.min(info_cursor));
+        assert_eq!(sf_comms.len(), 3);
+
+        sf_comms.push_back(SfMessage::Response("bestmove e2e4".to_string()));
+        assert_eq!(sf_comms.iter_responses_from(&mut response_cursor).count(), 4);
+        sf_comms.drop_read(response_cursor.min(info_cursor));
+        assert_eq!(sf_comms.len(), 1);
+
+        sf_comms.push_back(SfMessage::Response("readyok".to_string()));
+        assert_eq!(sf_comms.iter_infos_from(&mut info_cursor).count(), 0);
+        sf_comms.drop_read(response_cursor.min(info_cursor));
+        assert_eq!(sf_comms.len(), 1);
+        assert_eq!(
+            sf_comms.iter_responses_from(&mut response_cursor).collect::<Vec<_>>(),
+            ["readyok"]
+        );
+        sf_comms.drop_read(response_cursor.min(info_cursor));
+        assert!(sf_comms.is_empty());
+        assert_eq!((response_cursor, info_cursor), (5, 5));
+    }
+}
@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_egui::{
+    EguiContexts,
+    egui::{Align2, Area, Color32, CornerRadius, FontId, Frame, Id, Margin, Order, RichText, vec2},
+};
+
+use crate::{game::consts::COLOR_PANEL, utils::NoopExts};
+
+pub struct ToastPlugin;
+
+impl Plugin for ToastPlugin {
+    fn build(&self, app: &mut App) {
+        app.noop()
+            // Resources
+            .init_resource::<Toast>()
+            // Observers
+            .add_observer(show_toast)
+            // Systems
+            .add_systems(Update, draw_toast)
+            .noop();
+    }
+}
+
+/// How long a toast is shown for.
+const TOAST_DURATION: Duration = Duration::from_secs(2);
+
+/// Show a short message at the bottom of the window, replacing the one being shown.
+#[derive(Clone, Debug, Event)]
+pub struct ShowToast(pub String);
+
+/// The message being shown and how long until it is hidden.
+#[derive(Default, Resource)]
+pub struct Toast(Option<(String, Timer)>);
+
+fn show_toast(trigger: Trigger<ShowToast>, mut toast: ResMut<Toast>) {
+    let ShowToast(text) = trigger.event();
+    toast.0 = Some((text.clone(), Timer::new(TOAST_DURATION, TimerMode::Once)));
+}
+
+fn draw_toast(mut egui_contexts: EguiContexts, time: Res<Time>, mut toast: ResMut<Toast>) {
+    let Some((text, timer)) = &mut toast.0 else { return };
+    if timer.tick(time.delta()).finished() {
+        toast.0 = None;
+        return;
+    }
+
+    let ctx = egui_contexts.ctx_mut();
+    Area::new(Id::new("Toast"))
+        .order(Order::Foreground)
+        .interactable(false)
+        .anchor(Align2::CENTER_BOTTOM, vec2(0.0, -64.0))
+        .show(ctx, |ui| {
+            let [r, g, b, _] = COLOR_PANEL.to_srgba().to_u8_array();
+            Frame::NONE
+                .fill(Color32::from_rgb(r, g, b))
+                .corner_radius(CornerRadius::same(6))
+                .inner_margin(Margin::symmetric(16, 8))
+                .show(ui, |ui| {
+                    ui.label(
+                        RichText::new(text.as_str())
+                            .font(FontId::proportional(18.0))
+                            .color(Color32::WHITE),
+                    );
+                });
+        });
+}
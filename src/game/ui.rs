@@ -6,11 +6,15 @@ use crate::{
 };
 
 use super::{
+    analysis::AnalysisPlugin,
     board::{BoardPlugin, CapturePlugin, PromotionPlugin},
-    consts::{MIN_BOARD_SIZE, UI_GAP_VAL},
+    consts::{BOARD_REFERENCE_SIZE, MOVE_LIST_WIDTH, UI_GAP_VAL},
     eval_bar::EvaluationBarPlugin,
+    hint::HintPlugin,
+    layout::LayoutPlugin,
     menu::GameMenuUiPlugin,
     mouse::MouseUiPlugin,
+    move_list::MoveListPlugin,
     panels::UiPanelsPlugin,
 };
 
@@ -25,7 +29,11 @@ impl Plugin for GameUiPlugin {
             .add_plugins(CapturePlugin)
             .add_plugins(UiPanelsPlugin)
             .add_plugins(EvaluationBarPlugin)
+            .add_plugins(MoveListPlugin)
+            .add_plugins(AnalysisPlugin)
+            .add_plugins(HintPlugin)
             .add_plugins(PromotionPlugin)
+            .add_plugins(LayoutPlugin)
             .add_systems(Startup, spawn_ui)
             .noop();
     }
@@ -34,6 +42,9 @@ impl Plugin for GameUiPlugin {
 #[derive(Component)]
 pub struct Ui;
 
+#[derive(Component)]
+pub struct BoardRow;
+
 #[derive(Component)]
 pub struct EvaluationBarContainer;
 
@@ -43,6 +54,9 @@ pub struct BoardAndPanelsContainer;
 #[derive(Component)]
 pub struct BoardContainer;
 
+#[derive(Component)]
+pub struct MoveListContainer;
+
 pub fn spawn_ui(mut commands: Commands) {
     commands.spawn((
         debug_name!("Ui Wrapper"),
@@ -63,36 +77,62 @@ pub fn spawn_ui(mut commands: Commands) {
                 height: Val::Percent(100.0),
                 padding: UiRect::all(UI_GAP_VAL),
                 flex_direction: FlexDirection::Row,
+                align_items: AlignItems::FlexStart,
                 ..default()
             },
             children![
                 (
-                    EvaluationBarContainer,
-                    debug_name!("Evaluation Bar Container"),
+                    BoardRow,
+                    debug_name!("Board Row"),
+                    Node { flex_direction: FlexDirection::Row, ..default() },
+                    children![
+                        (
+                            EvaluationBarContainer,
+                            debug_name!("Evaluation Bar Container"),
+                            Node {
+                                margin: UiRect::right(UI_GAP_VAL),
+                                display: Display::Flex,
+                                flex_direction: FlexDirection::Column,
+                                row_gap: UI_GAP_VAL,
+                                ..default()
+                            },
+                        ),
+                        (
+                            BoardAndPanelsContainer,
+                            debug_name!("Board and Panels Container"),
+                            Node {
+                                flex_direction: FlexDirection::Column,
+                                row_gap: UI_GAP_VAL,
+                                ..default()
+                            },
+                            children![(
+                                BoardContainer,
+                                debug_name!("Board Container"),
+                                SortIndex(1),
+                                // Sized by the layout
+                                Node {
+                                    width: Val::Px(BOARD_REFERENCE_SIZE),
+                                    height: Val::Px(BOARD_REFERENCE_SIZE),
+                                    flex_shrink: 0.0,
+                                    ..default()
+                                },
+                            )],
+                        ),
+                    ],
+                ),
+                (
+                    MoveListContainer,
+                    debug_name!("Move List Container"),
+                    // Sized by the layout
                     Node {
-                        margin: UiRect::right(UI_GAP_VAL),
+                        margin: UiRect::left(UI_GAP_VAL),
+                        width: Val::Px(MOVE_LIST_WIDTH),
                         display: Display::Flex,
                         flex_direction: FlexDirection::Column,
                         row_gap: UI_GAP_VAL,
                         ..default()
                     },
                 ),
-                (
-                    BoardAndPanelsContainer,
-                    debug_name!("Board and Panels Container"),
-                    Node { flex_direction: FlexDirection::Column, ..default() },
-                    children![(
-                        BoardContainer,
-                        debug_name!("Board Container"),
-                        SortIndex(1),
-                        Node {
-                            flex_grow: 1.0,
-                            min_width: MIN_BOARD_SIZE,
-                            min_height: MIN_BOARD_SIZE,
-                            ..default()
-                        },
-                    )],
-                )
             ],
         )],
     ));
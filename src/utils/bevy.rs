@@ -1,4 +1,8 @@
-use bevy::{ecs, platform::collections::HashSet, prelude::*, state};
+use std::path::PathBuf;
+
+use bevy::{
+    asset::io::file::FileAssetReader, ecs, platform::collections::HashSet, prelude::*, state,
+};
 
 pub trait NoopExts {
     fn noop(&mut self) -> &mut Self {
@@ -125,3 +129,19 @@ pub fn set_state_on<S: state::state::FreelyMutableState, E: 'static>(
     };
     ecs::system::IntoObserverSystem::into_system(system)
 }
+
+/// The path of the assets folder on disk, for assets that are discovered rather than loaded by
+/// name, e.g. themes and sound packs.
+pub fn assets_dir() -> PathBuf {
+    FileAssetReader::new(AssetPlugin::default().file_path).root_path().clone()
+}
+
+/// Whether `Ctrl`, or `Cmd` on macOS, is held for a shortcut.
+pub fn ctrl_pressed(keys: &ButtonInput<KeyCode>) -> bool {
+    keys.any_pressed([
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+        KeyCode::SuperLeft,
+        KeyCode::SuperRight,
+    ])
+}
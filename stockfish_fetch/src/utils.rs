@@ -73,7 +73,7 @@ pub trait CommandExts {
 }
 
 impl CommandExts for Command {
-    fn display(&self) -> CommandDisplay {
+    fn display(&self) -> CommandDisplay<'_> {
         CommandDisplay(self)
     }
 }
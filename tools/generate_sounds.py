@@ -0,0 +1,94 @@
+#!/usr/bin/env python3
+
+"""Generate the sounds of the default pack that aren't recordings, as tones in FLAC files."""
+
+import math
+import struct
+from pathlib import Path
+
+ROOT = Path(__file__).parent.parent
+PACK = ROOT / 'assets' / 'audio' / 'default'
+
+SAMPLE_RATE = 44100
+BLOCK_SIZE = 4096
+AMPLITUDE = 0.3
+
+C4, E4, G4, C5, E5, G5, C6 = 261.63, 329.63, 392.00, 523.25, 659.25, 783.99, 1046.50
+
+# name: [(frequency or None for silence, seconds), ...]
+sounds: dict[str, list[tuple[float | None, float]]] = {
+    'game-start': [(C5, 0.12), (G5, 0.2)],
+    'game-win': [(C5, 0.1), (E5, 0.1), (G5, 0.1), (C6, 0.3)],
+    'game-lose': [(G4, 0.15), (E4, 0.15), (C4, 0.35)],
+    'game-draw': [(E5, 0.15), (None, 0.05), (E5, 0.25)],
+    'illegal': [(150.0, 0.18)],
+    'low-time': [(1000.0, 0.05), (None, 0.1), (1000.0, 0.05), (None, 0.1), (1000.0, 0.05)],
+    'premove': [(600.0, 0.05)],
+}
+
+
+def tone(frequency: float | None, seconds: float) -> list[float]:
+    n = int(SAMPLE_RATE * seconds)
+    if frequency is None:
+        return [0.0] * n
+    fade = min(n // 4, SAMPLE_RATE // 200)
+    samples = []
+    for i in range(n):
+        envelope = min(1.0, i / fade, (n - i) / fade) * math.exp(-3.0 * i / n)
+        samples.append(envelope * math.sin(2.0 * math.pi * frequency * i / SAMPLE_RATE))
+    return samples
+
+
+def crc8(data: bytes) -> int:
+    crc = 0
+    for byte in data:
+        crc ^= byte
+        for _ in range(8):
+            crc = ((crc << 1) ^ 0x07 if crc & 0x80 else crc << 1) & 0xff
+    return crc
+
+
+def crc16(data: bytes) -> int:
+    crc = 0
+    for byte in data:
+        crc ^= byte << 8
+        for _ in range(8):
+            crc = ((crc << 1) ^ 0x8005 if crc & 0x8000 else crc << 1) & 0xffff
+    return crc
+
+
+def utf8_number(n: int) -> bytes:
+    if n < 0x80:
+        return bytes([n])
+    return bytes([0xc0 | (n >> 6), 0x80 | (n & 0x3f)])
+
+
+def flac(samples: list[int]) -> bytes:
+    """Encode 16-bit mono samples as a FLAC stream of uncompressed (verbatim) frames."""
+    # STREAMINFO, with unknown frame sizes and MD5
+    stream_info = struct.pack('>HH', BLOCK_SIZE, BLOCK_SIZE) + bytes(6)
+    stream_info += ((SAMPLE_RATE << 44) | (0 << 41) | (15 << 36) | len(samples)).to_bytes(8, 'big')
+    stream_info += bytes(16)
+    out = b'fLaC' + bytes([0x80]) + len(stream_info).to_bytes(3, 'big') + stream_info
+
+    for number, start in enumerate(range(0, len(samples), BLOCK_SIZE)):
+        block = samples[start:start + BLOCK_SIZE]
+        # Sync code, 16-bit block size at the end of the header, 44.1 kHz, mono, 16 bits per sample
+        header = bytes([0xff, 0xf8, 0x79, 0x08]) + utf8_number(number)
+        header += struct.pack('>H', len(block) - 1)
+        header += bytes([crc8(header)])
+        # A verbatim subframe
+        frame = header + bytes([0x02]) + struct.pack(f'>{len(block)}h', *block)
+        out += frame + struct.pack('>H', crc16(frame))
+    return out
+
+
+def main():
+    for name, notes in sounds.items():
+        samples = [s for frequency, seconds in notes for s in tone(frequency, seconds)]
+        pcm = [round(s * AMPLITUDE * 32767) for s in samples]
+        (PACK / f'{name}.flac').write_bytes(flac(pcm))
+
+
+if __name__ == '__main__':
+    main()